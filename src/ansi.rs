@@ -0,0 +1,251 @@
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// One piece of a line of captured output: plain text, or the parameters of
+/// an SGR (`ESC [ ... m`) sequence. Every other escape sequence is dropped.
+enum Token<'a> {
+    Text(&'a str),
+    Sgr(&'a str),
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(ESC) {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let after = &rest[start + ESC.len_utf8()..];
+        rest = match after.chars().next() {
+            // CSI: parameters and intermediates, then a final byte in @..=~.
+            Some('[') => {
+                let body = &after[1..];
+                match body.find(|c: char| ('@'..='~').contains(&c)) {
+                    Some(end) => {
+                        if body[end..].starts_with('m') {
+                            tokens.push(Token::Sgr(&body[..end]));
+                        }
+                        &body[end + 1..]
+                    }
+                    None => "",
+                }
+            }
+            // OSC (e.g. window titles and hyperlinks): ends at BEL or ESC \.
+            Some(']') => {
+                let body = &after[1..];
+                match body.find([BEL, ESC]) {
+                    Some(end) if body[end..].starts_with(BEL) => &body[end + 1..],
+                    Some(end) => body[end + 1..]
+                        .strip_prefix('\\')
+                        .unwrap_or(&body[end + 1..]),
+                    None => "",
+                }
+            }
+            Some(c) => &after[c.len_utf8()..],
+            None => "",
+        };
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+fn basic_color(n: u16) -> Color {
+    match n {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// Reads the color after a `38` or `48`: `5;n` for the 256-color palette or
+/// `2;r;g;b` for true color.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match params.next()? {
+        5 => Some(Color::Indexed(params.next()?.min(255) as u8)),
+        2 => {
+            let mut channel = || params.next().map(|v| v.min(255) as u8);
+            Some(Color::Rgb(channel()?, channel()?, channel()?))
+        }
+        _ => None,
+    }
+}
+
+fn apply_sgr(style: Style, params: &str) -> Style {
+    let mut style = style;
+    // An empty parameter, including a bare `ESC [ m`, means 0 (reset).
+    let mut codes = params
+        .split([';', ':'])
+        .map(|p| p.parse::<u16>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            7 => style.add_modifier(Modifier::REVERSED),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(basic_color(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(basic_color(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(basic_color(code - 90 + 8)),
+            100..=107 => style.bg(basic_color(code - 100 + 8)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// Renders a line of captured output, turning SGR color and emphasis codes
+/// into styled spans and dropping cursor movement and other sequences that
+/// mean nothing in a scrollback pane.
+pub(crate) fn ansi_to_line(text: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    for token in tokenize(text) {
+        match token {
+            Token::Text(t) => spans.push(Span::styled(t.to_owned(), style)),
+            Token::Sgr(params) => style = apply_sgr(style, params),
+        }
+    }
+    Line::from(spans)
+}
+
+/// The SGR sequence that sets the foreground to `color`, the inverse of
+/// what `ansi_to_line` reads.
+pub(crate) fn foreground_sgr(color: Color) -> String {
+    let code = match color {
+        Color::Reset => "39".to_owned(),
+        Color::Rgb(r, g, b) => format!("38;2;{};{};{}", r, g, b),
+        Color::Indexed(n) => format!("38;5;{}", n),
+        named => {
+            let n = (0..16).find(|n| basic_color(*n) == named).unwrap_or(15);
+            if n < 8 {
+                (30 + n).to_string()
+            } else {
+                (90 + n - 8).to_string()
+            }
+        }
+    };
+    format!("{}[{}m", ESC, code)
+}
+
+/// The text of a line with every escape sequence removed.
+pub(crate) fn strip_ansi(text: &str) -> String {
+    tokenize(text)
+        .into_iter()
+        .filter_map(|token| match token {
+            Token::Text(t) => Some(t),
+            Token::Sgr(_) => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::{
+        style::{Color, Modifier, Style},
+        text::Span,
+    };
+
+    use crate::ansi::{ansi_to_line, foreground_sgr, strip_ansi};
+
+    #[test]
+    fn test_ansi_to_line_styles_sgr_codes() {
+        let line = ansi_to_line("\x1b[1;31mERROR\x1b[0m done \x1b[42;97mok\x1b[m.");
+        assert_eq!(
+            line.spans,
+            vec![
+                Span::styled(
+                    "ERROR",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                ),
+                Span::raw(" done "),
+                Span::styled("ok", Style::default().fg(Color::White).bg(Color::Green)),
+                Span::raw("."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ansi_to_line_extended_colors() {
+        let line = ansi_to_line("\x1b[38;5;208mA\x1b[48;2;10;20;30mB\x1b[39;49;22mC");
+        assert_eq!(
+            line.spans,
+            vec![
+                Span::styled("A", Style::default().fg(Color::Indexed(208))),
+                Span::styled(
+                    "B",
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Rgb(10, 20, 30))
+                ),
+                Span::styled(
+                    "C",
+                    Style::default()
+                        .fg(Color::Reset)
+                        .bg(Color::Reset)
+                        .remove_modifier(Modifier::BOLD | Modifier::DIM)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strips_cursor_movement_and_titles() {
+        let raw = "\x1b[2K\x1b[1Gbuilding\x1b]0;title\x07 \x1b]8;;http://x\x1b\\link\x1b[3A";
+        assert_eq!(strip_ansi(raw), "building link");
+        assert_eq!(ansi_to_line(raw).to_string(), "building link");
+        assert_eq!(strip_ansi("unterminated \x1b[12"), "unterminated ");
+    }
+
+    #[test]
+    fn test_foreground_sgr_round_trips() {
+        for color in [
+            Color::Red,
+            Color::LightCyan,
+            Color::Indexed(208),
+            Color::Rgb(1, 2, 3),
+        ] {
+            let line = ansi_to_line(&format!("{}x", foreground_sgr(color)));
+            assert_eq!(
+                line.spans,
+                vec![Span::styled("x", Style::default().fg(color))]
+            );
+        }
+    }
+}
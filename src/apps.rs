@@ -1,18 +1,58 @@
 use std::{
     error::Error,
+    os::unix::process::ExitStatusExt,
     process::ExitStatus,
     sync::mpsc::Sender,
     thread::{self, JoinHandle},
+    time::Instant,
 };
 
 use sysinfo::Pid;
 
-use crate::tmux::RunningProgram;
+use crate::{
+    config::ProgramSpec,
+    processes::{shell_command_succeeds_before, system_for_process, wait_for_exit},
+    tmux::{RunningProgram, pane_exit_status},
+    usage::ResourceUsage,
+};
 
 pub(crate) enum AppStatus {
     Started,
+    Skipped,
+    /// Running, but its readiness probe hasn't passed yet.
+    Starting(Pid),
     Running(Pid),
-    Dead(Pid),
+    NotReady(Pid),
+    /// Exited, or failed to start and so never had a pid.
+    Dead(Option<Pid>),
+    /// A `oneshot` app that exited 0.
+    Completed(Pid),
+}
+
+impl AppStatus {
+    pub(crate) fn pid(&self) -> Option<Pid> {
+        match self {
+            AppStatus::Starting(pid)
+            | AppStatus::Running(pid)
+            | AppStatus::NotReady(pid)
+            | AppStatus::Completed(pid) => Some(*pid),
+            AppStatus::Dead(pid) => *pid,
+            AppStatus::Started | AppStatus::Skipped => None,
+        }
+    }
+
+    /// A short machine-readable name for the status.
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            AppStatus::Started => "launching",
+            AppStatus::Skipped => "skipped",
+            AppStatus::Starting(_) => "starting",
+            AppStatus::Running(_) => "running",
+            AppStatus::NotReady(_) => "not_ready",
+            AppStatus::Dead(_) => "dead",
+            AppStatus::Completed(_) => "completed",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -20,7 +60,43 @@ pub(crate) enum AppEvent {
     ReceiveErr,
     IgnoredEvent,
     QuitKeyEvent,
-    LogEvent(Vec<u8>),
+    CycleViewMode,
+    ToggleEventHistory,
+    ToggleCommandDetail,
+    /// Empties the log the pane is showing.
+    ClearLog,
+    StartSearch,
+    /// A typed search character, or `None` for backspace.
+    SearchInput(Option<char>),
+    SubmitSearch,
+    CancelSearch,
+    NextSearchMatch,
+    PreviousSearchMatch,
+    ToggleSearchCase,
+    ToggleLogWrap,
+    ScrollLogLeft,
+    ScrollLogRight,
+    ScrollLogUp,
+    ScrollLogDown,
+    ScrollLogPageUp,
+    ScrollLogPageDown,
+    FollowLog,
+    /// Sent about once a second so the uptime clock keeps moving when idle.
+    Tick,
+    SelectNextApp,
+    SelectPreviousApp,
+    RestartSelectedApp,
+    /// Stops every app and launches them all again.
+    RestartAll,
+    KillSelectedApp,
+    AttachSelectedApp,
+    RestartApp(String),
+    /// Log output, tagged with the app it came from when known.
+    LogEvent(Option<String>, Vec<u8>),
+    UsageSampled(Vec<(Pid, ResourceUsage)>),
+    AppReady(String),
+    AppReadyTimeout(String),
+    AppReadyFailed(String, String),
     #[allow(dead_code)]
     ProcessEnded(String, String, Pid, Pid, Option<ExitStatus>),
 }
@@ -29,6 +105,33 @@ pub(crate) trait TryIntoWith<T, C> {
     fn try_into_with(&self, ctx: C) -> Result<T, Box<dyn Error>>;
 }
 
+/// Describes how a process ended, e.g. `exit 1` or `signal 9`.
+pub(crate) fn describe_exit(exit_status: Option<ExitStatus>) -> String {
+    match exit_status {
+        Some(es) => match (es.code(), es.signal()) {
+            (Some(code), _) => format!("exit {}", code),
+            (None, Some(sig)) => format!("signal {}", sig),
+            _ => "unknown exit status".to_owned(),
+        },
+        None => "unknown exit status".to_owned(),
+    }
+}
+
+/// Whether an exit is the expected end of a `oneshot` app rather than a
+/// failure: a one-shot app must exit 0 to count as completed.
+pub(crate) fn completed_successfully(spec: &ProgramSpec, exit_status: Option<ExitStatus>) -> bool {
+    spec.oneshot && exit_status.is_some_and(|es| es.success())
+}
+
+/// Evaluates an app's `launch_if` predicate; apps without one always launch.
+/// `None` means the check was still running at `deadline` and was killed.
+pub(crate) fn should_launch(spec: &ProgramSpec, deadline: Option<Instant>) -> Option<bool> {
+    match spec.launch_if.as_ref() {
+        Some(c) => shell_command_succeeds_before(c, &spec.working_directory, deadline),
+        None => Some(true),
+    }
+}
+
 pub(crate) fn wait_for_term(
     out_chan: &Sender<AppEvent>,
     running_p: &RunningProgram,
@@ -36,25 +139,17 @@ pub(crate) fn wait_for_term(
     let rp = (*running_p).clone();
     let tx = out_chan.clone();
     thread::spawn(move || {
-        let s: sysinfo::System = sysinfo::System::new_all();
-        let p_proc = s.process(rp.program.program_pid);
-        if let Some(_p_pid) = p_proc {
-            let stat = p_proc.unwrap().wait();
-            let _ = tx.send(AppEvent::ProcessEnded(
-                rp.spec.name,
-                rp.program.session_name,
-                rp.program.tmux_pid,
-                rp.program.program_pid,
-                stat,
-            ));
-        } else {
-            let _ = tx.send(AppEvent::ProcessEnded(
-                rp.spec.name,
-                rp.program.session_name,
-                rp.program.tmux_pid,
-                rp.program.program_pid,
-                None,
-            ));
-        }
+        let mut s = system_for_process(&rp.program.program_pid);
+        wait_for_exit(&mut s, &rp.program.program_identity);
+        // The pane's process is tmux's child rather than ours, so its exit
+        // status comes from tmux.
+        let stat = pane_exit_status(&rp.program.server, &rp.program.session_name);
+        let _ = tx.send(AppEvent::ProcessEnded(
+            rp.spec.name,
+            rp.program.session_name,
+            rp.program.tmux_pid,
+            rp.program.program_pid,
+            stat,
+        ));
     })
 }
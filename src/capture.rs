@@ -0,0 +1,162 @@
+use std::{
+    error::Error,
+    fs::{DirBuilder, File, OpenOptions},
+    io::{self, Read},
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+    sync::{
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{RecvTimeoutError, Sender, channel},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use tmux_interface::PipePane;
+
+use crate::{apps::AppEvent, shell::shell_quote, tmux::TmuxServer};
+
+/// How often captured pane output is checked for new data when
+/// `capture_interval_ms` isn't configured.
+pub(crate) const DEFAULT_CAPTURE_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A running capture of one app's pane output.
+pub(crate) struct OutputCapture {
+    stop_channel: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl OutputCapture {
+    /// Stops polling after forwarding any output still in the capture file.
+    pub(crate) fn stop(self) {
+        let _ = self.stop_channel.send(());
+        let _ = self.handle.join();
+    }
+}
+
+/// This run's directory for capture files, created on first use.
+static CAPTURE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Numbers each capture file, so a restarted app never reuses a name.
+static CAPTURE_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory only this run uses, since the temp directory is shared with
+/// other users and other checkouts running the same namespace. Creating it
+/// fails rather than reusing anything already at that path.
+fn capture_dir() -> io::Result<&'static Path> {
+    if let Some(dir) = CAPTURE_DIR.get() {
+        return Ok(dir);
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let dir = std::env::temp_dir().join(format!("devplexer-{}-{}", std::process::id(), nanos));
+    DirBuilder::new().mode(0o700).create(&dir)?;
+    Ok(CAPTURE_DIR.get_or_init(|| dir))
+}
+
+/// Creates a fresh capture file for the session, never following a link
+/// or truncating a file that is already there.
+fn create_capture_file(session_name: &str) -> io::Result<PathBuf> {
+    let n = CAPTURE_COUNT.fetch_add(1, Ordering::Relaxed);
+    let path = capture_dir()?.join(format!("{}-{}.log", session_name, n));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    Ok(path)
+}
+
+/// Removes this run's capture directory once every capture has stopped.
+pub(crate) fn remove_capture_dir() {
+    if let Some(dir) = CAPTURE_DIR.get()
+        && let Err(e) = std::fs::remove_dir(dir)
+    {
+        warn!(
+            "Could not remove capture directory {}: {}",
+            dir.display(),
+            e
+        );
+    }
+}
+
+fn forward_new_output(file: &mut File, app_name: &str, out_chan: &Sender<AppEvent>) {
+    let mut data = Vec::new();
+    if file.read_to_end(&mut data).is_ok() && !data.is_empty() {
+        let _ = out_chan.send(AppEvent::LogEvent(Some(app_name.to_owned()), data));
+    }
+}
+
+/// Pipes the session's pane output into a temporary file and forwards what
+/// is appended to it as `AppEvent::LogEvent`s tagged with the app's name.
+pub(crate) fn start_output_capture(
+    out_chan: &Sender<AppEvent>,
+    server: &TmuxServer,
+    app_name: &str,
+    session_name: &str,
+    interval: Duration,
+) -> Result<OutputCapture, Box<dyn Error>> {
+    let path = create_capture_file(session_name)?;
+    server
+        .tmux(
+            PipePane::new()
+                .open()
+                .target_pane(session_name)
+                .shell_command(format!("cat >> {}", shell_quote(&path.to_string_lossy())))
+                .build(),
+        )
+        .status()?;
+    let mut file = File::open(&path)?;
+    let (stop_channel, die_chan) = channel::<()>();
+    let tx = out_chan.clone();
+    let app_name = app_name.to_owned();
+    let handle = thread::spawn(move || {
+        loop {
+            forward_new_output(&mut file, &app_name, &tx);
+            match die_chan.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        }
+        forward_new_output(&mut file, &app_name, &tx);
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("Could not remove capture file {}: {}", path.display(), e);
+        }
+    });
+    Ok(OutputCapture {
+        stop_channel,
+        handle,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs::OpenOptions, os::unix::fs::PermissionsExt};
+
+    use crate::capture::create_capture_file;
+
+    #[test]
+    fn test_capture_files_are_private_and_fresh() {
+        let first = create_capture_file("ns-web").unwrap();
+        let second = create_capture_file("ns-web").unwrap();
+        assert_ne!(first, second);
+        let dir = first.parent().unwrap().to_path_buf();
+        assert_eq!(Some(dir.as_path()), second.parent());
+        assert_ne!(dir, std::env::temp_dir());
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        assert!(
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&first)
+                .is_err()
+        );
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+        std::fs::remove_dir(dir).unwrap();
+    }
+}
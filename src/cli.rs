@@ -0,0 +1,272 @@
+use std::path::PathBuf;
+
+use log::LevelFilter;
+
+use crate::logging::{LOG_LEVEL_NAMES, parse_log_level};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CliArgumentError {
+    MissingFlagValue(String),
+    UnknownFlag(String),
+    UnexpectedArgument(String),
+    InvalidFlagValue(String, String),
+    /// A flag that only makes sense alongside another one.
+    RequiresFlag(String, String),
+}
+
+impl std::fmt::Display for CliArgumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliArgumentError::MissingFlagValue(flag) => write!(f, "{} needs a value", flag),
+            CliArgumentError::UnknownFlag(flag) => write!(f, "unknown flag {}", flag),
+            CliArgumentError::UnexpectedArgument(arg) => write!(
+                f,
+                "unexpected argument \"{}\", only one configuration file can be given",
+                arg
+            ),
+            CliArgumentError::InvalidFlagValue(flag, value) => {
+                write!(f, "invalid value \"{}\" for {}", value, flag)?;
+                match flag.as_str() {
+                    "--log-level" => write!(f, " (expected {})", LOG_LEVEL_NAMES),
+                    "--status-port" => write!(f, " (expected a port number)"),
+                    _ => Ok(()),
+                }
+            }
+            CliArgumentError::RequiresFlag(flag, required) => {
+                write!(f, "{} can only be used with {}", flag, required)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliArgumentError {}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct CliOptions {
+    pub(crate) config_path: Option<PathBuf>,
+    pub(crate) procfile: Option<PathBuf>,
+    pub(crate) working_directory: Option<PathBuf>,
+    pub(crate) check: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) tmux_windows: bool,
+    pub(crate) headless: bool,
+    pub(crate) no_reclaim: bool,
+    pub(crate) sequential: bool,
+    pub(crate) no_validate_paths: bool,
+    pub(crate) events_file: Option<PathBuf>,
+    pub(crate) status_port: Option<u16>,
+    pub(crate) metrics: bool,
+    pub(crate) log_level: Option<LevelFilter>,
+    pub(crate) profile: Option<String>,
+    pub(crate) version: bool,
+    pub(crate) no_emoji: bool,
+    pub(crate) keep_dead_sessions: bool,
+}
+
+fn flag_value(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, CliArgumentError> {
+    args.next()
+        .ok_or_else(|| CliArgumentError::MissingFlagValue(flag.to_owned()))
+}
+
+/// Parses the command line, not including the program name.
+pub(crate) fn parse_args(
+    args: impl IntoIterator<Item = String>,
+) -> Result<CliOptions, CliArgumentError> {
+    let mut options = CliOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--working-directory" => {
+                options.working_directory = Some(PathBuf::from(flag_value(&arg, &mut args)?));
+            }
+            "--version" | "-V" => {
+                options.version = true;
+            }
+            "--check" => {
+                options.check = true;
+            }
+            "--dry-run" => {
+                options.dry_run = true;
+            }
+            "--headless" => {
+                options.headless = true;
+            }
+            "--no-reclaim" => {
+                options.no_reclaim = true;
+            }
+            "--sequential" => {
+                options.sequential = true;
+            }
+            "--no-validate-paths" => {
+                options.no_validate_paths = true;
+            }
+            "--keep-dead-sessions" => {
+                options.keep_dead_sessions = true;
+            }
+            "--no-emoji" => {
+                options.no_emoji = true;
+            }
+            "--metrics" => {
+                options.metrics = true;
+            }
+            "--tmux-windows" => {
+                options.tmux_windows = true;
+            }
+            "--procfile" => {
+                options.procfile = Some(PathBuf::from(flag_value(&arg, &mut args)?));
+            }
+            "--events-file" => {
+                options.events_file = Some(PathBuf::from(flag_value(&arg, &mut args)?));
+            }
+            "--status-port" => {
+                let value = flag_value(&arg, &mut args)?;
+                options.status_port = Some(
+                    value
+                        .parse()
+                        .map_err(|_e| CliArgumentError::InvalidFlagValue(arg.clone(), value))?,
+                );
+            }
+            "--log-level" => {
+                let value = flag_value(&arg, &mut args)?;
+                options.log_level = match parse_log_level(&value) {
+                    Ok(Some(level)) => Some(level),
+                    _ => return Err(CliArgumentError::InvalidFlagValue(arg, value)),
+                };
+            }
+            "--profile" => {
+                options.profile = Some(flag_value(&arg, &mut args)?);
+            }
+            "--config" => {
+                options.config_path = Some(PathBuf::from(flag_value(&arg, &mut args)?));
+            }
+            f if f.starts_with("--") => {
+                return Err(CliArgumentError::UnknownFlag(arg));
+            }
+            _ => {
+                if options.config_path.is_some() {
+                    return Err(CliArgumentError::UnexpectedArgument(arg));
+                }
+                options.config_path = Some(PathBuf::from(arg));
+            }
+        }
+    }
+    if options.metrics && options.status_port.is_none() {
+        return Err(CliArgumentError::RequiresFlag(
+            "--metrics".to_owned(),
+            "--status-port".to_owned(),
+        ));
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::cli::{CliArgumentError, CliOptions, parse_args};
+
+    fn parse(args: &[&str]) -> Result<CliOptions, CliArgumentError> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_no_args() {
+        assert_eq!(parse(&[]), Ok(CliOptions::default()));
+    }
+
+    #[test]
+    fn test_parse_flag_only() {
+        let options = parse(&["--headless"]).unwrap();
+        assert!(options.headless);
+        assert_eq!(options.config_path, None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert!(parse(&["--version"]).unwrap().version);
+        let short = parse(&["-V"]).unwrap();
+        assert!(short.version);
+        assert_eq!(short.config_path, None);
+    }
+
+    #[test]
+    fn test_parse_path_only() {
+        let options = parse(&["dev/devplexer.yaml"]).unwrap();
+        assert_eq!(
+            options.config_path,
+            Some(PathBuf::from("dev/devplexer.yaml"))
+        );
+    }
+
+    #[test]
+    fn test_parse_flags_and_path() {
+        let options = parse(&[
+            "--profile",
+            "frontend",
+            "devplexer.toml",
+            "--headless",
+            "--status-port",
+            "9000",
+            "--metrics",
+            "--dry-run",
+            "--sequential",
+            "--no-emoji",
+            "--keep-dead-sessions",
+        ])
+        .unwrap();
+        assert_eq!(options.profile.as_deref(), Some("frontend"));
+        assert_eq!(options.config_path, Some(PathBuf::from("devplexer.toml")));
+        assert!(options.headless);
+        assert_eq!(options.status_port, Some(9000));
+        assert!(options.metrics);
+        assert!(options.dry_run);
+        assert!(options.sequential);
+        assert!(options.no_emoji);
+        assert!(options.keep_dead_sessions);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        assert_eq!(
+            parse(&["a.yaml", "b.yaml"]),
+            Err(CliArgumentError::UnexpectedArgument("b.yaml".to_owned()))
+        );
+        assert_eq!(
+            parse(&["--profile"]),
+            Err(CliArgumentError::MissingFlagValue("--profile".to_owned()))
+        );
+        assert_eq!(
+            parse(&["--verbose"]),
+            Err(CliArgumentError::UnknownFlag("--verbose".to_owned()))
+        );
+        assert_eq!(
+            parse(&["--metrics"]),
+            Err(CliArgumentError::RequiresFlag(
+                "--metrics".to_owned(),
+                "--status-port".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_argument_error_messages() {
+        let message = |args: &[&str]| parse(args).unwrap_err().to_string();
+        assert_eq!(
+            message(&["--log-level", "loud"]),
+            "invalid value \"loud\" for --log-level (expected off|error|warn|info|debug|trace)"
+        );
+        assert_eq!(
+            message(&["--status-port", "http"]),
+            "invalid value \"http\" for --status-port (expected a port number)"
+        );
+        assert_eq!(message(&["--profile"]), "--profile needs a value");
+        assert_eq!(message(&["--verbose"]), "unknown flag --verbose");
+        assert_eq!(
+            message(&["--metrics"]),
+            "--metrics can only be used with --status-port"
+        );
+    }
+}
@@ -1,31 +1,213 @@
 use std::{
+    collections::{HashMap, HashSet},
     error::Error,
+    ops::RangeInclusive,
     path::{self, Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
+use ratatui::style::Color;
+use sysinfo::Signal;
 use yaml_rust2::{Yaml, YamlLoader};
 
+use crate::{
+    capture::DEFAULT_CAPTURE_INTERVAL,
+    cli::CliOptions,
+    dotenv::{is_valid_env_name, load_dotenv},
+    interpolate::{InterpolationError, interpolate},
+    keymap::{Action, Key, Keymap},
+    logging::{DEFAULT_LOG_LINES, LogTimestamps},
+    processes::parse_signal,
+    procfile::load_procfile,
+    shell::shell_quote,
+    tmux::sanitize_session_name,
+};
+
+/// The namespace used when the configuration doesn't name one.
+pub(crate) const DEFAULT_NAMESPACE: &str = "devplexer";
+
+/// The terminal tab title used when `tab_title_template` isn't configured.
+pub(crate) const DEFAULT_TAB_TITLE_TEMPLATE: &str = "{name}";
+
+/// How often the input thread wakes to check for key presses when
+/// `poll_interval_ms` isn't configured.
+pub(crate) const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+const POLL_INTERVAL_RANGE_MS: RangeInclusive<u64> = 10..=2000;
+
+/// How often the terminal UI redraws on its own when `tick_interval_ms`
+/// isn't configured.
+pub(crate) const DEFAULT_TICK_INTERVAL: Duration = Duration::from_millis(1000);
+
 pub(crate) struct Configuration {
     pub(crate) namespace: String,
+    /// Apps ordered so each comes after its dependencies.
     pub(crate) apps: Vec<ProgramSpec>,
+    /// App names in the order the configuration file declares them.
+    pub(crate) app_order: Vec<String>,
+    pub(crate) dedup_logs: bool,
+    pub(crate) notify_on_crash: bool,
+    pub(crate) bell_on_crash: bool,
+    pub(crate) log_lines: usize,
+    pub(crate) capture_interval: Duration,
+    pub(crate) poll_interval: Duration,
+    /// How often the terminal UI redraws while nothing else happens.
+    pub(crate) tick_interval: Duration,
+    pub(crate) log_dir: Option<PathBuf>,
+    pub(crate) log_timestamps: LogTimestamps,
+    pub(crate) group_by: Option<GroupBy>,
+    /// Each app's tab title, with `{name}` standing for the app's name.
+    pub(crate) tab_title_template: String,
+    /// The tmux server to run sessions on (`tmux -L`), instead of the default.
+    pub(crate) tmux_socket: Option<String>,
+    /// Named subsets of the apps, selected with `--profile`.
+    pub(crate) profiles: HashMap<String, Vec<String>>,
+    /// Run to completion before any app is launched.
+    pub(crate) before_start: Option<Hook>,
+    /// Run once every app has been stopped.
+    pub(crate) after_stop: Option<Hook>,
+    /// The keys for the terminal UI's rebindable actions.
+    pub(crate) keymap: Keymap,
+    pub(crate) status_style: StatusStyle,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// How the status table shows each app's status.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum StatusStyle {
+    #[default]
+    Emoji,
+    /// Colored symbols, legible on light backgrounds and in any terminal.
+    Symbols,
+}
+
+/// How the status table groups apps into sections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GroupBy {
+    Tag,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub(crate) struct ProgramSpec {
     pub(crate) working_directory: PathBuf,
-    pub(crate) command: String,
+    pub(crate) command: ProgramCommand,
     pub(crate) name: String,
     pub(crate) deps: Vec<String>,
+    pub(crate) env: Vec<(String, String)>,
+    pub(crate) env_file: Option<PathBuf>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) launch_if: Option<String>,
+    /// A `host:port` that must have no listener before the app is launched.
+    pub(crate) wait_port_free: Option<String>,
+    pub(crate) port_free_timeout: Option<Duration>,
+    /// How long its `launch_if` check, port wait and session start may take
+    /// before the app is given up on.
+    pub(crate) start_timeout: Option<Duration>,
+    pub(crate) readiness: Option<ReadinessProbe>,
+    pub(crate) ready_timeout: Option<Duration>,
+    pub(crate) on_ready_timeout: ReadyTimeoutPolicy,
+    pub(crate) restart: RestartPolicy,
+    pub(crate) stop_signal: Option<Signal>,
+    pub(crate) stop_timeout: Option<Duration>,
+    pub(crate) kill_timeout: Option<Duration>,
+    /// Whether the pane stays open after the command exits; see `keeps_pane`.
+    pub(crate) keep_alive: Option<bool>,
+    /// A task expected to run to completion, such as a migration.
+    pub(crate) oneshot: bool,
+    /// Whether the app's tab attaches read-only, so typing can't reach it.
+    pub(crate) attach_readonly: bool,
+    /// The shell the app's command is run with (`<shell> -c`), instead of
+    /// tmux's default shell.
+    pub(crate) shell: Option<String>,
+    /// Tints the app's name in the status table and merged log; unset apps
+    /// get a palette color.
+    pub(crate) color: Option<Color>,
+}
+
+/// An app's command, either a shell string or an argv array whose
+/// elements are quoted individually when handed to the shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ProgramCommand {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl Default for ProgramCommand {
+    fn default() -> Self {
+        ProgramCommand::Shell(String::new())
+    }
+}
+
+impl ProgramSpec {
+    /// Apps keep their dead pane, and with it their exit status, unless
+    /// `keep_alive: false` asks for the session to close on exit.
+    pub(crate) fn keeps_pane(&self) -> bool {
+        self.keep_alive.unwrap_or(true)
+    }
+}
+
+impl ProgramCommand {
+    pub(crate) fn to_shell_string(&self) -> String {
+        match self {
+            ProgramCommand::Shell(command) => command.to_owned(),
+            ProgramCommand::Argv(args) => args
+                .iter()
+                .map(|a| shell_quote(a))
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+}
+
+/// A command run once around the whole run rather than for one app, such
+/// as `before_start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Hook {
+    pub(crate) command: ProgramCommand,
+    pub(crate) working_directory: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ReadinessProbe {
+    Tcp(String),
+    Command(String),
+    /// Ready once the text shows up in the app's pane output.
+    LogPattern(String),
+}
+
+/// What happens to an app's dependents when it doesn't become ready in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) enum ReadyTimeoutPolicy {
+    #[default]
+    Fail,
+    Continue,
+}
+
+/// Whether an app is relaunched automatically after its process exits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
 }
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
+#[allow(dead_code, clippy::enum_variant_names)]
 pub(crate) enum InvalidAppSpecError {
     InvalidNameError(Yaml),
     InvalidSpecStructureError(String, Yaml),
     MissingCommandError(String, Yaml),
     InvalidWorkingDirectoryError(String, Yaml),
+    InvalidSettingError(String, String, Yaml),
+    /// Two keys that can't both be set on one app.
+    ConflictingSettingsError(String, String, String),
+    InvalidEnvironmentError(String, Yaml),
+    EnvFileNotFoundError(String, PathBuf),
+    /// The `env_file` exists but can't be read or parsed.
+    InvalidEnvFileError(String, String),
+    InterpolationError(String, InterpolationError),
+    MissingWorkingDirectoryError(String, PathBuf),
 }
 
 #[derive(Debug, Clone)]
@@ -36,12 +218,121 @@ pub(crate) enum ConfigurationSettingsError {
     InvalidConfigurationFileContentError(String),
     InvalidConfigurationFileStructureError(Yaml),
     InvalidConfigurationNamespaceError(Yaml),
+    InvalidConfigurationSettingError(String, Yaml),
+    SettingOutOfRangeError(String, String),
     InvalidSpecStructuresError(Vec<InvalidAppSpecError>),
+    UnknownDependencyError(String, String),
+    CyclicDependencyError(Vec<String>),
+    DuplicateAppName(Vec<String>),
+    DuplicateIncludedAppError(String, String, String),
+    IncludeCycleError(Vec<String>),
+    UnknownProfileError(String),
+    UnknownProfileAppError(String, String),
+    KeyBindingConflictError(String, String, String),
+}
+
+/// A YAML value written the way it would appear in a config file, on one
+/// line, for error messages.
+fn yaml_for_display(value: &Yaml) -> String {
+    match value {
+        Yaml::String(s) => format!("{:?}", s),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Real(r) => r.clone(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(yaml_for_display)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        Yaml::Hash(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", yaml_for_display(k), yaml_for_display(v)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        Yaml::Null => "nothing".to_owned(),
+        Yaml::Alias(_) | Yaml::BadValue => "an unreadable value".to_owned(),
+    }
 }
 
 impl std::fmt::Display for ConfigurationSettingsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("{:?}", self))
+        match self {
+            ConfigurationSettingsError::ConfigurationFileNotFound(path) => {
+                write!(f, "configuration file {} not found", path)
+            }
+            ConfigurationSettingsError::InvalidConfigurationFilePath(path) => {
+                write!(f, "{} is not a usable configuration file path", path)
+            }
+            ConfigurationSettingsError::InvalidConfigurationFileContentError(reason) => {
+                write!(f, "could not parse the configuration file: {}", reason)
+            }
+            ConfigurationSettingsError::InvalidConfigurationFileStructureError(found) => write!(
+                f,
+                "the configuration must be a mapping with an \"apps\" mapping inside it, found {}",
+                yaml_for_display(found)
+            ),
+            ConfigurationSettingsError::InvalidConfigurationNamespaceError(found) => write!(
+                f,
+                "\"namespace\" must be a string without \".\", \":\" or spaces, found {}",
+                yaml_for_display(found)
+            ),
+            ConfigurationSettingsError::InvalidConfigurationSettingError(key, found) => write!(
+                f,
+                "invalid value for \"{}\": {}",
+                key,
+                yaml_for_display(found)
+            ),
+            ConfigurationSettingsError::SettingOutOfRangeError(key, reason) => {
+                write!(f, "\"{}\" is out of range: {}", key, reason)
+            }
+            ConfigurationSettingsError::InvalidSpecStructuresError(fails) => {
+                write!(f, "invalid app settings:")?;
+                for fail in fails.iter() {
+                    write!(f, "\n  {}", fail)?;
+                }
+                Ok(())
+            }
+            ConfigurationSettingsError::UnknownDependencyError(app, dep) => {
+                write!(f, "app \"{}\" depends on unknown app \"{}\"", app, dep)
+            }
+            ConfigurationSettingsError::CyclicDependencyError(apps) => write!(
+                f,
+                "the deps of these apps form a cycle, so none of them can start first: {}",
+                apps.join(", ")
+            ),
+            ConfigurationSettingsError::DuplicateAppName(apps) => write!(
+                f,
+                "these app names would share a tmux session name, rename all but one: {}",
+                apps.join(", ")
+            ),
+            ConfigurationSettingsError::DuplicateIncludedAppError(app, first, second) => write!(
+                f,
+                "app \"{}\" is defined in both {} and {}",
+                app, first, second
+            ),
+            ConfigurationSettingsError::IncludeCycleError(files) => write!(
+                f,
+                "these configuration files include each other in a cycle: {}",
+                files.join(" -> ")
+            ),
+            ConfigurationSettingsError::UnknownProfileError(profile) => {
+                write!(f, "profile \"{}\" is not defined in \"profiles\"", profile)
+            }
+            ConfigurationSettingsError::UnknownProfileAppError(profile, app) => {
+                write!(f, "profile \"{}\" lists unknown app \"{}\"", profile, app)
+            }
+            ConfigurationSettingsError::KeyBindingConflictError(key, first, second) => write!(
+                f,
+                "key \"{}\" is bound to both \"{}\" and \"{}\" in \"keybindings\"",
+                key, first, second
+            ),
+        }
     }
 }
 
@@ -49,14 +340,176 @@ impl std::error::Error for InvalidAppSpecError {}
 
 impl std::fmt::Display for InvalidAppSpecError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("{:?}", self))
+        match self {
+            InvalidAppSpecError::InvalidNameError(found) => {
+                write!(f, "app name {} must be a string", yaml_for_display(found))
+            }
+            InvalidAppSpecError::InvalidSpecStructureError(app, found) => write!(
+                f,
+                "app \"{}\" must be a mapping of settings, found {}",
+                app,
+                yaml_for_display(found)
+            ),
+            InvalidAppSpecError::MissingCommandError(app, _) => {
+                write!(f, "app \"{}\" is missing required key \"command\"", app)
+            }
+            InvalidAppSpecError::InvalidWorkingDirectoryError(app, found) => write!(
+                f,
+                "app \"{}\" has an invalid \"working_directory\": {}",
+                app,
+                yaml_for_display(found)
+            ),
+            InvalidAppSpecError::InvalidSettingError(app, key, found) => write!(
+                f,
+                "app \"{}\" has an invalid value for \"{}\": {}",
+                app,
+                key,
+                yaml_for_display(found)
+            ),
+            InvalidAppSpecError::ConflictingSettingsError(app, first, second) => write!(
+                f,
+                "app \"{}\" sets both \"{}\" and \"{}\", which can't be used together",
+                app, first, second
+            ),
+            InvalidAppSpecError::InvalidEnvironmentError(app, found) => write!(
+                f,
+                "app \"{}\" has an invalid \"environment\", expected a mapping of variable names to scalar values: {}",
+                app,
+                yaml_for_display(found)
+            ),
+            InvalidAppSpecError::InvalidEnvFileError(app, reason) => {
+                write!(f, "app \"{}\" has an invalid \"env_file\": {}", app, reason)
+            }
+            InvalidAppSpecError::EnvFileNotFoundError(app, path) => write!(
+                f,
+                "app \"{}\" has an \"env_file\" that does not exist: {}",
+                app,
+                path.display()
+            ),
+            InvalidAppSpecError::InterpolationError(app, e) => write!(f, "app \"{}\": {}", app, e),
+            InvalidAppSpecError::MissingWorkingDirectoryError(app, path) => write!(
+                f,
+                "app \"{}\" has a \"working_directory\" that does not exist: {}",
+                app,
+                path.display()
+            ),
+        }
     }
 }
 
 impl std::error::Error for ConfigurationSettingsError {}
 
+/// Parses a readiness probe, either a `tcp:`/`command:` mapping or a string
+/// shorthand where `tcp:host:port` is a TCP probe and anything else a command.
+fn readiness_from_yaml(name: &str, content: &Yaml) -> Result<ReadinessProbe, InvalidAppSpecError> {
+    let invalid = || {
+        InvalidAppSpecError::InvalidSettingError(
+            name.to_owned(),
+            "readiness".to_owned(),
+            content.clone(),
+        )
+    };
+    if let Some(probe) = content.as_str() {
+        return Ok(match probe.strip_prefix("tcp:") {
+            Some(address) => ReadinessProbe::Tcp(address.to_owned()),
+            None => ReadinessProbe::Command(probe.to_owned()),
+        });
+    }
+    let h = content.as_hash().ok_or_else(invalid)?;
+    let tcp_key = Yaml::String("tcp".to_owned());
+    let command_key = Yaml::String("command".to_owned());
+    if let Some(tcp) = h.get(&tcp_key) {
+        let address = tcp.as_str().ok_or_else(invalid)?;
+        return Ok(ReadinessProbe::Tcp(address.to_owned()));
+    }
+    if let Some(command) = h.get(&command_key) {
+        let command_str = command.as_str().ok_or_else(invalid)?;
+        return Ok(ReadinessProbe::Command(command_str.to_owned()));
+    }
+    Err(invalid())
+}
+
+fn yaml_scalar_to_string(value: &Yaml) -> Option<String> {
+    match value {
+        Yaml::String(s) => Some(s.to_owned()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Real(r) => Some(r.to_owned()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn command_from_yaml(name: &str, content: &Yaml) -> Result<ProgramCommand, InvalidAppSpecError> {
+    let invalid = || InvalidAppSpecError::MissingCommandError(name.to_owned(), content.clone());
+    if let Some(command) = content.as_str() {
+        return Ok(ProgramCommand::Shell(command.to_owned()));
+    }
+    let args = content
+        .as_vec()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(yaml_scalar_to_string)
+        .collect::<Option<Vec<String>>>()
+        .filter(|args| !args.is_empty())
+        .ok_or_else(invalid)?;
+    Ok(ProgramCommand::Argv(args))
+}
+
+/// Reads a top-level hook command, a shell string or an argv array like an
+/// app's `command`, run from `base_dir`.
+fn hook_from_yaml(
+    key: &str,
+    base_dir: &Path,
+    content: &Yaml,
+) -> Result<Hook, ConfigurationSettingsError> {
+    let command = command_from_yaml(key, content).map_err(|_e| {
+        ConfigurationSettingsError::InvalidConfigurationSettingError(
+            key.to_owned(),
+            content.clone(),
+        )
+    })?;
+    Ok(Hook {
+        command,
+        working_directory: base_dir.to_path_buf(),
+    })
+}
+
+fn env_from_yaml(name: &str, content: &Yaml) -> Result<Vec<(String, String)>, InvalidAppSpecError> {
+    let h = content.as_hash().ok_or_else(|| {
+        InvalidAppSpecError::InvalidEnvironmentError(name.to_owned(), content.clone())
+    })?;
+    let mut env = Vec::new();
+    for (k, v) in h.iter() {
+        let key = k.as_str().filter(|k| is_valid_env_name(k)).ok_or_else(|| {
+            InvalidAppSpecError::InvalidEnvironmentError(name.to_owned(), k.clone())
+        })?;
+        let value = yaml_scalar_to_string(v).ok_or_else(|| {
+            InvalidAppSpecError::InvalidEnvironmentError(name.to_owned(), v.clone())
+        })?;
+        env.push((key.to_owned(), value));
+    }
+    Ok(env)
+}
+
+/// Expands `${NAME}` against the config's `variables`, falling back to the
+/// process environment.
+fn interpolate_setting(
+    name: &str,
+    variables: &HashMap<String, String>,
+    value: &str,
+) -> Result<String, InvalidAppSpecError> {
+    interpolate(value, |var| {
+        variables
+            .get(var)
+            .cloned()
+            .or_else(|| std::env::var(var).ok())
+    })
+    .map_err(|e| InvalidAppSpecError::InterpolationError(name.to_owned(), e))
+}
+
 fn spec_from_hash(
     base_dir: &Path,
+    variables: &HashMap<String, String>,
     name: &Yaml,
     content: &Yaml,
 ) -> Result<ProgramSpec, InvalidAppSpecError> {
@@ -76,20 +529,24 @@ fn spec_from_hash(
     let command = h.get(&command_key);
     let command_yaml = command
         .ok_or_else(|| InvalidAppSpecError::MissingCommandError(n.to_owned(), content.clone()))?;
-    let command_str = command_yaml.as_str().ok_or_else(|| {
-        InvalidAppSpecError::MissingCommandError(n.to_owned(), command_yaml.clone())
-    })?;
+    let command = match command_from_yaml(n, command_yaml)? {
+        ProgramCommand::Shell(command) => {
+            ProgramCommand::Shell(interpolate_setting(n, variables, &command)?)
+        }
+        ProgramCommand::Argv(args) => ProgramCommand::Argv(
+            args.iter()
+                .map(|a| interpolate_setting(n, variables, a))
+                .collect::<Result<Vec<String>, InvalidAppSpecError>>()?,
+        ),
+    };
 
     let path_yaml = h.get(&wd_key);
     let mut path_value = base_dir.to_path_buf();
-    if path_yaml.is_some() {
-        let p_yaml = path_yaml.unwrap();
+    if let Some(p_yaml) = path_yaml {
         let pys = p_yaml.as_str().ok_or_else(|| {
             InvalidAppSpecError::InvalidWorkingDirectoryError(n.to_owned(), p_yaml.clone())
         })?;
-        let p: PathBuf = pys.try_into().map_err(|_p| {
-            InvalidAppSpecError::InvalidWorkingDirectoryError(n.to_owned(), p_yaml.clone())
-        })?;
+        let p = PathBuf::from(interpolate_setting(n, variables, pys)?);
         if p.is_absolute() {
             path_value = p;
         } else {
@@ -98,133 +555,1181 @@ fn spec_from_hash(
             })?;
         }
     }
+
+    let deps_key = Yaml::String("deps".to_owned());
+    let mut deps = Vec::new();
+    if let Some(deps_yaml) = h.get(&deps_key) {
+        let invalid_deps = || {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "deps".to_owned(),
+                deps_yaml.clone(),
+            )
+        };
+        for dep in deps_yaml.as_vec().ok_or_else(invalid_deps)? {
+            deps.push(dep.as_str().ok_or_else(invalid_deps)?.to_owned());
+        }
+    }
+
+    let env_key = Yaml::String("environment".to_owned());
+    let mut env = match h.get(&env_key) {
+        Some(env_yaml) => env_from_yaml(n, env_yaml)?,
+        None => vec![],
+    };
+    for (_k, v) in env.iter_mut() {
+        *v = interpolate_setting(n, variables, v)?;
+    }
+
+    let env_file_key = Yaml::String("env_file".to_owned());
+    let mut env_file = None;
+    if let Some(ef_yaml) = h.get(&env_file_key) {
+        let ef_str = ef_yaml.as_str().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "env_file".to_owned(),
+                ef_yaml.clone(),
+            )
+        })?;
+        let ef_path = path_value.join(ef_str);
+        if !ef_path.is_file() {
+            return Err(InvalidAppSpecError::EnvFileNotFoundError(
+                n.to_owned(),
+                ef_path,
+            ));
+        }
+        // It is read again at launch, but a typo in it is better caught here.
+        load_dotenv(&ef_path)
+            .map_err(|e| InvalidAppSpecError::InvalidEnvFileError(n.to_owned(), e.to_string()))?;
+        env_file = Some(ef_path);
+    }
+
+    let tags_key = Yaml::String("tags".to_owned());
+    let mut tags = Vec::new();
+    if let Some(tags_yaml) = h.get(&tags_key) {
+        let invalid_tags = || {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "tags".to_owned(),
+                tags_yaml.clone(),
+            )
+        };
+        for tag in tags_yaml.as_vec().ok_or_else(invalid_tags)? {
+            tags.push(tag.as_str().ok_or_else(invalid_tags)?.to_owned());
+        }
+    }
+
+    let launch_if_key = Yaml::String("launch_if".to_owned());
+    let mut launch_if = None;
+    if let Some(li_yaml) = h.get(&launch_if_key) {
+        let li_str = li_yaml.as_str().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "launch_if".to_owned(),
+                li_yaml.clone(),
+            )
+        })?;
+        launch_if = Some(li_str.to_owned());
+    }
+
+    let wait_port_free_key = Yaml::String("wait_port_free".to_owned());
+    let mut wait_port_free = None;
+    if let Some(wpf_yaml) = h.get(&wait_port_free_key) {
+        let wpf_str = wpf_yaml.as_str().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "wait_port_free".to_owned(),
+                wpf_yaml.clone(),
+            )
+        })?;
+        wait_port_free = Some(wpf_str.to_owned());
+    }
+    let port_free_timeout = millis_setting(n, h, "wait_port_free_timeout_ms")?;
+    let start_timeout = millis_setting(n, h, "start_timeout_ms")?;
+
+    let readiness_key = Yaml::String("readiness".to_owned());
+    let health_check_key = Yaml::String("health_check".to_owned());
+    let readiness = match h.get(&readiness_key).or_else(|| h.get(&health_check_key)) {
+        Some(r_yaml) => Some(readiness_from_yaml(n, r_yaml)?),
+        None => None,
+    };
+    let log_pattern_key = Yaml::String("ready_log_pattern".to_owned());
+    let readiness = match (readiness, h.get(&log_pattern_key)) {
+        (readiness, None) => readiness,
+        (None, Some(lp_yaml)) => {
+            let pattern = lp_yaml.as_str().filter(|p| !p.is_empty()).ok_or_else(|| {
+                InvalidAppSpecError::InvalidSettingError(
+                    n.to_owned(),
+                    "ready_log_pattern".to_owned(),
+                    lp_yaml.clone(),
+                )
+            })?;
+            Some(ReadinessProbe::LogPattern(pattern.to_owned()))
+        }
+        // Only one probe decides readiness.
+        (Some(_), Some(_)) => {
+            let probe_key = if h.contains_key(&readiness_key) {
+                "readiness"
+            } else {
+                "health_check"
+            };
+            return Err(InvalidAppSpecError::ConflictingSettingsError(
+                n.to_owned(),
+                probe_key.to_owned(),
+                "ready_log_pattern".to_owned(),
+            ));
+        }
+    };
+
+    let ready_timeout = millis_setting(n, h, "ready_timeout_ms")?;
+
+    let on_ready_timeout_key = Yaml::String("on_ready_timeout".to_owned());
+    let mut on_ready_timeout = ReadyTimeoutPolicy::default();
+    if let Some(ort_yaml) = h.get(&on_ready_timeout_key) {
+        on_ready_timeout = match ort_yaml.as_str() {
+            Some("fail") => ReadyTimeoutPolicy::Fail,
+            Some("continue") => ReadyTimeoutPolicy::Continue,
+            _ => {
+                return Err(InvalidAppSpecError::InvalidSettingError(
+                    n.to_owned(),
+                    "on_ready_timeout".to_owned(),
+                    ort_yaml.clone(),
+                ));
+            }
+        };
+    }
+
+    let restart_key = Yaml::String("restart".to_owned());
+    let mut restart = RestartPolicy::default();
+    if let Some(restart_yaml) = h.get(&restart_key) {
+        restart = match restart_yaml.as_str() {
+            Some("never") => RestartPolicy::Never,
+            Some("on-failure") => RestartPolicy::OnFailure,
+            Some("always") => RestartPolicy::Always,
+            _ => {
+                return Err(InvalidAppSpecError::InvalidSettingError(
+                    n.to_owned(),
+                    "restart".to_owned(),
+                    restart_yaml.clone(),
+                ));
+            }
+        };
+    }
+
+    let stop_signal_key = Yaml::String("stop_signal".to_owned());
+    let mut stop_signal = None;
+    if let Some(ss_yaml) = h.get(&stop_signal_key) {
+        stop_signal = Some(ss_yaml.as_str().and_then(parse_signal).ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "stop_signal".to_owned(),
+                ss_yaml.clone(),
+            )
+        })?);
+    }
+    let stop_timeout = millis_setting(n, h, "stop_timeout_ms")?;
+    let kill_timeout = millis_setting(n, h, "kill_timeout_ms")?;
+    let keep_alive_key = Yaml::String("keep_alive".to_owned());
+    let mut keep_alive = None;
+    if let Some(ka_yaml) = h.get(&keep_alive_key) {
+        keep_alive = Some(ka_yaml.as_bool().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "keep_alive".to_owned(),
+                ka_yaml.clone(),
+            )
+        })?);
+    }
+    let oneshot_key = Yaml::String("oneshot".to_owned());
+    let mut oneshot = false;
+    if let Some(os_yaml) = h.get(&oneshot_key) {
+        oneshot = os_yaml.as_bool().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "oneshot".to_owned(),
+                os_yaml.clone(),
+            )
+        })?;
+    }
+    let attach_readonly_key = Yaml::String("attach_readonly".to_owned());
+    let mut attach_readonly = false;
+    if let Some(ar_yaml) = h.get(&attach_readonly_key) {
+        attach_readonly = ar_yaml.as_bool().ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "attach_readonly".to_owned(),
+                ar_yaml.clone(),
+            )
+        })?;
+    }
+    let shell = match h.get(&Yaml::String("shell".to_owned())) {
+        Some(shell_yaml) => Some(shell_setting(shell_yaml).ok_or_else(|| {
+            InvalidAppSpecError::InvalidSettingError(
+                n.to_owned(),
+                "shell".to_owned(),
+                shell_yaml.clone(),
+            )
+        })?),
+        None => None,
+    };
+    let color_key = Yaml::String("color".to_owned());
+    let mut color = None;
+    if let Some(color_yaml) = h.get(&color_key) {
+        color = Some(
+            color_yaml
+                .as_str()
+                .and_then(|c| Color::from_str(c).ok())
+                .ok_or_else(|| {
+                    InvalidAppSpecError::InvalidSettingError(
+                        n.to_owned(),
+                        "color".to_owned(),
+                        color_yaml.clone(),
+                    )
+                })?,
+        );
+    }
+
     Ok(ProgramSpec {
         name: n.to_owned(),
-        command: command_str.to_owned(),
+        command,
         working_directory: path_value.clone(),
-        deps: vec![],
+        deps,
+        env,
+        env_file,
+        tags,
+        launch_if,
+        wait_port_free,
+        port_free_timeout,
+        start_timeout,
+        readiness,
+        ready_timeout,
+        on_ready_timeout,
+        restart,
+        stop_signal,
+        stop_timeout,
+        kill_timeout,
+        keep_alive,
+        oneshot,
+        attach_readonly,
+        shell,
+        color,
     })
 }
 
-fn string_to_config(
-    base_dir: &Path,
-    config_contents: &str,
-) -> Result<Configuration, Box<dyn Error>> {
-    let yaml_str = YamlLoader::load_from_str(&config_contents);
-    if yaml_str.is_err() {
-        return Err(Box::new(
-            ConfigurationSettingsError::InvalidConfigurationFileContentError(
-                config_contents.to_owned(),
-            ),
-        ));
-    }
-    let yaml = yaml_str.unwrap();
-    let mut oks = Vec::new();
-    let mut fails = Vec::new();
-    let apps = Yaml::String("apps".to_owned());
-    let ns_key = Yaml::String("namespace".to_owned());
-    let mut namespace = "devplexer".to_owned();
-    for y in yaml.iter() {
-        let full_config = y.as_hash().ok_or_else(|| {
-            ConfigurationSettingsError::InvalidConfigurationFileStructureError(y.clone())
-        })?;
-        let ns_val = full_config.get(&ns_key);
-        if ns_val.is_some() {
-            namespace = ns_val
-                .unwrap()
-                .as_str()
+/// A `shell` setting: a non-empty program name or path.
+fn shell_setting(shell_yaml: &Yaml) -> Option<String> {
+    shell_yaml
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .map(str::to_owned)
+}
+
+/// Reads an optional non-negative millisecond count from an app's settings.
+fn millis_setting(
+    n: &str,
+    h: &yaml_rust2::yaml::Hash,
+    key: &str,
+) -> Result<Option<Duration>, InvalidAppSpecError> {
+    match h.get(&Yaml::String(key.to_owned())) {
+        Some(ms_yaml) => {
+            let ms = ms_yaml
+                .as_i64()
+                .and_then(|ms| u64::try_from(ms).ok())
                 .ok_or_else(|| {
-                    ConfigurationSettingsError::InvalidConfigurationNamespaceError(
-                        ns_val.unwrap().clone(),
+                    InvalidAppSpecError::InvalidSettingError(
+                        n.to_owned(),
+                        key.to_owned(),
+                        ms_yaml.clone(),
                     )
-                })?
-                .to_owned();
+                })?;
+            Ok(Some(Duration::from_millis(ms)))
         }
-        let app_section = full_config.get(&apps).ok_or_else(|| {
-            ConfigurationSettingsError::InvalidConfigurationFileStructureError(y.clone())
-        })?;
-        let spec_hash = app_section.as_hash().ok_or_else(|| {
-            ConfigurationSettingsError::InvalidConfigurationFileStructureError(app_section.clone())
-        })?;
-        for (k, v) in spec_hash.iter() {
-            let newspec = spec_from_hash(base_dir, k, v);
-            if newspec.is_ok() {
-                oks.push(newspec.unwrap());
-            } else {
-                fails.push(newspec.unwrap_err());
-            }
+        None => Ok(None),
+    }
+}
+
+/// Rejects apps whose names would share a tmux session once sanitized,
+/// listing every name involved.
+fn check_duplicate_names(apps: &[ProgramSpec]) -> Result<(), ConfigurationSettingsError> {
+    let mut by_session: HashMap<String, Vec<&str>> = HashMap::new();
+    for spec in apps.iter() {
+        by_session
+            .entry(sanitize_session_name(&spec.name))
+            .or_default()
+            .push(&spec.name);
+    }
+    let mut duplicates: Vec<String> = by_session
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .flatten()
+        .map(str::to_owned)
+        .collect();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    duplicates.sort();
+    Err(ConfigurationSettingsError::DuplicateAppName(duplicates))
+}
+
+/// Rejects an app name defined in more than one configuration file, naming
+/// both files.
+fn check_included_names(
+    apps: &[ProgramSpec],
+    sources: &[String],
+) -> Result<(), ConfigurationSettingsError> {
+    let mut seen: HashMap<&str, &str> = HashMap::new();
+    for (spec, source) in apps.iter().zip(sources.iter()) {
+        if let Some(first) = seen.insert(&spec.name, source)
+            && first != source
+        {
+            return Err(ConfigurationSettingsError::DuplicateIncludedAppError(
+                spec.name.clone(),
+                first.to_owned(),
+                source.clone(),
+            ));
         }
     }
-    if fails.len() > 0 {
-        return Err(Box::new(
-            ConfigurationSettingsError::InvalidSpecStructuresError(fails),
-        ));
+    Ok(())
+}
+
+fn check_profile_apps(
+    profiles: &HashMap<String, Vec<String>>,
+    apps: &[ProgramSpec],
+) -> Result<(), ConfigurationSettingsError> {
+    for (profile, members) in profiles.iter() {
+        if let Some(missing) = members.iter().find(|m| !apps.iter().any(|a| &a.name == *m)) {
+            return Err(ConfigurationSettingsError::UnknownProfileAppError(
+                profile.clone(),
+                missing.clone(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The title of an app's terminal tab: `template` with each `{name}`
+/// replaced by the app's name.
+pub(crate) fn tab_title(template: &str, app_name: &str) -> String {
+    template.replace("{name}", app_name)
+}
+
+/// Narrows the configuration to the apps in `profile` and everything they
+/// depend on, directly or not.
+pub(crate) fn select_profile(
+    config: Configuration,
+    profile: &str,
+) -> Result<Configuration, ConfigurationSettingsError> {
+    let members = config
+        .profiles
+        .get(profile)
+        .ok_or_else(|| ConfigurationSettingsError::UnknownProfileError(profile.to_owned()))?;
+    let mut selected: HashSet<String> = HashSet::new();
+    let mut pending: Vec<&String> = members.iter().collect();
+    while let Some(name) = pending.pop() {
+        if selected.insert(name.clone())
+            && let Some(spec) = config.apps.iter().find(|a| &a.name == name)
+        {
+            pending.extend(spec.deps.iter());
+        }
     }
     Ok(Configuration {
-        namespace: namespace,
-        apps: oks,
+        apps: config
+            .apps
+            .into_iter()
+            .filter(|a| selected.contains(&a.name))
+            .collect(),
+        app_order: config
+            .app_order
+            .into_iter()
+            .filter(|a| selected.contains(a))
+            .collect(),
+        ..config
     })
 }
 
-fn load_config(file_path: &Path) -> Result<Configuration, Box<dyn Error>> {
-    let p_dir = file_path.parent().unwrap();
-    let file_content = std::fs::read_to_string(file_path)?;
-    string_to_config(p_dir, &file_content)
+/// Orders apps so every app comes after its `deps`, keeping declaration
+/// order wherever the dependencies allow it.
+fn order_by_dependencies(
+    apps: Vec<ProgramSpec>,
+) -> Result<Vec<ProgramSpec>, ConfigurationSettingsError> {
+    for spec in apps.iter() {
+        for dep in spec.deps.iter() {
+            if !apps.iter().any(|a| &a.name == dep) {
+                return Err(ConfigurationSettingsError::UnknownDependencyError(
+                    spec.name.clone(),
+                    dep.clone(),
+                ));
+            }
+        }
+    }
+    let mut remaining = apps;
+    let mut ordered: Vec<ProgramSpec> = Vec::new();
+    while !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .position(|spec| {
+                spec.deps
+                    .iter()
+                    .all(|d| ordered.iter().any(|o| &o.name == d))
+            })
+            .ok_or_else(|| {
+                ConfigurationSettingsError::CyclicDependencyError(
+                    remaining.iter().map(|s| s.name.clone()).collect(),
+                )
+            })?;
+        ordered.push(remaining.remove(next));
+    }
+    Ok(ordered)
 }
 
-fn resolve_config_path(
-    current_dir: &Path,
-    args: &mut std::env::Args,
-) -> Result<PathBuf, Box<dyn Error>> {
-    if args.len() < 2 {
-        Ok(current_dir.join("devplexer.yaml"))
+#[cfg(test)]
+pub(crate) fn string_to_config(
+    base_dir: &Path,
+    config_contents: &str,
+) -> Result<Configuration, Box<dyn Error>> {
+    documents_to_config(
+        base_dir,
+        &parse_yaml_documents(config_contents)?,
+        &IncludeContext::default(),
+    )
+}
+
+fn parse_yaml_documents(config_contents: &str) -> Result<Vec<Yaml>, ConfigurationSettingsError> {
+    YamlLoader::load_from_str(config_contents).map_err(|e| {
+        ConfigurationSettingsError::InvalidConfigurationFileContentError(e.to_string())
+    })
+}
+
+fn toml_to_yaml(value: toml::Value) -> Yaml {
+    match value {
+        toml::Value::String(s) => Yaml::String(s),
+        toml::Value::Integer(i) => Yaml::Integer(i),
+        toml::Value::Float(f) => Yaml::Real(f.to_string()),
+        toml::Value::Boolean(b) => Yaml::Boolean(b),
+        toml::Value::Datetime(d) => Yaml::String(d.to_string()),
+        toml::Value::Array(a) => Yaml::Array(a.into_iter().map(toml_to_yaml).collect()),
+        toml::Value::Table(t) => Yaml::Hash(
+            t.into_iter()
+                .map(|(k, v)| (Yaml::String(k), toml_to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses a TOML configuration by mapping it onto the YAML document model,
+/// so both formats share the same keys, validation, and errors.
+fn parse_toml_documents(config_contents: &str) -> Result<Vec<Yaml>, ConfigurationSettingsError> {
+    let table = config_contents.parse::<toml::Table>().map_err(|e| {
+        ConfigurationSettingsError::InvalidConfigurationFileContentError(e.to_string())
+    })?;
+    Ok(vec![toml_to_yaml(toml::Value::Table(table))])
+}
+
+#[cfg(test)]
+fn string_to_toml_config(
+    base_dir: &Path,
+    config_contents: &str,
+) -> Result<Configuration, Box<dyn Error>> {
+    documents_to_config(
+        base_dir,
+        &parse_toml_documents(config_contents)?,
+        &IncludeContext::default(),
+    )
+}
+
+/// Reads a configuration file as YAML or, for a `.toml` file, TOML.
+fn parse_config_file(file_path: &Path) -> Result<Vec<Yaml>, Box<dyn Error>> {
+    let file_content = std::fs::read_to_string(file_path)?;
+    if file_path.extension().is_some_and(|ext| ext == "toml") {
+        Ok(parse_toml_documents(&file_content)?)
     } else {
-        let cfp = &args.nth_back(0).unwrap();
-        let pb = PathBuf::from_str(cfp).map_err(|_e| {
-            ConfigurationSettingsError::InvalidConfigurationFilePath(cfp.to_owned())
+        Ok(parse_yaml_documents(&file_content)?)
+    }
+}
+
+/// The file whose `include` paths are being resolved, and the files that
+/// included it, for catching include cycles.
+#[derive(Clone, Default)]
+struct IncludeContext {
+    dir: Option<PathBuf>,
+    chain: Vec<PathBuf>,
+}
+
+impl IncludeContext {
+    fn for_file(file_path: &Path) -> Result<Self, ConfigurationSettingsError> {
+        let file_path = std::fs::canonicalize(file_path).map_err(|_e| {
+            ConfigurationSettingsError::ConfigurationFileNotFound(file_path.display().to_string())
         })?;
-        if !pb.is_absolute() {
-            Ok(current_dir.join(pb))
-        } else {
-            Ok(pb)
+        Ok(IncludeContext {
+            dir: file_path.parent().map(Path::to_path_buf),
+            chain: vec![file_path],
+        })
+    }
+
+    /// The context for a file included from this one, failing if it is
+    /// already being loaded further up.
+    fn include(&self, file_path: &Path) -> Result<Self, ConfigurationSettingsError> {
+        let mut included = IncludeContext::for_file(file_path)?;
+        let file_path = included.chain.remove(0);
+        if self.chain.contains(&file_path) {
+            let mut cycle: Vec<String> = self
+                .chain
+                .iter()
+                .skip_while(|p| **p != file_path)
+                .map(|p| p.display().to_string())
+                .collect();
+            cycle.push(file_path.display().to_string());
+            return Err(ConfigurationSettingsError::IncludeCycleError(cycle));
         }
+        included.chain = self.chain.clone();
+        included.chain.push(file_path);
+        Ok(included)
     }
-}
 
-pub(crate) fn try_load_config(
-    current_dir: &Path,
-    args: &mut std::env::Args,
-) -> Result<Configuration, Box<dyn Error>> {
-    let full_config_path = resolve_config_path(current_dir, args)?;
-    if !full_config_path.exists() {
-        return Err(Box::new(
-            ConfigurationSettingsError::ConfigurationFileNotFound(
-                full_config_path.to_str().unwrap().to_owned(),
-            ),
-        ));
+    fn file_name(&self) -> String {
+        self.chain
+            .last()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "the configuration".to_owned())
     }
-    load_config(full_config_path.as_path())
 }
 
-#[cfg(test)]
-mod test {
-    use std::{
-        path::{Path, PathBuf},
-        str::FromStr,
+fn read_variables(
+    full_config: &yaml_rust2::yaml::Hash,
+    variables: &mut HashMap<String, String>,
+) -> Result<(), ConfigurationSettingsError> {
+    let Some(vars_val) = full_config.get(&Yaml::String("variables".to_owned())) else {
+        return Ok(());
     };
+    let invalid_vars = |v: &Yaml| {
+        ConfigurationSettingsError::InvalidConfigurationSettingError(
+            "variables".to_owned(),
+            v.clone(),
+        )
+    };
+    for (k, v) in vars_val.as_hash().ok_or_else(|| invalid_vars(vars_val))? {
+        let key = k.as_str().ok_or_else(|| invalid_vars(k))?;
+        let value = yaml_scalar_to_string(v).ok_or_else(|| invalid_vars(v))?;
+        variables.insert(key.to_owned(), value);
+    }
+    Ok(())
+}
 
-    use crate::config::{ProgramSpec, string_to_config};
-
-    #[test]
-    fn test_parse_yaml_config_string() {
-        let config_content = r#"
-namespace: example-config
+/// Applies a document's `keybindings`, each naming an action and the key
+/// (or list of keys) that replaces its default.
+fn read_keybindings(
+    full_config: &yaml_rust2::yaml::Hash,
+    keymap: &mut Keymap,
+) -> Result<(), ConfigurationSettingsError> {
+    let Some(bindings_val) = full_config.get(&Yaml::String("keybindings".to_owned())) else {
+        return Ok(());
+    };
+    let invalid_bindings = |v: &Yaml| {
+        ConfigurationSettingsError::InvalidConfigurationSettingError(
+            "keybindings".to_owned(),
+            v.clone(),
+        )
+    };
+    let mut actions = Vec::new();
+    for (k, v) in bindings_val
+        .as_hash()
+        .ok_or_else(|| invalid_bindings(bindings_val))?
+    {
+        let action = k
+            .as_str()
+            .and_then(Action::from_name)
+            .ok_or_else(|| invalid_bindings(k))?;
+        let key_vals = match v {
+            Yaml::Array(items) => items.iter().collect(),
+            _ => vec![v],
+        };
+        let mut keys = Vec::new();
+        for kv in key_vals {
+            let name = kv.as_str().ok_or_else(|| invalid_bindings(kv))?;
+            keys.push((name, Key::parse(name).ok_or_else(|| invalid_bindings(kv))?));
+        }
+        // Free every configured action's default keys first, so keys can be
+        // swapped between actions whatever order they are listed in.
+        let _ = keymap.bind(action, &[]);
+        actions.push((k, action, keys));
+    }
+    for (k, action, keys) in actions {
+        let parsed: Vec<Key> = keys.iter().map(|(_, key)| *key).collect();
+        keymap.bind(action, &parsed).map_err(|(key, other)| {
+            let name = keys.iter().find(|(_, k)| *k == key).map(|(n, _)| *n);
+            ConfigurationSettingsError::KeyBindingConflictError(
+                name.unwrap_or_default().to_owned(),
+                other.to_owned(),
+                k.as_str().unwrap_or_default().to_owned(),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// The directory a document's apps resolve their `working_directory`
+/// against: its own top-level `working_directory`, if any, under `base_dir`.
+fn apps_base_dir(
+    base_dir: &Path,
+    full_config: &yaml_rust2::yaml::Hash,
+) -> Result<PathBuf, ConfigurationSettingsError> {
+    let Some(wd_val) = full_config.get(&Yaml::String("working_directory".to_owned())) else {
+        return Ok(base_dir.to_path_buf());
+    };
+    let invalid_wd = || {
+        ConfigurationSettingsError::InvalidConfigurationSettingError(
+            "working_directory".to_owned(),
+            wd_val.clone(),
+        )
+    };
+    let wd = PathBuf::from(wd_val.as_str().ok_or_else(invalid_wd)?);
+    if wd.is_absolute() {
+        Ok(wd)
+    } else {
+        path::absolute(base_dir.join(wd)).map_err(|_e| invalid_wd())
+    }
+}
+
+fn read_apps(
+    apps_base_dir: &Path,
+    variables: &HashMap<String, String>,
+    document: &Yaml,
+    full_config: &yaml_rust2::yaml::Hash,
+    oks: &mut Vec<ProgramSpec>,
+    fails: &mut Vec<InvalidAppSpecError>,
+) -> Result<(), ConfigurationSettingsError> {
+    let app_section = full_config
+        .get(&Yaml::String("apps".to_owned()))
+        .ok_or_else(|| {
+            ConfigurationSettingsError::InvalidConfigurationFileStructureError(document.clone())
+        })?;
+    let spec_hash = app_section.as_hash().ok_or_else(|| {
+        ConfigurationSettingsError::InvalidConfigurationFileStructureError(app_section.clone())
+    })?;
+    for (k, v) in spec_hash.iter() {
+        match spec_from_hash(apps_base_dir, variables, k, v) {
+            Ok(newspec) => oks.push(newspec),
+            Err(e) => fails.push(e),
+        }
+    }
+    Ok(())
+}
+
+/// Loads the apps of every file listed under `include`, relative to
+/// `include_dir`, recording which file each came from in `sources`. Only an
+/// included file's `apps`, `working_directory`, `variables`, and own
+/// `include` are used; it sees the including file's variables too.
+fn read_includes(
+    include_dir: &Path,
+    full_config: &yaml_rust2::yaml::Hash,
+    variables: &HashMap<String, String>,
+    includes: &IncludeContext,
+    oks: &mut Vec<ProgramSpec>,
+    sources: &mut Vec<String>,
+    fails: &mut Vec<InvalidAppSpecError>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(include_val) = full_config.get(&Yaml::String("include".to_owned())) else {
+        return Ok(());
+    };
+    let invalid_include = |v: &Yaml| {
+        ConfigurationSettingsError::InvalidConfigurationSettingError(
+            "include".to_owned(),
+            v.clone(),
+        )
+    };
+    for entry in include_val
+        .as_vec()
+        .ok_or_else(|| invalid_include(include_val))?
+    {
+        let file_path = include_dir.join(entry.as_str().ok_or_else(|| invalid_include(entry))?);
+        let included = includes.include(&file_path)?;
+        let included_dir = included
+            .dir
+            .clone()
+            .unwrap_or_else(|| include_dir.to_path_buf());
+        let mut included_variables = variables.clone();
+        for y in parse_config_file(&file_path)?.iter() {
+            let included_config = y.as_hash().ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationFileStructureError(y.clone())
+            })?;
+            read_variables(included_config, &mut included_variables)?;
+            let apps_dir = apps_base_dir(&included_dir, included_config)?;
+            read_apps(
+                &apps_dir,
+                &included_variables,
+                y,
+                included_config,
+                oks,
+                fails,
+            )?;
+            sources.resize(oks.len(), included.file_name());
+            read_includes(
+                &included_dir,
+                included_config,
+                &included_variables,
+                &included,
+                oks,
+                sources,
+                fails,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn documents_to_config(
+    base_dir: &Path,
+    yaml: &[Yaml],
+    includes: &IncludeContext,
+) -> Result<Configuration, Box<dyn Error>> {
+    let mut oks = Vec::new();
+    let mut sources = Vec::new();
+    let mut fails = Vec::new();
+    let ns_key = Yaml::String("namespace".to_owned());
+    let dedup_key = Yaml::String("dedup_logs".to_owned());
+    let notify_key = Yaml::String("notify_on_crash".to_owned());
+    let bell_key = Yaml::String("bell_on_crash".to_owned());
+    let log_lines_key = Yaml::String("log_lines".to_owned());
+    let capture_interval_key = Yaml::String("capture_interval_ms".to_owned());
+    let poll_interval_key = Yaml::String("poll_interval_ms".to_owned());
+    let tick_interval_key = Yaml::String("tick_interval_ms".to_owned());
+    let log_dir_key = Yaml::String("log_dir".to_owned());
+    let log_timestamps_key = Yaml::String("log_timestamps".to_owned());
+    let group_by_key = Yaml::String("group_by".to_owned());
+    let status_style_key = Yaml::String("status_style".to_owned());
+    let tmux_socket_key = Yaml::String("tmux_socket".to_owned());
+    let tab_title_key = Yaml::String("tab_title_template".to_owned());
+    let shell_key = Yaml::String("shell".to_owned());
+    let before_start_key = Yaml::String("before_start".to_owned());
+    let after_stop_key = Yaml::String("after_stop".to_owned());
+    let profiles_key = Yaml::String("profiles".to_owned());
+    let mut variables = HashMap::new();
+    let mut profiles: HashMap<String, Vec<String>> = HashMap::new();
+    let mut namespace = DEFAULT_NAMESPACE.to_owned();
+    let mut dedup_logs = false;
+    let mut notify_on_crash = false;
+    let mut bell_on_crash = false;
+    let mut log_lines = DEFAULT_LOG_LINES;
+    let mut capture_interval = DEFAULT_CAPTURE_INTERVAL;
+    let mut poll_interval = DEFAULT_POLL_INTERVAL;
+    let mut tick_interval = DEFAULT_TICK_INTERVAL;
+    let mut log_dir = None;
+    let mut log_timestamps = LogTimestamps::None;
+    let mut group_by = None;
+    let mut status_style = StatusStyle::default();
+    let mut tmux_socket = None;
+    let mut tab_title_template = DEFAULT_TAB_TITLE_TEMPLATE.to_owned();
+    let mut shell = None;
+    let mut before_start = None;
+    let mut keymap = Keymap::default();
+    let mut after_stop = None;
+    for y in yaml.iter() {
+        let full_config = y.as_hash().ok_or_else(|| {
+            ConfigurationSettingsError::InvalidConfigurationFileStructureError(y.clone())
+        })?;
+        if let Some(ns_val) = full_config.get(&ns_key) {
+            // The namespace starts every session name, so it must already be
+            // one tmux accepts.
+            namespace = ns_val
+                .as_str()
+                .filter(|ns| !ns.is_empty() && sanitize_session_name(ns) == *ns)
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationNamespaceError(ns_val.clone())
+                })?
+                .to_owned();
+        }
+        if let Some(dedup_val) = full_config.get(&dedup_key) {
+            dedup_logs = dedup_val.as_bool().ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "dedup_logs".to_owned(),
+                    dedup_val.clone(),
+                )
+            })?;
+        }
+        if let Some(notify_val) = full_config.get(&notify_key) {
+            notify_on_crash = notify_val.as_bool().ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "notify_on_crash".to_owned(),
+                    notify_val.clone(),
+                )
+            })?;
+        }
+        if let Some(bell_val) = full_config.get(&bell_key) {
+            bell_on_crash = bell_val.as_bool().ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "bell_on_crash".to_owned(),
+                    bell_val.clone(),
+                )
+            })?;
+        }
+        if let Some(log_lines_val) = full_config.get(&log_lines_key) {
+            log_lines = log_lines_val
+                .as_i64()
+                .and_then(|n| usize::try_from(n).ok())
+                .filter(|n| *n > 0)
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationSettingError(
+                        "log_lines".to_owned(),
+                        log_lines_val.clone(),
+                    )
+                })?;
+        }
+        if let Some(ci_val) = full_config.get(&capture_interval_key) {
+            let ms = ci_val
+                .as_i64()
+                .and_then(|ms| u64::try_from(ms).ok())
+                .filter(|ms| *ms > 0)
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationSettingError(
+                        "capture_interval_ms".to_owned(),
+                        ci_val.clone(),
+                    )
+                })?;
+            capture_interval = Duration::from_millis(ms);
+        }
+        if let Some(ti_val) = full_config.get(&tick_interval_key) {
+            let ms = ti_val
+                .as_i64()
+                .and_then(|ms| u64::try_from(ms).ok())
+                .filter(|ms| *ms > 0)
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationSettingError(
+                        "tick_interval_ms".to_owned(),
+                        ti_val.clone(),
+                    )
+                })?;
+            tick_interval = Duration::from_millis(ms);
+        }
+        if let Some(pi_val) = full_config.get(&poll_interval_key) {
+            let ms = pi_val
+                .as_i64()
+                .and_then(|ms| u64::try_from(ms).ok())
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationSettingError(
+                        "poll_interval_ms".to_owned(),
+                        pi_val.clone(),
+                    )
+                })?;
+            if !POLL_INTERVAL_RANGE_MS.contains(&ms) {
+                return Err(Box::new(
+                    ConfigurationSettingsError::SettingOutOfRangeError(
+                        "poll_interval_ms".to_owned(),
+                        format!(
+                            "{} is not between {} and {}; lower values make keys respond faster but wake the CPU more often, higher values save power at the cost of input lag",
+                            ms,
+                            POLL_INTERVAL_RANGE_MS.start(),
+                            POLL_INTERVAL_RANGE_MS.end()
+                        ),
+                    ),
+                ));
+            }
+            poll_interval = Duration::from_millis(ms);
+        }
+        if let Some(log_dir_val) = full_config.get(&log_dir_key) {
+            let dir = log_dir_val.as_str().ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "log_dir".to_owned(),
+                    log_dir_val.clone(),
+                )
+            })?;
+            log_dir = Some(base_dir.join(dir));
+        }
+        if let Some(lt_val) = full_config.get(&log_timestamps_key) {
+            log_timestamps = match lt_val.as_str() {
+                Some("none") => LogTimestamps::None,
+                Some("elapsed") => LogTimestamps::Elapsed,
+                Some("clock") => LogTimestamps::Clock,
+                _ => {
+                    return Err(Box::new(
+                        ConfigurationSettingsError::InvalidConfigurationSettingError(
+                            "log_timestamps".to_owned(),
+                            lt_val.clone(),
+                        ),
+                    ));
+                }
+            };
+        }
+        if let Some(group_by_val) = full_config.get(&group_by_key) {
+            group_by = match group_by_val.as_str() {
+                Some("tag") => Some(GroupBy::Tag),
+                _ => {
+                    return Err(Box::new(
+                        ConfigurationSettingsError::InvalidConfigurationSettingError(
+                            "group_by".to_owned(),
+                            group_by_val.clone(),
+                        ),
+                    ));
+                }
+            };
+        }
+        if let Some(style_val) = full_config.get(&status_style_key) {
+            status_style = match style_val.as_str() {
+                Some("emoji") => StatusStyle::Emoji,
+                Some("symbols") => StatusStyle::Symbols,
+                _ => {
+                    return Err(Box::new(
+                        ConfigurationSettingsError::InvalidConfigurationSettingError(
+                            "status_style".to_owned(),
+                            style_val.clone(),
+                        ),
+                    ));
+                }
+            };
+        }
+        if let Some(socket_val) = full_config.get(&tmux_socket_key) {
+            tmux_socket = match socket_val.as_str() {
+                Some(name)
+                    if !name.is_empty() && !name.chars().any(|c| c.is_whitespace() || c == '/') =>
+                {
+                    Some(name.to_owned())
+                }
+                _ => {
+                    return Err(Box::new(
+                        ConfigurationSettingsError::InvalidConfigurationSettingError(
+                            "tmux_socket".to_owned(),
+                            socket_val.clone(),
+                        ),
+                    ));
+                }
+            };
+        }
+        if let Some(title_val) = full_config.get(&tab_title_key) {
+            tab_title_template = title_val
+                .as_str()
+                .ok_or_else(|| {
+                    ConfigurationSettingsError::InvalidConfigurationSettingError(
+                        "tab_title_template".to_owned(),
+                        title_val.clone(),
+                    )
+                })?
+                .to_owned();
+        }
+        if let Some(shell_val) = full_config.get(&shell_key) {
+            shell = Some(shell_setting(shell_val).ok_or_else(|| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "shell".to_owned(),
+                    shell_val.clone(),
+                )
+            })?);
+        }
+        if let Some(hook_val) = full_config.get(&before_start_key) {
+            before_start = Some(hook_from_yaml("before_start", base_dir, hook_val)?);
+        }
+        if let Some(hook_val) = full_config.get(&after_stop_key) {
+            after_stop = Some(hook_from_yaml("after_stop", base_dir, hook_val)?);
+        }
+        read_variables(full_config, &mut variables)?;
+        read_keybindings(full_config, &mut keymap)?;
+        if let Some(profiles_val) = full_config.get(&profiles_key) {
+            let invalid_profiles = |v: &Yaml| {
+                ConfigurationSettingsError::InvalidConfigurationSettingError(
+                    "profiles".to_owned(),
+                    v.clone(),
+                )
+            };
+            for (k, v) in profiles_val
+                .as_hash()
+                .ok_or_else(|| invalid_profiles(profiles_val))?
+            {
+                let name = k.as_str().ok_or_else(|| invalid_profiles(k))?;
+                let members = v
+                    .as_vec()
+                    .ok_or_else(|| invalid_profiles(v))?
+                    .iter()
+                    .map(|m| {
+                        m.as_str()
+                            .map(str::to_owned)
+                            .ok_or_else(|| invalid_profiles(m))
+                    })
+                    .collect::<Result<Vec<String>, ConfigurationSettingsError>>()?;
+                profiles.insert(name.to_owned(), members);
+            }
+        }
+        let apps_base_dir = apps_base_dir(base_dir, full_config)?;
+        read_apps(
+            &apps_base_dir,
+            &variables,
+            y,
+            full_config,
+            &mut oks,
+            &mut fails,
+        )?;
+        sources.resize(oks.len(), includes.file_name());
+        read_includes(
+            includes.dir.as_deref().unwrap_or(base_dir),
+            full_config,
+            &variables,
+            includes,
+            &mut oks,
+            &mut sources,
+            &mut fails,
+        )?;
+    }
+    if !fails.is_empty() {
+        return Err(Box::new(
+            ConfigurationSettingsError::InvalidSpecStructuresError(fails),
+        ));
+    }
+    if let Some(shell) = shell {
+        for spec in oks.iter_mut().filter(|spec| spec.shell.is_none()) {
+            spec.shell = Some(shell.clone());
+        }
+    }
+    check_included_names(&oks, &sources)?;
+    check_duplicate_names(&oks)?;
+    check_profile_apps(&profiles, &oks)?;
+    let app_order = oks.iter().map(|spec| spec.name.clone()).collect();
+    Ok(Configuration {
+        namespace,
+        apps: order_by_dependencies(oks)?,
+        app_order,
+        dedup_logs,
+        notify_on_crash,
+        bell_on_crash,
+        log_lines,
+        capture_interval,
+        poll_interval,
+        tick_interval,
+        log_dir,
+        log_timestamps,
+        group_by,
+        tab_title_template,
+        tmux_socket,
+        profiles,
+        before_start,
+        after_stop,
+        keymap,
+        status_style,
+    })
+}
+
+fn load_config(
+    file_path: &Path,
+    base_dir_override: Option<&Path>,
+) -> Result<Configuration, Box<dyn Error>> {
+    let p_dir = base_dir_override.unwrap_or_else(|| file_path.parent().unwrap());
+    documents_to_config(
+        p_dir,
+        &parse_config_file(file_path)?,
+        &IncludeContext::for_file(file_path)?,
+    )
+}
+
+fn resolve_against(current_dir: &Path, p: &Path) -> PathBuf {
+    if p.is_absolute() {
+        p.to_path_buf()
+    } else {
+        current_dir.join(p)
+    }
+}
+
+const DEFAULT_CONFIG_FILE_NAMES: [&str; 2] = ["devplexer.yaml", "devplexer.toml"];
+
+fn resolve_config_path(current_dir: &Path, config_path: Option<&Path>) -> PathBuf {
+    match config_path {
+        Some(cfp) => resolve_against(current_dir, cfp),
+        None => DEFAULT_CONFIG_FILE_NAMES
+            .iter()
+            .map(|f| current_dir.join(f))
+            .find(|p| p.exists())
+            .unwrap_or_else(|| current_dir.join(DEFAULT_CONFIG_FILE_NAMES[0])),
+    }
+}
+
+/// Checks a loaded configuration against the filesystem, returning every
+/// problem found rather than stopping at the first.
+pub(crate) fn validate_config(config: &Configuration) -> Vec<InvalidAppSpecError> {
+    config
+        .apps
+        .iter()
+        .filter(|spec| !spec.working_directory.is_dir())
+        .map(|spec| {
+            InvalidAppSpecError::MissingWorkingDirectoryError(
+                spec.name.clone(),
+                spec.working_directory.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Fails with every missing working directory, so a typo is reported before
+/// any app is launched into it.
+pub(crate) fn ensure_paths_exist(config: &Configuration) -> Result<(), ConfigurationSettingsError> {
+    let problems = validate_config(config);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigurationSettingsError::InvalidSpecStructuresError(
+            problems,
+        ))
+    }
+}
+
+pub(crate) fn try_load_config(
+    current_dir: &Path,
+    cli_options: &CliOptions,
+) -> Result<Configuration, Box<dyn Error>> {
+    let full_config_path = match cli_options.procfile.as_deref() {
+        Some(procfile) => resolve_against(current_dir, procfile),
+        None => resolve_config_path(current_dir, cli_options.config_path.as_deref()),
+    };
+    if !full_config_path.exists() {
+        return Err(Box::new(
+            ConfigurationSettingsError::ConfigurationFileNotFound(
+                full_config_path.to_str().unwrap().to_owned(),
+            ),
+        ));
+    }
+    let base_dir_override = cli_options
+        .working_directory
+        .as_deref()
+        .map(|wd| resolve_against(current_dir, wd));
+    let config = if cli_options.procfile.is_some() {
+        load_procfile(full_config_path.as_path(), base_dir_override.as_deref())?
+    } else {
+        load_config(full_config_path.as_path(), base_dir_override.as_deref())?
+    };
+    match cli_options.profile.as_deref() {
+        Some(profile) => Ok(select_profile(config, profile)?),
+        None => Ok(config),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::Duration,
+    };
+
+    use ratatui::style::Color;
+    use sysinfo::Signal;
+
+    use crate::{
+        capture::DEFAULT_CAPTURE_INTERVAL,
+        config::{
+            Configuration, ConfigurationSettingsError, DEFAULT_POLL_INTERVAL,
+            DEFAULT_TICK_INTERVAL, GroupBy, Hook, InvalidAppSpecError, ProgramCommand, ProgramSpec,
+            ReadinessProbe, ReadyTimeoutPolicy, RestartPolicy, StatusStyle, ensure_paths_exist,
+            load_config, select_profile, string_to_config, string_to_toml_config, tab_title,
+        },
+        keymap::{Action, Key, Keymap},
+        logging::{DEFAULT_LOG_LINES, LogTimestamps},
+    };
+
+    #[test]
+    fn test_parse_yaml_config_string() {
+        let config_content = r#"
+namespace: example-config
 apps:
   server:
     command: ls
   server-ui:
     command: echo "blah"
     working_directory: ./ui
+    environment:
+      RAILS_ENV: development
+      PORT: 8080
 "#;
         let base = Path::new("/");
         let config_results = string_to_config(base, config_content).unwrap();
@@ -233,18 +1738,1119 @@ apps:
             vec! {
                 ProgramSpec {
                     name: "server".to_owned(),
-                    command: "ls".to_owned(),
+                    command: ProgramCommand::Shell("ls".to_owned()),
                     working_directory: base.to_path_buf(),
-                    deps: vec!{}
+                    deps: vec!{},
+                    ..Default::default()
                 },
                 ProgramSpec {
                     name: "server-ui".to_owned(),
-                    command: "echo \"blah\"".to_owned(),
+                    command: ProgramCommand::Shell("echo \"blah\"".to_owned()),
                     working_directory: PathBuf::from_str("/ui").unwrap(),
-                    deps: vec!{}
+                    deps: vec!{},
+                    env: vec!{
+                        ("RAILS_ENV".to_owned(), "development".to_owned()),
+                        ("PORT".to_owned(), "8080".to_owned())
+                    },
+                    ..Default::default()
                 }
             }
         );
         assert_eq!(config_results.namespace, "example-config");
+        assert!(!config_results.dedup_logs);
+    }
+
+    #[test]
+    fn test_parse_readiness_settings() {
+        let config_content = r#"
+apps:
+  db:
+    command: postgres
+    readiness:
+      tcp: localhost:5432
+    ready_timeout_ms: 20000
+    on_ready_timeout: continue
+  static:
+    command: python -m http.server
+    readiness:
+      command: curl -sf http://localhost:8000
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        let db = &config_results.apps[0];
+        assert_eq!(
+            db.readiness,
+            Some(ReadinessProbe::Tcp("localhost:5432".to_owned()))
+        );
+        assert_eq!(db.ready_timeout, Some(Duration::from_millis(20000)));
+        assert_eq!(db.on_ready_timeout, ReadyTimeoutPolicy::Continue);
+        let web = &config_results.apps[1];
+        assert_eq!(
+            web.readiness,
+            Some(ReadinessProbe::Command(
+                "curl -sf http://localhost:8000".to_owned()
+            ))
+        );
+        assert_eq!(web.ready_timeout, None);
+        assert_eq!(web.on_ready_timeout, ReadyTimeoutPolicy::Fail);
+    }
+
+    #[test]
+    fn test_parse_ready_log_pattern() {
+        let config_content = r#"
+apps:
+  web:
+    command: rails server
+    ready_log_pattern: Listening on
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            config_results.apps[0].readiness,
+            Some(ReadinessProbe::LogPattern("Listening on".to_owned()))
+        );
+        let both = r#"
+apps:
+  web:
+    command: rails server
+    readiness: curl -sf localhost:3000/up
+    ready_log_pattern: Listening on
+"#;
+        let err = string_to_config(Path::new("/"), both).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid app settings:\n  app \"web\" sets both \"readiness\" and \"ready_log_pattern\", which can't be used together"
+        );
+    }
+
+    #[test]
+    fn test_parse_health_check_shorthand() {
+        let config_content = r#"
+apps:
+  db:
+    command: postgres
+    health_check: tcp:localhost:5432
+  web:
+    command: ./web
+    health_check: curl -sf http://localhost:3000/up
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            config_results.apps[0].readiness,
+            Some(ReadinessProbe::Tcp("localhost:5432".to_owned()))
+        );
+        assert_eq!(
+            config_results.apps[1].readiness,
+            Some(ReadinessProbe::Command(
+                "curl -sf http://localhost:3000/up".to_owned()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        let config_content = r#"
+apps:
+  worker:
+    command: ./worker
+    restart: on-failure
+  web:
+    command: ./web
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(config_results.apps[0].restart, RestartPolicy::OnFailure);
+        assert_eq!(config_results.apps[1].restart, RestartPolicy::Never);
+        let invalid = "apps:\n  worker:\n    command: ./worker\n    restart: sometimes\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_stop_settings() {
+        let config_content = r#"
+apps:
+  db:
+    command: postgres
+    stop_signal: SIGQUIT
+    stop_timeout_ms: 5000
+    kill_timeout_ms: 30000
+  web:
+    command: ./web
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        let db = &config_results.apps[0];
+        assert_eq!(db.stop_signal, Some(Signal::Quit));
+        assert_eq!(db.stop_timeout, Some(Duration::from_millis(5000)));
+        assert_eq!(db.kill_timeout, Some(Duration::from_millis(30000)));
+        let web = &config_results.apps[1];
+        assert_eq!(web.stop_signal, None);
+        assert_eq!(web.kill_timeout, None);
+        let invalid = "apps:\n  db:\n    command: postgres\n    stop_signal: SIGNOPE\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_launch_if() {
+        let config_content = r#"
+apps:
+  gpu-worker:
+    command: ./worker
+    launch_if: nvidia-smi
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            config_results.apps[0].launch_if,
+            Some("nvidia-smi".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_parse_tags_and_group_by() {
+        let config_content = r#"
+group_by: tag
+apps:
+  web:
+    command: ls
+    tags: [frontend]
+  api:
+    command: ls
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(config_results.group_by, Some(GroupBy::Tag));
+        assert_eq!(config_results.apps[0].tags, vec!["frontend".to_owned()]);
+        assert!(config_results.apps[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_tab_title_template() {
+        let config_results =
+            string_to_config(Path::new("/"), "apps:\n  web.v2:\n    command: ls\n").unwrap();
+        assert_eq!(
+            tab_title(
+                &config_results.tab_title_template,
+                &config_results.apps[0].name
+            ),
+            "web.v2"
+        );
+        let config_content =
+            "tab_title_template: \"dev: {name}\"\napps:\n  web:\n    command: ls\n";
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            tab_title(&config_results.tab_title_template, "web"),
+            "dev: web"
+        );
+        let invalid = "tab_title_template: [a]\napps:\n  web:\n    command: ls\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_tmux_socket() {
+        let config_content = "tmux_socket: devplexer\napps:\n  web:\n    command: ls\n";
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(config_results.tmux_socket, Some("devplexer".to_owned()));
+        let config_results = string_to_config(Path::new("/"), "apps:\n  web:\n    command: ls\n");
+        assert_eq!(config_results.unwrap().tmux_socket, None);
+        for bad in ["''", "my socket", "/tmp/sock", "[a]"] {
+            let invalid = format!("tmux_socket: {}\napps:\n  web:\n    command: ls\n", bad);
+            assert!(string_to_config(Path::new("/"), &invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_non_scalar_environment_rejected() {
+        let config_content = r#"
+apps:
+  server:
+    command: ls
+    environment:
+      NESTED:
+        - a
+"#;
+        let err = string_to_config(Path::new("/"), config_content)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::InvalidSpecStructuresError(errs))
+                if matches!(errs[0], InvalidAppSpecError::InvalidEnvironmentError(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_parse_string_and_argv_commands() {
+        let config_content = r#"
+apps:
+  shell:
+    command: echo "it's fine" && sleep 5
+  argv:
+    command: [grep, -e, "it's $HOME", notes.txt, 5]
+  empty:
+    command: []
+"#;
+        let err = string_to_config(Path::new("/"), config_content)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::InvalidSpecStructuresError(errs))
+                if matches!(&errs[..], [InvalidAppSpecError::MissingCommandError(n, _)] if n == "empty")
+        ));
+        let valid = config_content.split("  empty:").next().unwrap();
+        let config_results = string_to_config(Path::new("/"), valid).unwrap();
+        assert_eq!(
+            config_results.apps[0].command,
+            ProgramCommand::Shell("echo \"it's fine\" && sleep 5".to_owned())
+        );
+        assert_eq!(
+            config_results.apps[1].command,
+            ProgramCommand::Argv(vec![
+                "grep".to_owned(),
+                "-e".to_owned(),
+                "it's $HOME".to_owned(),
+                "notes.txt".to_owned(),
+                "5".to_owned(),
+            ])
+        );
+        assert_eq!(
+            config_results.apps[1].command.to_shell_string(),
+            "grep -e 'it'\\''s $HOME' notes.txt 5"
+        );
+    }
+
+    #[test]
+    fn test_top_level_working_directory() {
+        let relative_default = r#"
+working_directory: services
+apps:
+  api:
+    command: ls
+    working_directory: api
+  gateway:
+    command: ls
+  legacy:
+    command: ls
+    working_directory: /opt/legacy
+"#;
+        let config_results = string_to_config(Path::new("/repo"), relative_default).unwrap();
+        assert_eq!(
+            config_results.apps[0].working_directory,
+            PathBuf::from("/repo/services/api")
+        );
+        assert_eq!(
+            config_results.apps[1].working_directory,
+            PathBuf::from("/repo/services")
+        );
+        assert_eq!(
+            config_results.apps[2].working_directory,
+            PathBuf::from("/opt/legacy")
+        );
+
+        let absolute_default = r#"
+working_directory: /srv
+apps:
+  web:
+    command: ls
+    working_directory: web
+"#;
+        let config_results = string_to_config(Path::new("/repo"), absolute_default).unwrap();
+        assert_eq!(
+            config_results.apps[0].working_directory,
+            PathBuf::from("/srv/web")
+        );
+    }
+
+    #[test]
+    fn test_env_file_resolved_against_working_directory() {
+        let app_dir = std::env::temp_dir().join("devplexer-env-file-test");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join(".env"), "SECRET=shh\n").unwrap();
+        let config_content = format!(
+            r#"
+apps:
+  server:
+    command: ls
+    working_directory: {}
+    env_file: .env
+  missing:
+    command: ls
+    working_directory: {}
+    env_file: .env.missing
+"#,
+            app_dir.display(),
+            app_dir.display()
+        );
+        let err = string_to_config(Path::new("/"), &config_content)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::InvalidSpecStructuresError(errs))
+                if errs.len() == 1
+                    && matches!(&errs[0], InvalidAppSpecError::EnvFileNotFoundError(n, _) if n == "missing")
+        ));
+        let valid = config_content.split("  missing:").next().unwrap();
+        let config_results = string_to_config(Path::new("/"), valid).unwrap();
+        assert_eq!(config_results.apps[0].env_file, Some(app_dir.join(".env")));
+    }
+
+    #[test]
+    fn test_env_file_contents_checked_at_load() {
+        let app_dir = std::env::temp_dir().join("devplexer-bad-env-file-test");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join(".env"), "SECRET=shh\nfoo bar\n").unwrap();
+        let config_content = format!(
+            "apps:\n  server:\n    command: ls\n    working_directory: {}\n    env_file: .env\n",
+            app_dir.display()
+        );
+        let err = string_to_config(Path::new("/"), &config_content)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "invalid app settings:\n  app \"server\" has an invalid \"env_file\": {}: line 2 is not a KEY=VALUE assignment: \"foo bar\"",
+                app_dir.join(".env").display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_apps_ordered_by_dependencies() {
+        let config_content = r#"
+apps:
+  worker:
+    command: ./worker
+    deps: [backend]
+  backend:
+    command: ./backend
+    deps: [db]
+  ui:
+    command: ./ui
+  db:
+    command: postgres
+"#;
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        let names: Vec<&str> = config_results
+            .apps
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["ui", "db", "backend", "worker"]);
+        assert_eq!(config_results.apps[3].deps, vec!["backend".to_owned()]);
+    }
+
+    #[test]
+    fn test_unknown_and_cyclic_dependencies_rejected() {
+        let unknown = r#"
+apps:
+  worker:
+    command: ./worker
+    deps: [backend]
+"#;
+        let err = string_to_config(Path::new("/"), unknown).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::UnknownDependencyError(app, dep))
+                if app == "worker" && dep == "backend"
+        ));
+        let cyclic = r#"
+apps:
+  a:
+    command: ./a
+    deps: [b]
+  b:
+    command: ./b
+    deps: [a]
+  c:
+    command: ./c
+"#;
+        let err = string_to_config(Path::new("/"), cyclic).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::CyclicDependencyError(names))
+                if names == &vec!["a".to_owned(), "b".to_owned()]
+        ));
+    }
+
+    #[test]
+    fn test_select_profile_adds_dependencies() {
+        let config_content = r#"
+profiles:
+  frontend: [ui]
+  backend: [db]
+apps:
+  ui:
+    command: ./ui
+    deps: [api]
+  api:
+    command: ./api
+    deps: [db]
+  db:
+    command: ./db
+  worker:
+    command: ./worker
+    deps: [db]
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        let frontend = select_profile(config, "frontend").unwrap();
+        let names: Vec<&str> = frontend.apps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["db", "api", "ui"]);
+        assert_eq!(frontend.app_order, vec!["ui", "api", "db"]);
+
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        assert!(matches!(
+            select_profile(config, "mobile"),
+            Err(ConfigurationSettingsError::UnknownProfileError(p)) if p == "mobile"
+        ));
+    }
+
+    #[test]
+    fn test_rejects_unknown_profile_apps() {
+        let config_content = r#"
+profiles:
+  frontend: [ui, assets]
+apps:
+  ui:
+    command: ./ui
+"#;
+        let err = string_to_config(Path::new("/"), config_content)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::UnknownProfileAppError(profile, app))
+                if profile == "frontend" && app == "assets"
+        ));
+    }
+
+    #[test]
+    fn test_parse_keep_alive() {
+        let config_content = r#"
+apps:
+  migrate:
+    command: rake db:migrate
+    keep_alive: false
+  web:
+    command: rails s
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        let migrate = config.apps.iter().find(|s| s.name == "migrate").unwrap();
+        let web = config.apps.iter().find(|s| s.name == "web").unwrap();
+        assert_eq!(migrate.keep_alive, Some(false));
+        assert!(!migrate.keeps_pane());
+        assert!(web.keeps_pane());
+        let invalid = "apps:\n  web:\n    command: ls\n    keep_alive: sometimes\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_oneshot() {
+        let config_content = r#"
+apps:
+  migrate:
+    command: rake db:migrate
+    oneshot: true
+  web:
+    command: rails s
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        let migrate = config.apps.iter().find(|s| s.name == "migrate").unwrap();
+        let web = config.apps.iter().find(|s| s.name == "web").unwrap();
+        assert!(migrate.oneshot);
+        assert!(!web.oneshot);
+        let invalid = "apps:\n  web:\n    command: ls\n    oneshot: once\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_attach_readonly() {
+        let watched = "apps:\n  web:\n    command: ls\n    attach_readonly: true\n";
+        let config = string_to_config(Path::new("/"), watched).unwrap();
+        assert!(config.apps[0].attach_readonly);
+        let default = "apps:\n  web:\n    command: ls\n";
+        assert!(!string_to_config(Path::new("/"), default).unwrap().apps[0].attach_readonly);
+        let invalid = "apps:\n  web:\n    command: ls\n    attach_readonly: 1\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_color() {
+        let config_content = r##"
+apps:
+  web:
+    command: ls
+    color: lightblue
+  api:
+    command: ls
+    color: "#ff8800"
+  db:
+    command: ls
+"##;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(config.apps[0].color, Some(Color::LightBlue));
+        assert_eq!(config.apps[1].color, Some(Color::Rgb(0xff, 0x88, 0x00)));
+        assert_eq!(config.apps[2].color, None);
+        let invalid = "apps:\n  web:\n    command: ls\n    color: chartreuse-ish\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_shell() {
+        let config_content = r#"
+shell: /bin/bash
+apps:
+  web:
+    command: ls
+  fishy:
+    command: ls
+    shell: fish
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(config.apps[0].shell.as_deref(), Some("/bin/bash"));
+        assert_eq!(config.apps[1].shell.as_deref(), Some("fish"));
+        let default = "apps:\n  web:\n    command: ls\n";
+        assert_eq!(
+            string_to_config(Path::new("/"), default).unwrap().apps[0].shell,
+            None
+        );
+        let invalid = "apps:\n  web:\n    command: ls\n    shell: \"\"\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let config_content = r#"
+before_start: docker compose up -d
+after_stop: [docker, compose, down]
+apps:
+  web:
+    command: ls
+"#;
+        let config = string_to_config(Path::new("/srv"), config_content).unwrap();
+        assert_eq!(
+            config.before_start,
+            Some(Hook {
+                command: ProgramCommand::Shell("docker compose up -d".to_owned()),
+                working_directory: PathBuf::from("/srv"),
+            })
+        );
+        assert_eq!(
+            config.after_stop.unwrap().command,
+            ProgramCommand::Argv(vec![
+                "docker".to_owned(),
+                "compose".to_owned(),
+                "down".to_owned()
+            ])
+        );
+        let without = string_to_config(Path::new("/srv"), "apps: {}\n").unwrap();
+        assert_eq!(without.before_start, None);
+        let invalid = "before_start: {up: true}\napps: {}\n";
+        assert!(string_to_config(Path::new("/srv"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let config_content = r#"
+apps:
+  web:
+    working_directory: server
+  api:
+    command: ls
+    deps: {db: true}
+"#;
+        let err = string_to_config(Path::new("/"), config_content)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid app settings:\n  app \"web\" is missing required key \"command\"\n  app \"api\" has an invalid value for \"deps\": {\"db\": true}"
+        );
+        let err = string_to_config(Path::new("/"), "apps:\n  web: {command: \"ls\"\n")
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .starts_with("could not parse the configuration file: ")
+        );
+    }
+
+    #[test]
+    fn test_parse_wait_port_free() {
+        let config_content = r#"
+apps:
+  web:
+    command: rails s
+    wait_port_free: localhost:3000
+    wait_port_free_timeout_ms: 5000
+  worker:
+    command: sidekiq
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            config.apps[0].wait_port_free,
+            Some("localhost:3000".to_owned())
+        );
+        assert_eq!(
+            config.apps[0].port_free_timeout,
+            Some(Duration::from_millis(5000))
+        );
+        assert_eq!(config.apps[1].wait_port_free, None);
+        let invalid = "apps:\n  web:\n    command: ls\n    wait_port_free: [3000]\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_namespace_must_be_a_valid_session_name() {
+        let valid = "namespace: my-app_2\napps:\n  web:\n    command: ls\n";
+        let config = string_to_config(Path::new("/"), valid).unwrap();
+        assert_eq!(config.namespace, "my-app_2");
+        for bad in ["my.app", "my:app", "\"my app\"", "\"\"", "[a]"] {
+            let config_content = format!("namespace: {}\napps:\n  web:\n    command: ls\n", bad);
+            let err = string_to_config(Path::new("/"), &config_content)
+                .err()
+                .unwrap();
+            assert!(
+                err.to_string()
+                    .starts_with("\"namespace\" must be a string without"),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_start_timeout() {
+        let config_content = r#"
+apps:
+  web:
+    command: rails s
+    start_timeout_ms: 15000
+  worker:
+    command: sidekiq
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        assert_eq!(
+            config.apps[0].start_timeout,
+            Some(Duration::from_millis(15000))
+        );
+        assert_eq!(config.apps[1].start_timeout, None);
+        let invalid = "apps:\n  web:\n    command: ls\n    start_timeout_ms: soon\n";
+        assert!(string_to_config(Path::new("/"), invalid).is_err());
+    }
+
+    #[test]
+    fn test_keeps_declaration_order() {
+        let config_content = r#"
+apps:
+  web:
+    command: ./web
+    deps: [db]
+  db:
+    command: ./db
+  assets:
+    command: ./assets
+"#;
+        let config = string_to_config(Path::new("/"), config_content).unwrap();
+        let launch_order: Vec<&str> = config.apps.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(launch_order, vec!["db", "web", "assets"]);
+        assert_eq!(config.app_order, vec!["web", "db", "assets"]);
+    }
+
+    #[test]
+    fn test_rejects_names_sharing_a_session() {
+        let config_content = r#"
+apps:
+  api.v2:
+    command: ./api
+  "api:v2":
+    command: ./api
+  api v2:
+    command: ./api
+  api_v2:
+    command: ./api
+  web:
+    command: ./web
+"#;
+        let err = string_to_config(Path::new("/"), config_content)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::DuplicateAppName(names))
+                if names == &vec!["api v2".to_owned(), "api.v2".to_owned(), "api:v2".to_owned()]
+        ));
+    }
+
+    #[test]
+    fn test_interpolates_variables() {
+        let home = std::env::var("HOME").unwrap();
+        let yaml_content = r#"
+variables:
+  ROOT: /src/project
+apps:
+  server:
+    command: ${ROOT}/bin/server --cache ${HOME}/.cache --price $$5
+    working_directory: ${ROOT}/server
+    environment:
+      ASSETS: ${ROOT}/assets
+  ui:
+    command: [npx, serve, "${ROOT}/ui"]
+"#;
+        let config = string_to_config(Path::new("/"), yaml_content).unwrap();
+        let server = &config.apps[0];
+        assert_eq!(
+            server.command,
+            ProgramCommand::Shell(format!(
+                "/src/project/bin/server --cache {}/.cache --price $5",
+                home
+            ))
+        );
+        assert_eq!(
+            server.working_directory,
+            PathBuf::from("/src/project/server")
+        );
+        assert_eq!(
+            server.env,
+            vec![("ASSETS".to_owned(), "/src/project/assets".to_owned())]
+        );
+        assert_eq!(
+            config.apps[1].command,
+            ProgramCommand::Argv(vec![
+                "npx".to_owned(),
+                "serve".to_owned(),
+                "/src/project/ui".to_owned()
+            ])
+        );
+
+        let undefined = r#"
+apps:
+  server:
+    command: ${DEVPLEXER_SURELY_UNDEFINED}/server
+"#;
+        assert!(string_to_config(Path::new("/"), undefined).is_err());
+    }
+
+    #[test]
+    fn test_toml_config_matches_yaml() {
+        let yaml_content = r#"
+namespace: example-config
+apps:
+  server:
+    command: ls
+    environment:
+      PORT: 8080
+  server-ui:
+    command: [npx, serve]
+    working_directory: ./ui
+    deps: [server]
+"#;
+        let toml_content = r#"
+namespace = "example-config"
+
+[apps.server]
+command = "ls"
+environment = { PORT = 8080 }
+
+[apps.server-ui]
+command = ["npx", "serve"]
+working_directory = "./ui"
+deps = ["server"]
+"#;
+        let base = Path::new("/");
+        let from_yaml = string_to_config(base, yaml_content).unwrap();
+        let from_toml = string_to_toml_config(base, toml_content).unwrap();
+        assert_eq!(from_toml.namespace, from_yaml.namespace);
+        assert_eq!(from_toml.apps, from_yaml.apps);
+        assert!(string_to_toml_config(base, "apps = [").is_err());
+    }
+
+    #[test]
+    fn test_parse_top_level_settings() {
+        type Field = fn(&Configuration) -> String;
+        // Each setting's key, a valid value and what it parses to, what an
+        // unset key defaults to, and a value that must be rejected.
+        type Case = (
+            &'static str,
+            &'static str,
+            Field,
+            String,
+            String,
+            &'static str,
+        );
+        let cases: Vec<Case> = vec![
+            (
+                "log_timestamps",
+                "elapsed",
+                |c| format!("{:?}", c.log_timestamps),
+                format!("{:?}", LogTimestamps::Elapsed),
+                format!("{:?}", LogTimestamps::None),
+                "sometimes",
+            ),
+            (
+                "dedup_logs",
+                "true",
+                |c| c.dedup_logs.to_string(),
+                "true".to_owned(),
+                "false".to_owned(),
+                "maybe",
+            ),
+            (
+                "notify_on_crash",
+                "true",
+                |c| c.notify_on_crash.to_string(),
+                "true".to_owned(),
+                "false".to_owned(),
+                "loudly",
+            ),
+            (
+                "bell_on_crash",
+                "true",
+                |c| c.bell_on_crash.to_string(),
+                "true".to_owned(),
+                "false".to_owned(),
+                "ding",
+            ),
+            (
+                "status_style",
+                "symbols",
+                |c| format!("{:?}", c.status_style),
+                format!("{:?}", StatusStyle::Symbols),
+                format!("{:?}", StatusStyle::Emoji),
+                "plain",
+            ),
+            (
+                "capture_interval_ms",
+                "50",
+                |c| format!("{:?}", c.capture_interval),
+                format!("{:?}", Duration::from_millis(50)),
+                format!("{:?}", DEFAULT_CAPTURE_INTERVAL),
+                "0",
+            ),
+            (
+                "tick_interval_ms",
+                "250",
+                |c| format!("{:?}", c.tick_interval),
+                format!("{:?}", Duration::from_millis(250)),
+                format!("{:?}", DEFAULT_TICK_INTERVAL),
+                "0",
+            ),
+            (
+                "poll_interval_ms",
+                "500",
+                |c| format!("{:?}", c.poll_interval),
+                format!("{:?}", Duration::from_millis(500)),
+                format!("{:?}", DEFAULT_POLL_INTERVAL),
+                "1",
+            ),
+            (
+                "log_dir",
+                "tmp/logs",
+                |c| format!("{:?}", c.log_dir),
+                format!("{:?}", Some(PathBuf::from("/srv/app/tmp/logs"))),
+                format!("{:?}", None::<PathBuf>),
+                "[tmp]",
+            ),
+            (
+                "log_lines",
+                "250",
+                |c| c.log_lines.to_string(),
+                "250".to_owned(),
+                DEFAULT_LOG_LINES.to_string(),
+                "0",
+            ),
+        ];
+        let parse = |setting: &str| {
+            let content = format!("{}apps:\n  server:\n    command: ls\n", setting);
+            string_to_config(Path::new("/srv/app"), &content)
+        };
+        for (key, value, field, expected, default, invalid) in cases {
+            let config = parse(&format!("{}: {}\n", key, value)).unwrap();
+            assert_eq!(field(&config), expected, "{}: {}", key, value);
+            assert_eq!(field(&parse("").unwrap()), default, "{} unset", key);
+            let rejected = parse(&format!("{}: {}\n", key, invalid));
+            assert!(rejected.is_err(), "{}: {}", key, invalid);
+        }
+        let err = parse("poll_interval_ms: 1\n").err().unwrap();
+        assert!(
+            err.to_string()
+                .starts_with("\"poll_interval_ms\" is out of range: 1 is not between 10 and 2000")
+        );
+    }
+
+    #[test]
+    fn test_parse_keybindings() {
+        let config_content =
+            "keybindings:\n  quit: ctrl-x\n  follow: [G, F5]\napps:\n  server:\n    command: ls\n";
+        let config_results = string_to_config(Path::new("/"), config_content).unwrap();
+        let mut expected = Keymap::default();
+        expected
+            .bind(Action::Quit, &[Key::parse("ctrl-x").unwrap()])
+            .unwrap();
+        expected
+            .bind(
+                Action::Follow,
+                &[Key::parse("G").unwrap(), Key::parse("f5").unwrap()],
+            )
+            .unwrap();
+        assert!(config_results.keymap == expected);
+        let unknown_action = "keybindings:\n  explode: x\napps:\n  server:\n    command: ls\n";
+        assert!(string_to_config(Path::new("/"), unknown_action).is_err());
+        let unknown_key = "keybindings:\n  quit: hyper-q\napps:\n  server:\n    command: ls\n";
+        assert!(string_to_config(Path::new("/"), unknown_key).is_err());
+        let swapped = "keybindings:\n  select_next: down\n  scroll_down: j\napps:\n  server:\n    command: ls\n";
+        let keymap = string_to_config(Path::new("/"), swapped).unwrap().keymap;
+        assert_eq!(keymap.label(Action::SelectNext), "Down");
+        assert_eq!(keymap.label(Action::ScrollDown), "J");
+        let conflict = "keybindings:\n  kill: r\napps:\n  server:\n    command: ls\n";
+        let err = string_to_config(Path::new("/"), conflict).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "key \"r\" is bound to both \"restart\" and \"kill\" in \"keybindings\""
+        );
+        let built_in = "keybindings:\n  quit: /\napps:\n  server:\n    command: ls\n";
+        let err = string_to_config(Path::new("/"), built_in).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "key \"/\" is bound to both \"search\" and \"quit\" in \"keybindings\""
+        );
+    }
+
+    #[test]
+    fn test_ensure_paths_exist() {
+        let base_dir = std::env::temp_dir();
+        let config_content = r#"
+apps:
+  present:
+    command: ls
+  missing:
+    command: ls
+    working_directory: devplexer-no-such-directory
+"#;
+        let config = string_to_config(&base_dir, config_content).unwrap();
+        let missing_dir = base_dir.join("devplexer-no-such-directory");
+        assert!(matches!(
+            ensure_paths_exist(&config),
+            Err(ConfigurationSettingsError::InvalidSpecStructuresError(fails))
+                if matches!(
+                    fails.as_slice(),
+                    [InvalidAppSpecError::MissingWorkingDirectoryError(name, dir)]
+                        if name == "missing" && dir == &missing_dir
+                )
+        ));
+    }
+
+    #[test]
+    fn test_working_directory_override() {
+        let config_dir = std::env::temp_dir().join("devplexer-wd-override-test");
+        std::fs::create_dir_all(&config_dir).unwrap();
+        let config_path = config_dir.join("devplexer.yaml");
+        std::fs::write(
+            &config_path,
+            r#"
+apps:
+  server:
+    command: ls
+  ui:
+    command: ls
+    working_directory: ui
+  abs:
+    command: ls
+    working_directory: /opt/abs
+"#,
+        )
+        .unwrap();
+        let other_checkout = Path::new("/other/checkout");
+        let config_results = load_config(&config_path, Some(other_checkout)).unwrap();
+        assert_eq!(config_results.apps[0].working_directory, other_checkout);
+        assert_eq!(
+            config_results.apps[1].working_directory,
+            other_checkout.join("ui")
+        );
+        assert_eq!(
+            config_results.apps[2].working_directory,
+            PathBuf::from("/opt/abs")
+        );
+        let default_results = load_config(&config_path, None).unwrap();
+        assert_eq!(
+            default_results.apps[1].working_directory,
+            config_dir.join("ui")
+        );
+    }
+
+    #[test]
+    fn test_include_merges_apps() {
+        let config_dir = std::env::temp_dir().join("devplexer-include-test");
+        std::fs::create_dir_all(config_dir.join("services")).unwrap();
+        let config_dir = std::fs::canonicalize(&config_dir).unwrap();
+        let root_path = config_dir.join("devplexer.yaml");
+        let services_path = config_dir.join("services/devplexer.yaml");
+        std::fs::write(
+            &root_path,
+            r#"
+namespace: root
+include: [services/devplexer.yaml]
+variables:
+  PORT: "8080"
+apps:
+  ui:
+    command: serve --port ${PORT}
+    deps: [api]
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            &services_path,
+            r#"
+namespace: ignored
+apps:
+  api:
+    command: api --port ${PORT}
+"#,
+        )
+        .unwrap();
+        let config = load_config(&root_path, None).unwrap();
+        assert_eq!(config.namespace, "root");
+        assert_eq!(config.app_order, vec!["ui", "api"]);
+        assert_eq!(config.apps[0].name, "api");
+        assert_eq!(
+            config.apps[0].working_directory,
+            config_dir.join("services")
+        );
+        assert_eq!(
+            config.apps[0].command,
+            ProgramCommand::Shell("api --port 8080".to_owned())
+        );
+
+        std::fs::write(
+            &services_path,
+            r#"
+apps:
+  ui:
+    command: other-ui
+"#,
+        )
+        .unwrap();
+        let err = load_config(&root_path, None).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "app \"ui\" is defined in both {} and {}",
+                root_path.display(),
+                services_path.display()
+            )
+        );
+
+        std::fs::write(
+            &services_path,
+            r#"
+include: [../devplexer.yaml]
+apps:
+  api:
+    command: api
+"#,
+        )
+        .unwrap();
+        let err = load_config(&root_path, None).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ConfigurationSettingsError>(),
+            Some(ConfigurationSettingsError::IncludeCycleError(_))
+        ));
     }
 }
@@ -0,0 +1,150 @@
+use std::{error::Error, path::Path};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DotenvError {
+    /// A line, numbered from 1, with no `=` in it.
+    InvalidLine(usize, String),
+    /// A line whose key can't be exported from a shell.
+    InvalidName(usize, String),
+}
+
+impl std::fmt::Display for DotenvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DotenvError::InvalidLine(number, line) => write!(
+                f,
+                "line {} is not a KEY=VALUE assignment: {:?}",
+                number, line
+            ),
+            DotenvError::InvalidName(number, name) => write!(
+                f,
+                "line {} sets {:?}, which is not a valid variable name",
+                number, name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DotenvError {}
+
+/// Whether `name` can be used with `export` in a POSIX shell.
+pub(crate) fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn unquote(value: &str) -> &str {
+    let is_quoted = value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')));
+    if is_quoted {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+/// Parses `KEY=VALUE` lines, skipping blank lines and `#` comments and
+/// allowing an optional leading `export`.
+pub(crate) fn parse_dotenv(contents: &str) -> Result<Vec<(String, String)>, DotenvError> {
+    let mut entries = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| DotenvError::InvalidLine(i + 1, line.to_owned()))?;
+        let key = key.trim();
+        if !is_valid_env_name(key) {
+            return Err(DotenvError::InvalidName(i + 1, key.to_owned()));
+        }
+        entries.push((key.to_owned(), unquote(value.trim()).to_owned()));
+    }
+    Ok(entries)
+}
+
+/// Reads and parses the file, naming it in any error.
+pub(crate) fn load_dotenv(path: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    parse_dotenv(&contents).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Merges the file's entries with the inline ones, inline entries winning.
+pub(crate) fn merge_env(
+    file_env: Vec<(String, String)>,
+    inline_env: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut merged: Vec<(String, String)> = file_env
+        .into_iter()
+        .filter(|(k, _)| !inline_env.iter().any(|(ik, _)| ik == k))
+        .collect();
+    merged.extend(inline_env.iter().cloned());
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dotenv::{DotenvError, merge_env, parse_dotenv};
+
+    #[test]
+    fn test_parse_dotenv() {
+        let contents = r#"
+# database settings
+DATABASE_URL=postgres://localhost/dev
+
+export SECRET_KEY="abc 123"
+  PORT = 3000
+"#;
+        assert_eq!(
+            parse_dotenv(contents).unwrap(),
+            vec![
+                (
+                    "DATABASE_URL".to_owned(),
+                    "postgres://localhost/dev".to_owned()
+                ),
+                ("SECRET_KEY".to_owned(), "abc 123".to_owned()),
+                ("PORT".to_owned(), "3000".to_owned()),
+            ]
+        );
+        assert_eq!(
+            parse_dotenv("NOT A VALID LINE"),
+            Err(DotenvError::InvalidLine(1, "NOT A VALID LINE".to_owned()))
+        );
+        assert_eq!(
+            parse_dotenv("# ok\nPORT=3000\nfoo bar")
+                .unwrap_err()
+                .to_string(),
+            "line 3 is not a KEY=VALUE assignment: \"foo bar\""
+        );
+        assert_eq!(
+            parse_dotenv("MY-KEY=1").unwrap_err().to_string(),
+            "line 1 sets \"MY-KEY\", which is not a valid variable name"
+        );
+    }
+
+    #[test]
+    fn test_inline_env_overrides_file() {
+        let merged = merge_env(
+            vec![
+                ("PORT".to_owned(), "3000".to_owned()),
+                ("SECRET_KEY".to_owned(), "abc".to_owned()),
+            ],
+            &[("PORT".to_owned(), "4000".to_owned())],
+        );
+        assert_eq!(
+            merged,
+            vec![
+                ("SECRET_KEY".to_owned(), "abc".to_owned()),
+                ("PORT".to_owned(), "4000".to_owned()),
+            ]
+        );
+    }
+}
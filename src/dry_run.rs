@@ -0,0 +1,126 @@
+use std::error::Error;
+
+use crate::{
+    config::{Configuration, tab_title},
+    scheduler::StartupScheduler,
+    tmux::{LaunchContext, attach_session_command_for_cli, command_line, session_command},
+};
+
+/// What a run would do, in order, for `--dry-run`: the tmux command that
+/// starts each app's session, in the steps `deps` allow, and the tab each
+/// app would get. Nothing is run, so readiness is assumed and `launch_if`
+/// checks and hooks are only listed.
+pub(crate) fn dry_run_plan(
+    config: &Configuration,
+    launch_ctx: &LaunchContext,
+    reclaim: bool,
+    tmux_windows: bool,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut plan = Vec::new();
+    if reclaim {
+        plan.push(format!(
+            "# kill leftover sessions named {}-*",
+            launch_ctx.namespace
+        ));
+    }
+    if let Some(hook) = config.before_start.as_ref() {
+        plan.push(format!(
+            "# run before_start in {}: {}",
+            hook.working_directory.display(),
+            hook.command.to_shell_string()
+        ));
+    }
+    let mut scheduler = StartupScheduler::new(&config.apps);
+    let mut step = 0;
+    loop {
+        let launchable = scheduler.take_launchable();
+        if launchable.is_empty() {
+            break;
+        }
+        step += 1;
+        plan.push(format!("# step {}", step));
+        for spec in launchable.iter() {
+            if let Some(check) = spec.launch_if.as_ref() {
+                plan.push(format!("# start {} only if `{}` exits 0", spec.name, check));
+            }
+            if let Some(address) = spec.wait_port_free.as_ref() {
+                plan.push(format!("# wait for nothing to listen on {}", address));
+            }
+            let (started, cmd) = session_command(launch_ctx, spec)?;
+            plan.push(command_line(&cmd));
+            let title = tab_title(&config.tab_title_template, &spec.name);
+            let attach = attach_session_command_for_cli(
+                &launch_ctx.server,
+                &started.session_name,
+                spec.attach_readonly,
+            )?;
+            plan.push(if tmux_windows {
+                format!(
+                    "# window \"{}\" in session {}: {}",
+                    title, launch_ctx.namespace, attach
+                )
+            } else {
+                format!("# tab \"{}\": {}", title, attach)
+            });
+        }
+        for spec in launchable.iter() {
+            if spec.oneshot {
+                scheduler.mark_completed(&spec.name);
+            } else {
+                scheduler.mark_ready(&spec.name);
+            }
+        }
+    }
+    if let Some(hook) = config.after_stop.as_ref() {
+        plan.push(format!(
+            "# on shutdown, run after_stop in {}: {}",
+            hook.working_directory.display(),
+            hook.command.to_shell_string()
+        ));
+    }
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        config::string_to_config,
+        dry_run::dry_run_plan,
+        tmux::{LaunchContext, TmuxServer},
+    };
+
+    #[test]
+    fn test_dry_run_plan_follows_deps() {
+        let config_content = r#"
+namespace: ns
+apps:
+  api:
+    command: ./api
+    deps: [migrate]
+  migrate:
+    command: ./migrate
+    oneshot: true
+"#;
+        let config = string_to_config(Path::new("/srv"), config_content).unwrap();
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: true,
+            server: TmuxServer::new(Some("dev".to_owned())),
+        };
+        let plan = dry_run_plan(&config, &ctx, true, false).unwrap();
+        assert_eq!(
+            plan,
+            vec![
+                "# kill leftover sessions named ns-*",
+                "# step 1",
+                "tmux -L dev new -d -c /srv -s ns-migrate 'tmux set-window-option -t ns-migrate remain-on-exit on; ./migrate'",
+                "# tab \"migrate\": tmux -L dev attach -d -t ns-migrate",
+                "# step 2",
+                "tmux -L dev new -d -c /srv -s ns-api 'tmux set-window-option -t ns-api remain-on-exit on; ./api'",
+                "# tab \"api\": tmux -L dev attach -d -t ns-api",
+            ]
+        );
+    }
+}
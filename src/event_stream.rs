@@ -0,0 +1,138 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    process::ExitStatus,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+use sysinfo::Pid;
+
+use crate::json::{json_number, json_string};
+
+/// Passed as the `--events-file` path to write events to stdout instead.
+pub(crate) const STDOUT_SENTINEL: &str = "-";
+
+/// One app lifecycle change, written as a single line of JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LifecycleEvent {
+    ProcessEnded {
+        app_name: String,
+        session_name: String,
+        pid: Pid,
+        exit_status: Option<ExitStatus>,
+    },
+    Restart {
+        app_name: String,
+        session_name: String,
+        pid: Pid,
+    },
+    Quit,
+}
+
+impl LifecycleEvent {
+    /// Serializes the event with `at` as milliseconds since the Unix epoch.
+    pub(crate) fn to_json(&self, at: SystemTime) -> String {
+        let timestamp = at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        match self {
+            LifecycleEvent::ProcessEnded {
+                app_name,
+                session_name,
+                pid,
+                exit_status,
+            } => format!(
+                "{{\"timestamp\":{},\"event\":\"process_ended\",\"app\":{},\"session\":{},\"pid\":{},\"exit_code\":{},\"signal\":{}}}",
+                timestamp,
+                json_string(app_name),
+                json_string(session_name),
+                pid,
+                json_number(exit_status.and_then(|es| es.code())),
+                json_number(exit_status.and_then(|es| es.signal())),
+            ),
+            LifecycleEvent::Restart {
+                app_name,
+                session_name,
+                pid,
+            } => format!(
+                "{{\"timestamp\":{},\"event\":\"restart\",\"app\":{},\"session\":{},\"pid\":{}}}",
+                timestamp,
+                json_string(app_name),
+                json_string(session_name),
+                pid,
+            ),
+            LifecycleEvent::Quit => {
+                format!("{{\"timestamp\":{},\"event\":\"quit\"}}", timestamp)
+            }
+        }
+    }
+}
+
+/// Appends lifecycle events as newline-delimited JSON.
+pub(crate) struct EventStream {
+    out: Box<dyn Write>,
+}
+
+impl EventStream {
+    /// Opens `path` for appending, or stdout for `STDOUT_SENTINEL`.
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let out: Box<dyn Write> = if path.as_os_str() == STDOUT_SENTINEL {
+            Box::new(io::stdout())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+        Ok(EventStream { out })
+    }
+
+    pub(crate) fn emit(&mut self, event: &LifecycleEvent) {
+        let line = event.to_json(SystemTime::now());
+        if let Err(e) = writeln!(self.out, "{}", line).and_then(|_| self.out.flush()) {
+            warn!("Could not write lifecycle event: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        os::unix::process::ExitStatusExt,
+        process::ExitStatus,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use sysinfo::Pid;
+
+    use crate::event_stream::LifecycleEvent;
+
+    #[test]
+    fn test_lifecycle_event_json() {
+        let at = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let ended = LifecycleEvent::ProcessEnded {
+            app_name: "web".to_owned(),
+            session_name: "ns-web".to_owned(),
+            pid: Pid::from_u32(42),
+            exit_status: Some(ExitStatus::from_raw(3 << 8)),
+        };
+        assert_eq!(
+            ended.to_json(at),
+            r#"{"timestamp":1700000000123,"event":"process_ended","app":"web","session":"ns-web","pid":42,"exit_code":3,"signal":null}"#
+        );
+        let restart = LifecycleEvent::Restart {
+            app_name: "say \"hi\"".to_owned(),
+            session_name: "ns-say".to_owned(),
+            pid: Pid::from_u32(7),
+        };
+        assert_eq!(
+            restart.to_json(at),
+            r#"{"timestamp":1700000000123,"event":"restart","app":"say \"hi\"","session":"ns-say","pid":7}"#
+        );
+        assert_eq!(
+            LifecycleEvent::Quit.to_json(at),
+            r#"{"timestamp":1700000000123,"event":"quit"}"#
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::{collections::VecDeque, time::SystemTime};
+
+pub(crate) const EVENT_HISTORY_CAPACITY: usize = 200;
+
+/// A single lifecycle transition shown in the event timeline.
+#[derive(Debug, Clone)]
+pub(crate) struct EventRecord {
+    pub(crate) at: SystemTime,
+    pub(crate) app_name: String,
+    pub(crate) message: String,
+}
+
+impl EventRecord {
+    pub(crate) fn new(app_name: &str, message: &str) -> Self {
+        EventRecord {
+            at: SystemTime::now(),
+            app_name: app_name.to_owned(),
+            message: message.to_owned(),
+        }
+    }
+
+    /// Formats the record with its time relative to `started_at`, e.g. `[+00:01:05] api died (exit 1)`.
+    pub(crate) fn to_line(&self, started_at: SystemTime) -> String {
+        let secs = self
+            .at
+            .duration_since(started_at)
+            .unwrap_or_default()
+            .as_secs();
+        format!(
+            "[+{:02}:{:02}:{:02}] {} {}",
+            secs / 3600,
+            (secs / 60) % 60,
+            secs % 60,
+            self.app_name,
+            self.message
+        )
+    }
+}
+
+pub(crate) fn push_event_record(history: &mut VecDeque<EventRecord>, record: EventRecord) {
+    if history.len() >= EVENT_HISTORY_CAPACITY {
+        history.pop_front();
+    }
+    history.push_back(record);
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::VecDeque,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::history::{EVENT_HISTORY_CAPACITY, EventRecord, push_event_record};
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = VecDeque::new();
+        for i in 0..(EVENT_HISTORY_CAPACITY + 5) {
+            push_event_record(
+                &mut history,
+                EventRecord::new(&format!("app{}", i), "started"),
+            );
+        }
+        assert_eq!(history.len(), EVENT_HISTORY_CAPACITY);
+        assert_eq!(history.front().unwrap().app_name, "app5");
+    }
+
+    #[test]
+    fn test_record_line_format() {
+        let started_at = SystemTime::now();
+        let record = EventRecord {
+            at: started_at + Duration::from_secs(3725),
+            app_name: "api".to_owned(),
+            message: "died (exit 1)".to_owned(),
+        };
+        assert_eq!(record.to_line(started_at), "[+01:02:05] api died (exit 1)");
+    }
+}
@@ -0,0 +1,84 @@
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::{apps::describe_exit, config::Hook};
+
+/// Why a `before_start` or `after_stop` hook didn't succeed.
+#[derive(Debug)]
+pub(crate) enum HookError {
+    /// The hook ran and exited unsuccessfully.
+    Failed(String, ExitStatus),
+    /// The hook couldn't be run at all, e.g. a missing working directory.
+    CouldNotRun(String, String),
+}
+
+impl std::fmt::Display for HookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookError::Failed(command, status) => write!(
+                f,
+                "`{}` failed with {}",
+                command,
+                describe_exit(Some(*status))
+            ),
+            HookError::CouldNotRun(command, reason) => {
+                write!(f, "`{}` could not be run: {}", command, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Runs a hook to completion in its working directory. Its output goes to
+/// devplexer's own stdout and stderr unless `quiet`, for when the terminal
+/// UI owns the screen.
+pub(crate) fn run_hook(hook: &Hook, quiet: bool) -> Result<(), HookError> {
+    let command = hook.command.to_shell_string();
+    let output = || {
+        if quiet {
+            Stdio::null()
+        } else {
+            Stdio::inherit()
+        }
+    };
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .current_dir(&hook.working_directory)
+        .stdin(Stdio::null())
+        .stdout(output())
+        .stderr(output())
+        .status()
+        .map_err(|e| HookError::CouldNotRun(command.clone(), e.to_string()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(HookError::Failed(command, status))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{
+        config::{Hook, ProgramCommand},
+        hooks::run_hook,
+    };
+
+    #[test]
+    fn test_run_hook() {
+        let hook = |command: &str| Hook {
+            command: ProgramCommand::Shell(command.to_owned()),
+            working_directory: PathBuf::from("/"),
+        };
+        assert!(run_hook(&hook("test \"$(pwd)\" = /"), true).is_ok());
+        let err = run_hook(&hook("exit 3"), true).unwrap_err();
+        assert_eq!(err.to_string(), "`exit 3` failed with exit 3");
+        let missing = Hook {
+            working_directory: PathBuf::from("/no/such/dir"),
+            ..hook("true")
+        };
+        assert!(run_hook(&missing, true).is_err());
+    }
+}
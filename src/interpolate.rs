@@ -0,0 +1,85 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InterpolationError {
+    UndefinedVariable(String),
+    UnterminatedVariable(String),
+}
+
+impl std::fmt::Display for InterpolationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpolationError::UndefinedVariable(name) => write!(
+                f,
+                "${{{}}} is not defined in \"variables\" or the environment",
+                name
+            ),
+            InterpolationError::UnterminatedVariable(value) => {
+                write!(f, "\"${{\" without a closing \"}}\" in {:?}", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InterpolationError {}
+
+/// Replaces each `${NAME}` in `value` with `lookup(NAME)`, treating `$$` as
+/// a literal `$`. Any other `$` is left alone so plain shell variables pass
+/// through untouched.
+pub(crate) fn interpolate(
+    value: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, InterpolationError> {
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        result.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        if let Some(stripped) = after.strip_prefix('$') {
+            result.push('$');
+            rest = stripped;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| InterpolationError::UnterminatedVariable(value.to_owned()))?;
+            let name = &braced[..end];
+            let substitution = lookup(name)
+                .ok_or_else(|| InterpolationError::UndefinedVariable(name.to_owned()))?;
+            result.push_str(&substitution);
+            rest = &braced[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::interpolate::{InterpolationError, interpolate};
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "ROOT" => Some("/src/project".to_owned()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(
+            interpolate("cd ${ROOT}/ui && echo $$HOME $PATH", lookup).unwrap(),
+            "cd /src/project/ui && echo $HOME $PATH"
+        );
+        assert_eq!(
+            interpolate("${MISSING}", lookup),
+            Err(InterpolationError::UndefinedVariable("MISSING".to_owned()))
+        );
+        assert_eq!(
+            interpolate("${ROOT", lookup),
+            Err(InterpolationError::UnterminatedVariable(
+                "${ROOT".to_owned()
+            ))
+        );
+    }
+}
@@ -0,0 +1,330 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::apps::AppEvent;
+
+/// The actions whose keys can be changed under `keybindings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    Quit,
+    Restart,
+    RestartAll,
+    Kill,
+    Attach,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    Follow,
+    SelectNext,
+    SelectPrevious,
+}
+
+const ACTION_NAMES: [(&str, Action); 12] = [
+    ("quit", Action::Quit),
+    ("restart", Action::Restart),
+    ("restart_all", Action::RestartAll),
+    ("kill", Action::Kill),
+    ("attach", Action::Attach),
+    ("scroll_up", Action::ScrollUp),
+    ("scroll_down", Action::ScrollDown),
+    ("page_up", Action::PageUp),
+    ("page_down", Action::PageDown),
+    ("follow", Action::Follow),
+    ("select_next", Action::SelectNext),
+    ("select_previous", Action::SelectPrevious),
+];
+
+impl Action {
+    pub(crate) fn from_name(name: &str) -> Option<Action> {
+        ACTION_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, action)| *action)
+    }
+
+    fn name(self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, a)| *a == self)
+            .map(|(n, _)| *n)
+            .unwrap_or_default()
+    }
+
+    fn event(self) -> AppEvent {
+        match self {
+            Action::Quit => AppEvent::QuitKeyEvent,
+            Action::Restart => AppEvent::RestartSelectedApp,
+            Action::RestartAll => AppEvent::RestartAll,
+            Action::Kill => AppEvent::KillSelectedApp,
+            Action::Attach => AppEvent::AttachSelectedApp,
+            Action::ScrollUp => AppEvent::ScrollLogUp,
+            Action::ScrollDown => AppEvent::ScrollLogDown,
+            Action::PageUp => AppEvent::ScrollLogPageUp,
+            Action::PageDown => AppEvent::ScrollLogPageDown,
+            Action::Follow => AppEvent::FollowLog,
+            Action::SelectNext => AppEvent::SelectNextApp,
+            Action::SelectPrevious => AppEvent::SelectPreviousApp,
+        }
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 16] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("shift-tab", KeyCode::BackTab),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("space", KeyCode::Char(' ')),
+];
+
+/// A key with the modifiers that must be held with it. Shift is implied by
+/// an uppercase character, so it isn't tracked separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Key {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Key {
+    const fn plain(code: KeyCode) -> Key {
+        Key {
+            code,
+            ctrl: false,
+            alt: false,
+        }
+    }
+
+    /// Parses a key name: a single character such as `q` or `R`, a named
+    /// key such as `up`, `pagedown`, `end` or `f5`, optionally prefixed with
+    /// `ctrl-` and/or `alt-`.
+    pub(crate) fn parse(name: &str) -> Option<Key> {
+        let mut rest = name;
+        let mut key = Key::plain(KeyCode::Null);
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl-") && rest.len() > 5 {
+                key.ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt-") && rest.len() > 4 {
+                key.alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        key.code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let lower = rest.to_ascii_lowercase();
+                match NAMED_KEYS.iter().find(|(n, _)| *n == lower) {
+                    Some((_, code)) => *code,
+                    None => KeyCode::F(
+                        lower
+                            .strip_prefix('f')?
+                            .parse()
+                            .ok()
+                            .filter(|n| (1..=12).contains(n))?,
+                    ),
+                }
+            }
+        };
+        Some(key)
+    }
+
+    fn matches(&self, event: &KeyEvent) -> bool {
+        self.code == event.code
+            && self.ctrl == event.modifiers.contains(KeyModifiers::CONTROL)
+            && self.alt == event.modifiers.contains(KeyModifiers::ALT)
+    }
+}
+
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl-")?;
+        }
+        if self.alt {
+            write!(f, "Alt-")?;
+        }
+        match self.code {
+            KeyCode::Char(c) if c.is_uppercase() => write!(f, "Shift-{}", c),
+            KeyCode::Char(c) => write!(f, "{}", c.to_uppercase()),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::BackTab => write!(f, "Shift-Tab"),
+            code => write!(f, "{}", code),
+        }
+    }
+}
+
+/// Keys the terminal UI handles itself, which can't be given to an action.
+const BUILT_IN_KEYS: [(&str, Key); 13] = [
+    (
+        "quit",
+        Key {
+            code: KeyCode::Char('c'),
+            ctrl: true,
+            alt: false,
+        },
+    ),
+    ("search", Key::plain(KeyCode::Char('/'))),
+    ("next_match", Key::plain(KeyCode::Char('n'))),
+    ("previous_match", Key::plain(KeyCode::Char('N'))),
+    ("search_case", Key::plain(KeyCode::Char('i'))),
+    ("cancel_search", Key::plain(KeyCode::Esc)),
+    ("view", Key::plain(KeyCode::Char('v'))),
+    ("wrap", Key::plain(KeyCode::Char('w'))),
+    ("events", Key::plain(KeyCode::Char('e'))),
+    ("command", Key::plain(KeyCode::Char('c'))),
+    ("clear_log", Key::plain(KeyCode::Char('C'))),
+    ("scroll_left", Key::plain(KeyCode::Left)),
+    ("scroll_right", Key::plain(KeyCode::Right)),
+];
+
+/// Which keys trigger each rebindable action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Keymap {
+    bindings: Vec<(Action, Key)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: vec![
+                (Action::Quit, Key::plain(KeyCode::Char('q'))),
+                (Action::Restart, Key::plain(KeyCode::Char('r'))),
+                (Action::RestartAll, Key::plain(KeyCode::Char('R'))),
+                (Action::Kill, Key::plain(KeyCode::Char('k'))),
+                (Action::Attach, Key::plain(KeyCode::Char('a'))),
+                (Action::ScrollUp, Key::plain(KeyCode::Up)),
+                (Action::ScrollDown, Key::plain(KeyCode::Down)),
+                (Action::PageUp, Key::plain(KeyCode::PageUp)),
+                (Action::PageDown, Key::plain(KeyCode::PageDown)),
+                (Action::Follow, Key::plain(KeyCode::Char('G'))),
+                (Action::Follow, Key::plain(KeyCode::End)),
+                (Action::SelectNext, Key::plain(KeyCode::Tab)),
+                (Action::SelectPrevious, Key::plain(KeyCode::BackTab)),
+            ],
+        }
+    }
+}
+
+impl Keymap {
+    /// Replaces the keys bound to `action`, failing with the action or
+    /// built-in key already using one of them.
+    pub(crate) fn bind(&mut self, action: Action, keys: &[Key]) -> Result<(), (Key, &'static str)> {
+        self.bindings.retain(|(a, _)| *a != action);
+        for key in keys.iter() {
+            if let Some((built_in, _)) = BUILT_IN_KEYS.iter().find(|(_, k)| k == key) {
+                return Err((*key, built_in));
+            }
+            if let Some((other, _)) = self.bindings.iter().find(|(_, k)| k == key) {
+                return Err((*key, other.name()));
+            }
+            self.bindings.push((action, *key));
+        }
+        Ok(())
+    }
+
+    /// The event for a key press, if it is bound to an action.
+    pub(crate) fn event_for(&self, event: &KeyEvent) -> Option<AppEvent> {
+        self.bindings
+            .iter()
+            .find(|(_, key)| key.matches(event))
+            .map(|(action, _)| action.event())
+    }
+
+    /// The first key bound to `action`, for the help bar.
+    pub(crate) fn label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, key)| key.to_string())
+            .unwrap_or_else(|| "-".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::{
+        apps::AppEvent,
+        keymap::{Action, Key, Keymap},
+    };
+
+    #[test]
+    fn test_parse_keys() {
+        let ctrl_x = Key::parse("ctrl-x").unwrap();
+        assert!(ctrl_x.matches(&KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+        assert!(!ctrl_x.matches(&KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE)));
+        assert_eq!(ctrl_x.to_string(), "Ctrl-X");
+        assert_eq!(Key::parse("PageDown").unwrap().to_string(), "PageDown");
+        assert_eq!(Key::parse("R").unwrap().to_string(), "Shift-R");
+        assert_eq!(Key::parse("f5").unwrap().to_string(), "F5");
+        assert_eq!(Key::parse("shift-tab").unwrap().to_string(), "Shift-Tab");
+        assert_eq!(Key::parse("-").unwrap().to_string(), "-");
+        for bad in ["", "qq", "f13", "hyper-q", "ctrl-nope"] {
+            assert_eq!(Key::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_rebinding() {
+        let mut keymap = Keymap::default();
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        let x = KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE);
+        assert!(matches!(keymap.event_for(&q), Some(AppEvent::QuitKeyEvent)));
+        keymap
+            .bind(Action::Quit, &[Key::parse("x").unwrap()])
+            .unwrap();
+        assert!(keymap.event_for(&q).is_none());
+        assert!(matches!(keymap.event_for(&x), Some(AppEvent::QuitKeyEvent)));
+        assert_eq!(keymap.label(Action::Quit), "X");
+        assert_eq!(
+            keymap.bind(Action::Kill, &[Key::parse("x").unwrap()]),
+            Err((Key::parse("x").unwrap(), "quit"))
+        );
+        for (name, built_in) in [("v", "view"), ("esc", "cancel_search"), ("ctrl-c", "quit")] {
+            let key = Key::parse(name).unwrap();
+            assert_eq!(keymap.bind(Action::Kill, &[key]), Err((key, built_in)));
+        }
+    }
+
+    #[test]
+    fn test_selecting_apps_with_arrow_keys() {
+        let mut keymap = Keymap::default();
+        let up = Key::parse("up").unwrap();
+        let down = Key::parse("down").unwrap();
+        keymap.bind(Action::ScrollUp, &[]).unwrap();
+        keymap.bind(Action::ScrollDown, &[]).unwrap();
+        keymap.bind(Action::SelectPrevious, &[up]).unwrap();
+        keymap.bind(Action::SelectNext, &[down]).unwrap();
+        assert!(matches!(
+            keymap.event_for(&KeyEvent::new(KeyCode::Down, KeyModifiers::NONE)),
+            Some(AppEvent::SelectNextApp)
+        ));
+        assert!(matches!(
+            keymap.event_for(&KeyEvent::new(KeyCode::Up, KeyModifiers::NONE)),
+            Some(AppEvent::SelectPreviousApp)
+        ));
+        assert!(
+            keymap
+                .event_for(&KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE))
+                .is_none()
+        );
+        assert_eq!(keymap.label(Action::SelectNext), "Down");
+    }
+}
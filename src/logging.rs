@@ -1,10 +1,13 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
     io::Write,
-    sync::{Arc, Mutex, mpsc::Sender},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock, mpsc::Sender},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use log::Log;
+use log::{LevelFilter, Log};
 use simplelog::{Config, WriteLogger};
 
 use crate::AppEvent;
@@ -55,45 +58,558 @@ impl<'a> Log for EventLogger<'a> {
         };
         self.write_logger.log(record);
         let ls = self.writer.lock().unwrap().clone();
-        let _ = self.event_sender.send(AppEvent::LogEvent(ls));
+        let _ = self.event_sender.send(AppEvent::LogEvent(None, ls));
     }
 
     fn flush(&self) {}
 }
 
+/// Size at which a log file under `log_dir` is rotated.
+pub(crate) const LOG_FILE_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// How many rotated files (`app.log.1` is the newest) are kept per log.
+pub(crate) const LOG_FILE_BACKUPS: usize = 3;
+
+/// An append-only log file that is rotated once it would grow past
+/// `max_bytes`, keeping `backups` older files alongside it.
+pub(crate) struct RotatingLogFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    backups: usize,
+}
+
+impl RotatingLogFile {
+    pub(crate) fn open(path: &Path, max_bytes: u64, backups: usize) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingLogFile {
+            path: path.to_path_buf(),
+            file,
+            written,
+            max_bytes,
+            backups,
+        })
+    }
+
+    fn backup_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        if self.backups == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            for n in (1..self.backups).rev() {
+                let from = self.backup_path(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.backup_path(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.backup_path(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Lines kept in the log pane when `log_lines` isn't configured.
+pub(crate) const DEFAULT_LOG_LINES: usize = 1000;
+
+/// How each line in the log pane is stamped with the time it arrived.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum LogTimestamps {
+    #[default]
+    None,
+    /// Time since launch, e.g. `[+1.234s]`.
+    Elapsed,
+    /// UTC wall-clock time, e.g. `[14:03:07]`, matching devplexer's own log.
+    Clock,
+}
+
+/// The prefix for a line arriving `elapsed` after launch, at `now`.
+pub(crate) fn format_timestamp(
+    timestamps: LogTimestamps,
+    elapsed: Duration,
+    now: SystemTime,
+) -> String {
+    match timestamps {
+        LogTimestamps::None => String::new(),
+        LogTimestamps::Elapsed => {
+            format!("[+{}.{:03}s] ", elapsed.as_secs(), elapsed.subsec_millis())
+        }
+        LogTimestamps::Clock => {
+            let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+            format!(
+                "[{:02}:{:02}:{:02}] ",
+                secs / 3600,
+                (secs / 60) % 60,
+                secs % 60
+            )
+        }
+    }
+}
+
+/// A ring buffer of the most recent log lines.
 pub(crate) struct LogBuffer {
-    pub(crate) data_queue: VecDeque<u8>,
+    lines: VecDeque<String>,
+    capacity: usize,
+    dedup: bool,
+    timestamps: LogTimestamps,
+    started_at: Instant,
+    /// The last finished line, which a repeat is compared against.
+    last_line: Option<String>,
+    last_line_stamp: String,
+    last_line_repeats: usize,
+    /// A line still waiting for its `\n`, shown as the newest entry.
+    partial: Option<String>,
+    partial_stamp: String,
 }
 
 impl LogBuffer {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(capacity: usize, dedup: bool) -> Self {
         LogBuffer {
-            data_queue: VecDeque::with_capacity(512),
+            lines: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            dedup,
+            timestamps: LogTimestamps::None,
+            started_at: Instant::now(),
+            last_line: None,
+            last_line_stamp: String::new(),
+            last_line_repeats: 0,
+            partial: None,
+            partial_stamp: String::new(),
         }
     }
 
-    pub(crate) fn write_data(&mut self, data: &Vec<u8>) {
-        if data.len() > 512 {
-            self.data_queue.clear();
-            let start_n = data.len() - 512;
-            self.data_queue.write_all(&data[start_n..]).unwrap();
-        } else if self.data_queue.len() + data.len() > 512 {
-            let dropped_length = (self.data_queue.len() + data.len()) - 512;
-            self.data_queue.drain(0..dropped_length);
-            self.data_queue.write_all(data.as_slice()).unwrap();
-        } else {
-            self.data_queue.write_all(data.as_slice()).unwrap();
+    /// Stamps each new line, with elapsed times counted from `started_at`.
+    pub(crate) fn with_timestamps(
+        mut self,
+        timestamps: LogTimestamps,
+        started_at: Instant,
+    ) -> Self {
+        self.timestamps = timestamps;
+        self.started_at = started_at;
+        self
+    }
+
+    /// Appends log output, returning how many entries it added, less any
+    /// partial line that was folded into a repeat once it ended.
+    pub(crate) fn write_data(&mut self, data: &[u8]) -> isize {
+        let text = String::from_utf8_lossy(data);
+        let mut added = 0;
+        for chunk in text.split_inclusive('\n') {
+            let (content, terminated) = match chunk.strip_suffix('\n') {
+                Some(c) => (c.strip_suffix('\r').unwrap_or(c), true),
+                None => (chunk, false),
+            };
+            let started = self.partial.is_none();
+            let mut line = self.partial.take().unwrap_or_default();
+            line.push_str(content);
+            if terminated && self.is_repeat(&line, !started) {
+                if !started {
+                    self.lines.pop_back();
+                    added -= 1;
+                }
+                self.collapse_repeat(&line);
+                continue;
+            }
+            if started {
+                added += self.start_line();
+            }
+            if let Some(last) = self.lines.back_mut() {
+                *last = format!("{}{}", self.partial_stamp, line);
+            }
+            if terminated {
+                self.last_line = Some(line);
+                self.last_line_stamp = self.partial_stamp.clone();
+                self.last_line_repeats = 1;
+            } else {
+                self.partial = Some(line);
+            }
+        }
+        added
+    }
+
+    /// Appends whole lines with `prefix` at the start of each.
+    pub(crate) fn write_prefixed(&mut self, prefix: &str, lines: &[String]) -> isize {
+        let mut prefixed = String::new();
+        for line in lines.iter() {
+            prefixed.push_str(prefix);
+            prefixed.push_str(line);
+            prefixed.push('\n');
+        }
+        self.write_data(prefixed.as_bytes())
+    }
+
+    /// Whether a finished `line` should be collapsed into the entry before
+    /// it, which must still be there after dropping the line's own entry.
+    fn is_repeat(&self, line: &str, has_own_entry: bool) -> bool {
+        self.dedup
+            && self.last_line.as_deref() == Some(line)
+            && self.lines.len() > usize::from(has_own_entry)
+    }
+
+    /// Bumps the `(xN)` counter on the previous entry for a repeat of it.
+    fn collapse_repeat(&mut self, line: &str) {
+        self.last_line_repeats += 1;
+        if let Some(last) = self.lines.back_mut() {
+            *last = format!(
+                "{}{} (x{})",
+                self.last_line_stamp, line, self.last_line_repeats
+            );
         }
     }
+
+    /// Starts a new, empty entry. A line is stamped when it starts, so the
+    /// rest of a partial line doesn't move it.
+    fn start_line(&mut self) -> isize {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.partial_stamp = format_timestamp(
+            self.timestamps,
+            self.started_at.elapsed(),
+            SystemTime::now(),
+        );
+        self.lines.push_back(String::new());
+        1
+    }
+
+    pub(crate) fn lines(&self) -> &VecDeque<String> {
+        &self.lines
+    }
+
+    /// Drops every line, so the next output starts a fresh line.
+    pub(crate) fn clear(&mut self) {
+        self.lines.clear();
+        self.last_line = None;
+        self.last_line_repeats = 0;
+        self.partial = None;
+    }
 }
 
-fn create_event_logger(aes: &'static Sender<AppEvent>) -> &'static dyn Log {
-    let el = EventLogger::new(&aes);
-    Box::leak(Box::new(el))
+/// Longest partial line held back for a source before it is let through
+/// anyway, so output that never ends a line (e.g. a progress bar) shows up.
+const PARTIAL_LINE_MAX_BYTES: usize = 4096;
+
+/// Reassembles each source's output into whole lines, since captured output
+/// arrives in chunks that can end mid-line (or mid-character).
+#[derive(Default)]
+pub(crate) struct LineAssembler {
+    partial: HashMap<String, Vec<u8>>,
 }
 
-pub(crate) fn initialize_logger(aes: &'static Sender<AppEvent>) {
-    let logger = create_event_logger(aes);
-    log::set_logger(&*logger).unwrap();
-    log::set_max_level(log::LevelFilter::Info);
+impl LineAssembler {
+    /// Adds `data` from `source`, returning the lines it completed without
+    /// their line endings. A trailing partial line is kept for next time.
+    pub(crate) fn push(&mut self, source: &str, data: &[u8]) -> Vec<String> {
+        let buffered = self.partial.entry(source.to_owned()).or_default();
+        buffered.extend_from_slice(data);
+        let complete = match buffered.iter().rposition(|b| *b == b'\n') {
+            Some(end) => {
+                let rest = buffered.split_off(end + 1);
+                std::mem::replace(buffered, rest)
+            }
+            None if buffered.len() > PARTIAL_LINE_MAX_BYTES => std::mem::take(buffered),
+            None => return Vec::new(),
+        };
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+/// The level used when neither `--log-level` nor `RUST_LOG` sets one.
+pub(crate) const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LogLevelError {
+    InvalidLogLevel(String),
+}
+
+/// The levels `--log-level` and `RUST_LOG` accept, for error messages.
+pub(crate) const LOG_LEVEL_NAMES: &str = "off|error|warn|info|debug|trace";
+
+impl std::fmt::Display for LogLevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogLevelError::InvalidLogLevel(level) => write!(
+                f,
+                "invalid log level \"{}\" (expected {})",
+                level, LOG_LEVEL_NAMES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LogLevelError {}
+
+/// Parses a `RUST_LOG`-style spec: either a bare level such as `debug`, or
+/// comma-separated `target=level` directives of which only the bare ones and
+/// those for `devplexer` apply. Returns `None` when nothing applies.
+pub(crate) fn parse_log_level(spec: &str) -> Result<Option<LevelFilter>, LogLevelError> {
+    let mut level = None;
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let (target, value) = match directive.split_once('=') {
+            Some((target, value)) => (Some(target.trim()), value.trim()),
+            None => (None, directive),
+        };
+        let parsed = value
+            .parse::<LevelFilter>()
+            .map_err(|_e| LogLevelError::InvalidLogLevel(directive.to_owned()))?;
+        match target {
+            None => level = level.or(Some(parsed)),
+            Some(t) if t == "devplexer" || t.starts_with("devplexer::") => level = Some(parsed),
+            Some(_) => {}
+        }
+    }
+    Ok(level)
+}
+
+/// The process-wide logger; `log` needs it to live for the whole run.
+static EVENT_LOGGER: OnceLock<EventLogger<'static>> = OnceLock::new();
+
+/// Installs the logger that turns log records into `AppEvent`s, returning
+/// whether this call installed it. Later calls only change the level, so
+/// initializing again is harmless.
+pub(crate) fn initialize_logger(aes: &'static Sender<AppEvent>, level: LevelFilter) -> bool {
+    let mut created = false;
+    let logger = EVENT_LOGGER.get_or_init(|| {
+        created = true;
+        EventLogger::new(aes)
+    });
+    let installed = created && log::set_logger(logger).is_ok();
+    log::set_max_level(level);
+    installed
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write,
+        sync::mpsc::channel,
+        time::{Duration, Instant, UNIX_EPOCH},
+    };
+
+    use log::LevelFilter;
+
+    use crate::logging::{
+        LineAssembler, LogBuffer, LogLevelError, LogTimestamps, RotatingLogFile, format_timestamp,
+        initialize_logger, parse_log_level,
+    };
+
+    #[test]
+    fn test_initializing_logger_twice_is_a_no_op() {
+        let (first, _first_rx) = channel();
+        let (second, _second_rx) = channel();
+        assert!(initialize_logger(
+            Box::leak(Box::new(first)),
+            LevelFilter::Info
+        ));
+        assert!(!initialize_logger(
+            Box::leak(Box::new(second)),
+            LevelFilter::Debug
+        ));
+        assert_eq!(log::max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_parse_log_level() {
+        assert_eq!(parse_log_level("debug"), Ok(Some(LevelFilter::Debug)));
+        assert_eq!(parse_log_level("TRACE"), Ok(Some(LevelFilter::Trace)));
+        assert_eq!(
+            parse_log_level("hyper=warn,devplexer=trace"),
+            Ok(Some(LevelFilter::Trace))
+        );
+        assert_eq!(
+            parse_log_level("devplexer::tmux=debug,error"),
+            Ok(Some(LevelFilter::Debug))
+        );
+        assert_eq!(parse_log_level("hyper=warn"), Ok(None));
+        assert_eq!(parse_log_level(""), Ok(None));
+        assert_eq!(
+            parse_log_level("loud"),
+            Err(LogLevelError::InvalidLogLevel("loud".to_owned()))
+        );
+        assert_eq!(
+            parse_log_level("devplexer=loud").unwrap_err().to_string(),
+            "invalid log level \"devplexer=loud\" (expected off|error|warn|info|debug|trace)"
+        );
+    }
+
+    fn buffer_contents(lb: &LogBuffer) -> Vec<&str> {
+        lb.lines().iter().map(|l| l.as_str()).collect()
+    }
+
+    #[test]
+    fn test_dedup_collapses_repeated_lines() {
+        let mut lb = LogBuffer::new(10, true);
+        lb.write_data(b"retrying connection\n");
+        lb.write_data(b"retrying connection\n");
+        lb.write_data(b"retrying connection\n");
+        lb.write_data(b"connected\n");
+        assert_eq!(
+            buffer_contents(&lb),
+            vec!["retrying connection (x3)", "connected"]
+        );
+    }
+
+    #[test]
+    fn test_dedup_compares_lines_split_across_chunks() {
+        let mut lb = LogBuffer::new(10, true);
+        assert_eq!(lb.write_data(b"retry\n"), 1);
+        assert_eq!(lb.write_data(b"retry"), 1);
+        assert_eq!(buffer_contents(&lb), vec!["retry", "retry"]);
+        assert_eq!(lb.write_data(b"ing\n"), 0);
+        assert_eq!(buffer_contents(&lb), vec!["retry", "retrying"]);
+        assert_eq!(lb.write_data(b"retr"), 1);
+        assert_eq!(lb.write_data(b"ying\n"), -1);
+        assert_eq!(buffer_contents(&lb), vec!["retry", "retrying (x2)"]);
+    }
+
+    #[test]
+    fn test_without_dedup_keeps_repeated_lines() {
+        let mut lb = LogBuffer::new(10, false);
+        lb.write_data(b"retrying connection\n");
+        lb.write_data(b"retrying connection\n");
+        assert_eq!(
+            buffer_contents(&lb),
+            vec!["retrying connection", "retrying connection"]
+        );
+    }
+
+    #[test]
+    fn test_truncation_keeps_valid_utf8() {
+        let mut lb = LogBuffer::new(3, false);
+        for _ in 0..10 {
+            lb.write_data("é🚀 ".repeat(200).as_bytes());
+            lb.write_data(b"\n");
+        }
+        lb.write_data(&"🚀".as_bytes()[..2]);
+        assert_eq!(lb.lines().len(), 3);
+        assert_eq!(lb.lines()[0], "é🚀 ".repeat(200));
+        assert_eq!(lb.lines()[2], "\u{FFFD}");
+    }
+
+    #[test]
+    fn test_write_prefixed_marks_each_line() {
+        let mut lb = LogBuffer::new(10, false);
+        let lines = vec!["listening".to_owned(), "ready".to_owned()];
+        assert_eq!(lb.write_prefixed("[web] ", &lines), 2);
+        lb.write_data(b"next\n");
+        assert_eq!(
+            buffer_contents(&lb),
+            vec!["[web] listening", "[web] ready", "next"]
+        );
+    }
+
+    #[test]
+    fn test_line_assembler_joins_lines_split_across_chunks() {
+        let mut assembler = LineAssembler::default();
+        assert_eq!(assembler.push("web", b"listen"), Vec::<String>::new());
+        assert_eq!(assembler.push("db", b"ready\n"), vec!["ready"]);
+        assert_eq!(
+            assembler.push("web", b"ing on 3000\r\nGET /\nGET"),
+            vec!["listening on 3000", "GET /"]
+        );
+        assert_eq!(assembler.push("web", b" /about\n"), vec!["GET /about"]);
+        // A multi-byte character split between chunks survives intact.
+        let snowman = "☃\n".as_bytes();
+        assert_eq!(assembler.push("web", &snowman[..1]), Vec::<String>::new());
+        assert_eq!(assembler.push("web", &snowman[1..]), vec!["☃"]);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let now = UNIX_EPOCH + Duration::from_secs(3 * 86400 + 14 * 3600 + 3 * 60 + 7);
+        let elapsed = Duration::from_millis(61_234);
+        assert_eq!(format_timestamp(LogTimestamps::None, elapsed, now), "");
+        assert_eq!(
+            format_timestamp(LogTimestamps::Elapsed, elapsed, now),
+            "[+61.234s] "
+        );
+        assert_eq!(
+            format_timestamp(LogTimestamps::Clock, elapsed, now),
+            "[14:03:07] "
+        );
+    }
+
+    #[test]
+    fn test_timestamps_stamp_each_line_once() {
+        let mut lb =
+            LogBuffer::new(10, true).with_timestamps(LogTimestamps::Elapsed, Instant::now());
+        lb.write_data(b"compil");
+        lb.write_data(b"ing\nretry\nretry\n");
+        let lines = buffer_contents(&lb);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("[+0."));
+        assert!(lines[0].ends_with("s] compiling"));
+        assert_eq!(lines[0].matches("[+").count(), 1);
+        assert!(lines[1].ends_with("s] retry (x2)"));
+    }
+
+    #[test]
+    fn test_clear_starts_fresh() {
+        let mut lb = LogBuffer::new(10, true);
+        lb.write_data(b"failed to bind\npartial");
+        lb.clear();
+        assert!(lb.lines().is_empty());
+        assert_eq!(lb.write_data(b"failed to bind\n"), 1);
+        assert_eq!(buffer_contents(&lb), vec!["failed to bind"]);
+    }
+
+    #[test]
+    fn test_evicts_oldest_lines_at_capacity() {
+        let mut lb = LogBuffer::new(2, false);
+        assert_eq!(lb.write_data(b"one\ntwo\nthree\n"), 3);
+        assert_eq!(lb.write_data(b"four, part"), 1);
+        assert_eq!(lb.write_data(b" two\n"), 0);
+        assert_eq!(buffer_contents(&lb), vec!["three", "four, part two"]);
+    }
+
+    #[test]
+    fn test_rotating_log_file_keeps_backups() {
+        let dir = std::env::temp_dir().join(format!("devplexer-rotate-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("web.log");
+        let mut log = RotatingLogFile::open(&path, 10, 2).unwrap();
+        for chunk in ["aaaaaaaa\n", "bbbbbbbb\n", "cccccccc\n", "dddddddd\n"] {
+            log.write_all(chunk.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("web.log"), "dddddddd\n");
+        assert_eq!(read("web.log.1"), "cccccccc\n");
+        assert_eq!(read("web.log.2"), "bbbbbbbb\n");
+        assert!(!dir.join("web.log.3").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
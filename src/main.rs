@@ -1,19 +1,50 @@
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{HashMap, HashSet, VecDeque},
     error::Error,
-    sync::mpsc::{Receiver, Sender},
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+    },
     thread::JoinHandle,
-    time::Duration,
+    time::{Duration, Instant, SystemTime},
 };
 
+mod ansi;
+
+mod capture;
+
+mod cli;
+
 mod config;
 
+mod dotenv;
+
+mod dry_run;
+
+mod event_stream;
+
+mod interpolate;
+
+mod keymap;
+
+mod json;
+
 mod apps;
 
-use log::{error, info};
+use log::{error, info, warn};
+
+mod history;
+
+mod hooks;
 
 mod logging;
 
+mod notify;
+
 use sysinfo::Pid;
 
 mod tabadapter;
@@ -22,81 +53,445 @@ mod tmux;
 
 mod processes;
 
+mod procfile;
+
+mod readiness;
+
+mod restart;
+
+mod scheduler;
+
+mod search;
+
+mod shell;
+
+mod signals;
+
+mod status_server;
+
+mod usage;
+
 use ratatui::{
-    crossterm::event::{self, Event, KeyCode},
+    DefaultTerminal,
+    crossterm::event::{self, Event, KeyCode, KeyModifiers},
     layout::{Constraint, Flex, Layout},
-    style::Stylize,
-    text::Text,
-    widgets::{Paragraph, Row, Table, Widget},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Paragraph, Row, Table, Widget, Wrap},
 };
 use std::sync::mpsc::channel;
 use std::thread;
 
 use crate::{
-    apps::{AppEvent, AppStatus, TryIntoWith, wait_for_term},
-    config::try_load_config,
-    logging::{LogBuffer, initialize_logger},
-    processes::kill_process,
-    tabadapter::{TabAdapter, choose_tab_adapter},
-    tmux::{RunningProgram, StartedProgram, cleanup_session, convert_pids},
+    ansi::{ansi_to_line, foreground_sgr, strip_ansi},
+    apps::{
+        AppEvent, AppStatus, TryIntoWith, completed_successfully, describe_exit, should_launch,
+        wait_for_term,
+    },
+    capture::{OutputCapture, remove_capture_dir, start_output_capture},
+    cli::{CliOptions, parse_args},
+    config::{
+        Configuration, ConfigurationSettingsError, GroupBy, Hook, ProgramSpec, StatusStyle,
+        ensure_paths_exist, tab_title, try_load_config, validate_config,
+    },
+    dry_run::dry_run_plan,
+    event_stream::{EventStream, LifecycleEvent},
+    history::{EventRecord, push_event_record},
+    hooks::run_hook,
+    keymap::{Action, Keymap},
+    logging::{
+        DEFAULT_LOG_LEVEL, LOG_FILE_BACKUPS, LOG_FILE_MAX_BYTES, LineAssembler, LogBuffer,
+        RotatingLogFile, initialize_logger, parse_log_level,
+    },
+    notify::{CrashNotification, send_notification},
+    processes::{ProcessIdentity, StopSettings, kill_process},
+    readiness::{PortWaitError, ensure_port_free, wait_for_ready},
+    restart::{RESTART_BACKOFF_CAP, restart_backoff, schedule_restart, should_restart},
+    scheduler::StartupScheduler,
+    search::{LogSearch, count_matching_lines, find_matching_line, highlight_matches},
+    signals::forward_quit_signals,
+    status_server::{AppSnapshot, StatusServer},
+    tabadapter::{AppTab, TabAdapter, TmuxWindowAdapter, choose_tab_adapter, tab_adapter_name},
+    tmux::{
+        LaunchContext, ProgramStartErrors, RunningProgram, StartedProgram, TmuxServer,
+        attach_session_command_for_cli, capture_pane_tail, cleanup_session, convert_pids,
+        ensure_available, reclaim_sessions, supports_remain_on_exit,
+    },
+    usage::{ResourceUsage, format_memory, start_usage_sampler},
 };
 
+/// Longest command shown in the detailed view's column; `c` shows it in full.
+const COMMAND_COLUMN_MAX_CHARS: usize = 40;
+
+/// Shortens `text` to at most `max_chars` characters, ending in `…` when cut.
+fn truncate_with_ellipsis(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// The file under `log_dir` that devplexer's own log lines are written to.
+const DEVPLEXER_LOG_NAME: &str = "devplexer";
+
+/// Columns moved per Left/Right press when the log pane isn't wrapping.
+const LOG_H_SCROLL_STEP: i16 = 8;
+
+/// Formats how long the session or an app has been up, e.g. `1h 2m 3s`.
+fn format_uptime(uptime: Duration) -> String {
+    let secs = uptime.as_secs();
+    format!("{}h {}m {}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Apps that die within this long of starting have their pane's last
+/// lines kept, since such deaths are usually a typo in the command.
+const STARTUP_OUTPUT_WINDOW: Duration = Duration::from_secs(5);
+
+/// How many of a quickly-dead pane's last lines are kept.
+const STARTUP_OUTPUT_LINES: usize = 10;
+
+/// How often the tab adapter is asked which tabs were closed by hand.
+const TAB_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Lines moved per PageUp/PageDown press in the log pane.
+const LOG_PAGE_SCROLL_LINES: isize = 10;
+
+/// Colors handed out in declaration order to apps without a `color`.
+const APP_PALETTE: [Color; 10] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Yellow,
+    Color::Blue,
+    Color::Green,
+    Color::LightCyan,
+    Color::LightMagenta,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightGreen,
+];
+
+/// Which columns the status table shows, cycled with `v`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ViewMode {
+    Compact,
+    Normal,
+    Detailed,
+}
+
+impl ViewMode {
+    fn next(self) -> Self {
+        match self {
+            ViewMode::Compact => ViewMode::Normal,
+            ViewMode::Normal => ViewMode::Detailed,
+            ViewMode::Detailed => ViewMode::Compact,
+        }
+    }
+}
+
 struct DisplayStatus<'a> {
     app_statuses: HashMap<String, AppStatus>,
+    app_specs: HashMap<String, ProgramSpec>,
+    /// App names in declaration order, which the status table follows.
+    app_order: Vec<String>,
+    /// App names in dependency order, the order they are launched in.
+    launch_order: Vec<String>,
+    view_mode: ViewMode,
+    group_by: Option<GroupBy>,
+    status_style: StatusStyle,
+    log_wrap: bool,
+    log_h_scroll: u16,
+    /// How far the unwrapped log can scroll right before only blank space
+    /// would show, as of the last render.
+    log_h_scroll_max: Cell<u16>,
+    /// How many lines the log pane is scrolled up from the tail; 0 follows it.
+    log_scroll: usize,
+    selected_app: Option<String>,
+    /// Apps stopped with `k` whose processes haven't exited yet.
+    stopping_apps: HashSet<String>,
+    restart_counts: HashMap<String, u32>,
+    consecutive_failures: HashMap<String, u32>,
+    running_since: HashMap<String, Instant>,
+    /// The pane's last lines for apps that died soon after starting.
+    startup_output: HashMap<String, Vec<String>>,
+    /// Apps given up on because they didn't start within `start_timeout_ms`.
+    timed_out_apps: HashSet<String>,
+    /// Apps waiting out their backoff before an automatic restart.
+    pending_restarts: HashSet<String>,
+    /// Set while every app is being stopped to be launched again with `R`.
+    restarting_all: bool,
+    event_history: VecDeque<EventRecord>,
+    show_event_history: bool,
+    show_command_detail: bool,
+    search: LogSearch,
+    /// The full command each app was last launched with, as sent to tmux.
+    launched_commands: HashMap<String, String>,
+    started_at: SystemTime,
     pid_map: HashMap<Pid, String>,
+    /// Start times recorded at launch, so a recycled pid is never signalled.
+    process_identities: HashMap<Pid, ProcessIdentity>,
+    /// The app each session belongs to, since session names are sanitized.
+    session_apps: HashMap<String, String>,
     outstanding_pids: Vec<Pid>,
+    /// The pids the usage sampler watches, kept in step with `outstanding_pids`.
+    tracked_pids: Arc<Mutex<Vec<Pid>>>,
+    usage: HashMap<Pid, ResourceUsage>,
+    usage_handle: Option<JoinHandle<()>>,
+    usage_signal_channel: Option<Sender<()>>,
     dead_sessions: Vec<String>,
+    /// Leave sessions that died before quitting open for inspection.
+    keep_dead_sessions: bool,
+    /// The sessions that were already dead when quitting began.
+    kept_sessions: Vec<String>,
     join_handles: Vec<JoinHandle<()>>,
     event_handle: Option<JoinHandle<()>>,
     event_signal_channel: Option<Sender<()>>,
     is_quiting: bool,
     killer_procs: Option<Vec<JoinHandle<()>>>,
     tab_adapter: Option<Box<dyn TabAdapter>>,
+    /// Apps whose tab was closed by hand, which `a` can reopen.
+    detached_tabs: HashSet<String>,
+    last_tab_check: Instant,
     child_event_listener: Receiver<AppEvent>,
     child_event_sender: &'a Sender<AppEvent>,
     logbuffer: LogBuffer,
+    /// Holds back each app's partial lines so merged output is prefixed
+    /// only at real line starts.
+    merged_lines: LineAssembler,
+    /// Each app's own captured output; `logbuffer` holds the merged stream.
+    app_logs: HashMap<String, LogBuffer>,
+    captures: HashMap<String, OutputCapture>,
+    capture_interval: Duration,
+    poll_interval: Duration,
+    tick_interval: Duration,
+    keymap: Keymap,
+    /// How each app last exited, for the status endpoint.
+    exit_statuses: HashMap<String, Option<ExitStatus>>,
+    status_server: Option<StatusServer>,
+    log_dir: Option<PathBuf>,
+    /// Open files under `log_dir` by app name, or `None` once a file has
+    /// failed so it isn't retried on every line.
+    log_files: HashMap<String, Option<RotatingLogFile>>,
+    tmux_server: TmuxServer,
+    tab_title_template: String,
+    after_stop: Option<Hook>,
+    /// Start each tier's apps one at a time rather than all at once.
+    sequential_launch: bool,
 }
 
 impl<'a> DisplayStatus<'a> {
     fn new(
         ta: Option<Box<dyn TabAdapter>>,
+        config: &Configuration,
         ces: &'a Sender<AppEvent>,
         cel: Receiver<AppEvent>,
     ) -> Self {
+        let launched_at = Instant::now();
         DisplayStatus {
-            app_statuses: HashMap::new(),
+            app_statuses: HashMap::from_iter(
+                config
+                    .apps
+                    .iter()
+                    .map(|s| (s.name.clone(), AppStatus::Started)),
+            ),
+            app_specs: HashMap::from_iter(config.apps.iter().map(|s| (s.name.clone(), s.clone()))),
+            app_order: config.app_order.clone(),
+            launch_order: config.apps.iter().map(|s| s.name.clone()).collect(),
+            view_mode: ViewMode::Normal,
+            group_by: config.group_by,
+            status_style: config.status_style,
+            log_wrap: true,
+            log_h_scroll: 0,
+            log_h_scroll_max: Cell::new(0),
+            log_scroll: 0,
+            selected_app: None,
+            stopping_apps: HashSet::new(),
+            restart_counts: HashMap::new(),
+            consecutive_failures: HashMap::new(),
+            running_since: HashMap::new(),
+            startup_output: HashMap::new(),
+            timed_out_apps: HashSet::new(),
+            pending_restarts: HashSet::new(),
+            restarting_all: false,
+            event_history: VecDeque::new(),
+            show_event_history: false,
+            show_command_detail: false,
+            search: LogSearch::default(),
+            launched_commands: HashMap::new(),
+            started_at: SystemTime::now(),
             outstanding_pids: Vec::new(),
+            tracked_pids: Arc::new(Mutex::new(Vec::new())),
+            usage: HashMap::new(),
+            usage_handle: None,
+            usage_signal_channel: None,
             pid_map: HashMap::new(),
+            process_identities: HashMap::new(),
+            session_apps: HashMap::new(),
             dead_sessions: Vec::new(),
+            keep_dead_sessions: false,
+            kept_sessions: Vec::new(),
             join_handles: Vec::new(),
             event_handle: None,
             event_signal_channel: None,
             is_quiting: false,
             killer_procs: None,
             tab_adapter: ta,
+            detached_tabs: HashSet::new(),
+            last_tab_check: Instant::now(),
             child_event_listener: cel,
             child_event_sender: ces,
-            logbuffer: LogBuffer::new(),
+            logbuffer: LogBuffer::new(config.log_lines, config.dedup_logs)
+                .with_timestamps(config.log_timestamps, launched_at),
+            merged_lines: LineAssembler::default(),
+            app_logs: HashMap::from_iter(config.apps.iter().map(|s| {
+                (
+                    s.name.clone(),
+                    LogBuffer::new(config.log_lines, config.dedup_logs)
+                        .with_timestamps(config.log_timestamps, launched_at),
+                )
+            })),
+            captures: HashMap::new(),
+            capture_interval: config.capture_interval,
+            poll_interval: config.poll_interval,
+            tick_interval: config.tick_interval,
+            keymap: config.keymap.clone(),
+            exit_statuses: HashMap::new(),
+            status_server: None,
+            log_dir: config.log_dir.clone(),
+            log_files: HashMap::new(),
+            tmux_server: TmuxServer::new(config.tmux_socket.clone()),
+            tab_title_template: config.tab_title_template.clone(),
+            after_stop: config.after_stop.clone(),
+            sequential_launch: false,
         }
     }
 
     fn mark_app_started(&mut self, app_name: &str) {
+        self.timed_out_apps.remove(app_name);
         self.app_statuses
             .insert(app_name.to_owned(), AppStatus::Started);
     }
 
-    fn mark_app_running(&mut self, app_name: &str, session_name: &str, pid: &Pid) {
-        self.outstanding_pids.push(pid.clone());
+    fn record_event(&mut self, app_name: &str, message: &str) {
+        push_event_record(&mut self.event_history, EventRecord::new(app_name, message));
+    }
+
+    fn mark_app_skipped(&mut self, app_name: &str) {
         self.app_statuses
-            .insert(app_name.to_owned(), AppStatus::Running(pid.clone()));
-        self.pid_map.insert(pid.clone(), session_name.to_owned());
+            .insert(app_name.to_owned(), AppStatus::Skipped);
+        self.record_event(app_name, "skipped");
     }
 
-    fn mark_app_dead(&mut self, app_name: &str, session_name: &str, pid: &Pid) {
+    /// Marks an app dead when its session couldn't be started or ended
+    /// before its pid was read, handing back the reason.
+    fn mark_app_failed_to_start(
+        &mut self,
+        app_name: &str,
+        reason: Box<dyn Error>,
+    ) -> Box<dyn Error> {
+        self.record_event(app_name, &format!("failed to start ({})", reason));
+        self.exit_statuses.insert(app_name.to_owned(), None);
         self.app_statuses
-            .insert(app_name.to_owned(), AppStatus::Dead(pid.clone()));
+            .insert(app_name.to_owned(), AppStatus::Dead(None));
+        self.clamp_selection();
+        reason
+    }
+
+    /// Marks an app dead after its launch took longer than its start timeout.
+    fn mark_app_timed_out(&mut self, app_name: &str, timeout: Duration) {
+        self.mark_app_failed_to_start(
+            app_name,
+            format!("timed out after {}ms", timeout.as_millis()).into(),
+        );
+        self.timed_out_apps.insert(app_name.to_owned());
+    }
+
+    fn mark_app_ready(&mut self, app_name: &str) {
+        if let Some(AppStatus::Starting(pid)) = self.app_statuses.get(app_name) {
+            let pid = *pid;
+            self.app_statuses
+                .insert(app_name.to_owned(), AppStatus::Running(pid));
+        }
+        self.record_event(app_name, "ready");
+    }
+
+    fn mark_app_running(&mut self, app_name: &str, session_name: &str, identity: &ProcessIdentity) {
+        let pid = &identity.pid;
+        self.process_identities.insert(*pid, *identity);
+        self.record_event(app_name, "started");
+        self.running_since
+            .insert(app_name.to_owned(), Instant::now());
+        self.startup_output.remove(app_name);
+        self.outstanding_pids.push(*pid);
+        self.sync_tracked_pids();
+        let has_probe = self
+            .app_specs
+            .get(app_name)
+            .is_some_and(|spec| spec.readiness.is_some());
+        let status = if has_probe {
+            AppStatus::Starting(*pid)
+        } else {
+            AppStatus::Running(*pid)
+        };
+        self.app_statuses.insert(app_name.to_owned(), status);
+        self.pid_map.insert(*pid, session_name.to_owned());
+        self.session_apps
+            .insert(session_name.to_owned(), app_name.to_owned());
+    }
+
+    fn mark_app_not_ready(&mut self, app_name: &str) {
+        if let Some(AppStatus::Starting(pid)) | Some(AppStatus::Running(pid)) =
+            self.app_statuses.get(app_name)
+        {
+            let pid = *pid;
+            self.app_statuses
+                .insert(app_name.to_owned(), AppStatus::NotReady(pid));
+            self.record_event(app_name, "not ready");
+        }
+    }
+
+    fn mark_app_dead(
+        &mut self,
+        app_name: &str,
+        session_name: &str,
+        pid: &Pid,
+        exit_status: Option<ExitStatus>,
+    ) {
+        let completed = self
+            .app_specs
+            .get(app_name)
+            .is_some_and(|spec| completed_successfully(spec, exit_status));
+        if completed {
+            self.record_event(app_name, "completed");
+        } else {
+            self.record_event(app_name, &format!("died ({})", describe_exit(exit_status)));
+        }
+        self.exit_statuses.insert(app_name.to_owned(), exit_status);
+        let status = if completed {
+            AppStatus::Completed(*pid)
+        } else {
+            AppStatus::Dead(Some(*pid))
+        };
+        self.app_statuses.insert(app_name.to_owned(), status);
         self.outstanding_pids.retain(|f| f != pid);
+        self.sync_tracked_pids();
         self.dead_sessions.push(session_name.to_owned());
+        self.clamp_selection();
+    }
+
+    fn sync_tracked_pids(&mut self) {
+        *self.tracked_pids.lock().unwrap() = self.outstanding_pids.clone();
+        self.usage
+            .retain(|pid, _| self.outstanding_pids.contains(pid));
+    }
+
+    fn record_usage(&mut self, samples: Vec<(Pid, ResourceUsage)>) {
+        for (pid, sample) in samples {
+            if self.outstanding_pids.contains(&pid) {
+                self.usage.insert(pid, sample);
+            }
+        }
     }
 
     fn enqueue_receiver(&mut self, recv: JoinHandle<()>) {
@@ -110,37 +505,106 @@ impl<'a> DisplayStatus<'a> {
         }
     }
 
-    fn start_running(&mut self, running_programs: &Vec<RunningProgram>) {
-        let (es, dc) = channel::<()>();
+    fn start_running(&mut self, running_programs: &[RunningProgram]) {
+        if running_programs.is_empty() {
+            return;
+        }
         if let Some(ta) = self.tab_adapter.as_mut() {
             for c in running_programs.iter() {
-                ta.open(&c.program.session_name);
+                self.detached_tabs.remove(&c.spec.name);
+                let tab = AppTab {
+                    session_name: &c.program.session_name,
+                    title: &tab_title(&self.tab_title_template, &c.spec.name),
+                    read_only: c.spec.attach_readonly,
+                };
+                if let Err(e) = ta.open(&tab) {
+                    error!(
+                        "Could not open a tab for {}, attach to tmux session {} by hand: {}",
+                        c.spec.name, c.program.session_name, e
+                    );
+                }
+            }
+            if let Err(e) = ta.after_all_open() {
+                warn!("Could not refocus the terminal after opening tabs: {}", e);
             }
-            ta.after_all_open();
         }
         for c in running_programs.iter() {
+            self.launched_commands
+                .insert(c.spec.name.clone(), c.program.command.clone());
             self.mark_app_running(
                 &c.spec.name,
                 &c.program.session_name,
-                &c.program.program_pid,
+                &c.program.program_identity,
+            );
+            self.enqueue_receiver(wait_for_term(self.child_event_sender, c));
+            self.start_capture(&c.spec.name, &c.program.session_name);
+            // Readiness probes finish on their own, so their handles are not joined on shutdown.
+            let _ = wait_for_ready(
+                self.child_event_sender,
+                &c.spec,
+                &self.tmux_server,
+                &c.program.session_name,
             );
-            self.enqueue_receiver(wait_for_term(&self.child_event_sender, &c));
         }
+    }
+
+    /// Captures the app's pane output, replacing any capture left from an
+    /// earlier run of the same app.
+    fn start_capture(&mut self, app_name: &str, session_name: &str) {
+        if let Some(old) = self.captures.remove(app_name) {
+            old.stop();
+        }
+        match start_output_capture(
+            self.child_event_sender,
+            &self.tmux_server,
+            app_name,
+            session_name,
+            self.capture_interval,
+        ) {
+            Ok(capture) => {
+                self.captures.insert(app_name.to_owned(), capture);
+            }
+            Err(e) => warn!("Could not capture output of {}: {}", app_name, e),
+        }
+    }
+
+    fn start_input(&mut self) {
+        let (es, dc) = channel::<()>();
         self.event_signal_channel = Some(es);
-        self.event_handle = Some(start_event_loop(&self.child_event_sender, dc));
+        self.event_handle = Some(start_event_loop(
+            self.child_event_sender,
+            self.poll_interval,
+            self.tick_interval,
+            self.keymap.clone(),
+            dc,
+        ));
+    }
+
+    fn start_usage_sampling(&mut self) {
+        let (us, ud) = channel::<()>();
+        self.usage_signal_channel = Some(us);
+        self.usage_handle = Some(start_usage_sampler(
+            self.child_event_sender,
+            self.tracked_pids.clone(),
+            ud,
+        ));
     }
 
     fn finish_running_with_adapter(&mut self) {
         if let Some(ta) = self.tab_adapter.as_mut() {
             info!("Shutting down adapter.");
-            ta.after_all_closed();
+            if let Err(e) = ta.after_all_closed() {
+                warn!("Could not shut down the terminal adapter: {}", e);
+            }
         }
     }
 
     fn shutdown_session(&mut self, session_name: &str) {
-        cleanup_session(session_name);
-        if let Some(ta) = self.tab_adapter.as_mut() {
-            ta.close(session_name);
+        cleanup_session(&self.tmux_server, session_name);
+        if let Some(ta) = self.tab_adapter.as_mut()
+            && let Err(e) = ta.close(session_name)
+        {
+            warn!("Could not close the tab for {}: {}", session_name, e);
         }
     }
 
@@ -151,6 +615,12 @@ impl<'a> DisplayStatus<'a> {
         if let Some(eh) = self.event_handle {
             let _ = eh.join();
         }
+        if let Some(usc) = self.usage_signal_channel {
+            let _ = usc.send(());
+        }
+        if let Some(uh) = self.usage_handle {
+            let _ = uh.join();
+        }
         if let Some(mut kp) = self.killer_procs {
             while !kp.is_empty() {
                 if let Some(kp_jh) = kp.pop() {
@@ -160,39 +630,820 @@ impl<'a> DisplayStatus<'a> {
         }
     }
 
+    /// The stop settings of the app running as `pid`, or the defaults.
+    fn stop_settings_for(&self, pid: &Pid) -> StopSettings {
+        self.app_statuses
+            .iter()
+            .find(|(_, status)| {
+                matches!(status, AppStatus::Starting(p) | AppStatus::Running(p) | AppStatus::NotReady(p) | AppStatus::Dead(Some(p)) | AppStatus::Completed(p) if p == pid)
+            })
+            .and_then(|(app_name, _)| self.app_specs.get(app_name))
+            .map(StopSettings::for_spec)
+            .unwrap_or_default()
+    }
+
+    fn identity_for(&self, pid: &Pid) -> ProcessIdentity {
+        self.process_identities
+            .get(pid)
+            .copied()
+            .unwrap_or(ProcessIdentity {
+                pid: *pid,
+                start_time: None,
+            })
+    }
+
+    /// Whether the event loop is done. After a quit that is once every app
+    /// has stopped. Otherwise it runs until nothing is running or due to
+    /// restart and, with the UI up, no app is left dead for the user to
+    /// restart, so stopping or losing the last app doesn't close the UI.
+    fn is_finished(&self) -> bool {
+        if !self.outstanding_pids.is_empty() {
+            return false;
+        }
+        if self.is_quiting {
+            return true;
+        }
+        let interactive = self.event_handle.is_some();
+        self.pending_restarts.is_empty()
+            && !(interactive
+                && self
+                    .app_statuses
+                    .values()
+                    .any(|s| matches!(s, AppStatus::Dead(_))))
+    }
+
     fn execute_quit(&mut self) {
         if !self.is_quiting {
             self.is_quiting = true;
+            self.pending_restarts.clear();
+            if self.keep_dead_sessions {
+                self.kept_sessions = self.dead_sessions.clone();
+            }
             info!("Shutting down tmux sessions and processes.");
             let mut kps = Vec::new();
             for p in self.outstanding_pids.iter() {
-                let the_process = p.clone();
+                let the_process = *p;
                 let session_name = self.pid_map.get(&the_process);
                 let owned_sn = session_name.map(|s| s.to_owned());
+                let settings = self.stop_settings_for(&the_process);
+                let identity = self.identity_for(&the_process);
+                let app_name = session_name.and_then(|s| self.session_apps.get(s));
                 info!(
-                    "Shutting down session named: {} - PID {}",
+                    "Shutting down session named: {} ({}) - PID {}",
                     session_name.unwrap_or(&"N/A".to_owned()),
+                    app_name.unwrap_or(&"N/A".to_owned()),
                     p
                 );
+                let server = self.tmux_server.clone();
                 kps.push(thread::spawn(move || {
-                    kill_process(&the_process, &owned_sn);
+                    kill_process(&server, &identity, &owned_sn, &settings);
                 }));
             }
             self.killer_procs = Some(kps);
         }
     }
 
-    fn add_log_entry(&mut self, data: &Vec<u8>) {
-        self.logbuffer.write_data(data);
+    fn cycle_view_mode(&mut self) {
+        self.view_mode = self.view_mode.next();
+    }
+
+    fn toggle_event_history(&mut self) {
+        self.show_event_history = !self.show_event_history;
+    }
+
+    fn toggle_command_detail(&mut self) {
+        self.show_command_detail = !self.show_command_detail;
+    }
+
+    /// Empties the log the pane is showing: the selected app's, or the
+    /// merged log when "All" is selected.
+    fn clear_viewed_log(&mut self) {
+        match self
+            .selected_app
+            .as_ref()
+            .and_then(|a| self.app_logs.get_mut(a))
+        {
+            Some(log) => log.clear(),
+            None => self.logbuffer.clear(),
+        }
+        self.log_scroll = 0;
+    }
+
+    fn start_search(&mut self) {
+        self.search.query.clear();
+        self.search.editing = true;
+    }
+
+    fn edit_search(&mut self, input: Option<char>) {
+        match input {
+            Some(c) => self.search.query.push(c),
+            None => {
+                self.search.query.pop();
+            }
+        }
+    }
+
+    fn submit_search(&mut self) {
+        self.search.editing = false;
+        let bottom = self.bottom_log_line();
+        // Start just below the bottom line so a match on it is found first.
+        self.jump_to_search_match(bottom + 1, false);
+    }
+
+    fn cancel_search(&mut self) {
+        self.search = LogSearch {
+            case_sensitive: self.search.case_sensitive,
+            ..Default::default()
+        };
+    }
+
+    fn toggle_search_case(&mut self) {
+        self.search.case_sensitive = !self.search.case_sensitive;
+    }
+
+    /// The index in the viewed log of the line at the bottom of the pane.
+    fn bottom_log_line(&self) -> usize {
+        self.viewed_log()
+            .lines()
+            .len()
+            .saturating_sub(1 + self.log_scroll)
+    }
+
+    /// Scrolls so the next match after, or previous match before, the line
+    /// at `from` sits at the bottom of the pane.
+    fn jump_to_search_match(&mut self, from: usize, forward: bool) {
+        let Some(query) = self.search.active_query() else {
+            return;
+        };
+        let lines = self.viewed_log().lines();
+        if let Some(i) = find_matching_line(lines, query, self.search.case_sensitive, from, forward)
+        {
+            self.log_scroll = lines.len() - 1 - i;
+        }
+    }
+
+    fn next_search_match(&mut self, forward: bool) {
+        let bottom = self.bottom_log_line();
+        self.jump_to_search_match(bottom, forward);
+    }
+
+    /// The log pane's title, which doubles as the search prompt.
+    fn log_title(&self) -> String {
+        let source = self.selected_app.as_deref().unwrap_or("All");
+        let case = if self.search.case_sensitive {
+            " [Aa]"
+        } else {
+            ""
+        };
+        if self.search.editing {
+            return format!("Log: {} | Search{}: {}_", source, case, self.search.query);
+        }
+        match self.search.active_query() {
+            Some(query) => {
+                let count = count_matching_lines(
+                    self.viewed_log().lines(),
+                    query,
+                    self.search.case_sensitive,
+                );
+                format!(
+                    "Log: {} | /{}{} ({} matching lines, N/n to jump)",
+                    source, query, case, count
+                )
+            }
+            None => format!("Log: {}", source),
+        }
+    }
+
+    /// The lines of the command detail panel for the selected app.
+    fn command_detail_lines(&self) -> Vec<String> {
+        let Some(app_name) = self.selected_app.as_deref() else {
+            return vec![format!(
+                "Select an app with {} to see its command.",
+                self.keymap.label(Action::SelectNext)
+            )];
+        };
+        let command = self
+            .launched_commands
+            .get(app_name)
+            .cloned()
+            .unwrap_or_else(|| "(not launched)".to_owned());
+        let directory = self
+            .app_specs
+            .get(app_name)
+            .map(|s| s.working_directory.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut lines = vec![
+            format!("Command: {}", command),
+            format!("Directory: {}", directory),
+        ];
+        if let Some(output) = self.startup_output.get(app_name) {
+            lines.push(String::new());
+            lines.push("Output before it exited:".to_owned());
+            lines.extend(output.iter().cloned());
+        }
+        lines
+    }
+
+    /// Keeps the pane's last lines if the app died soon after starting, so
+    /// the reason (e.g. `command not found`) can be read without attaching.
+    fn snapshot_startup_output(&mut self, app_name: &str, session_name: &str) {
+        let died_early = self
+            .running_since
+            .get(app_name)
+            .is_some_and(|t| t.elapsed() < STARTUP_OUTPUT_WINDOW);
+        if !died_early {
+            return;
+        }
+        if let Some(output) =
+            capture_pane_tail(&self.tmux_server, session_name, STARTUP_OUTPUT_LINES)
+        {
+            self.startup_output.insert(app_name.to_owned(), output);
+        }
+    }
+
+    fn toggle_log_wrap(&mut self) {
+        self.log_wrap = !self.log_wrap;
+        self.log_h_scroll = 0;
+    }
+
+    fn scroll_log_horizontal(&mut self, delta: i16) {
+        if !self.log_wrap {
+            self.log_h_scroll = self
+                .log_h_scroll
+                .saturating_add_signed(delta)
+                .min(self.log_h_scroll_max.get());
+        }
+    }
+
+    /// Whether the log pane is at the bottom, following new lines as they
+    /// arrive, rather than paused on scrolled-back history.
+    fn following_log(&self) -> bool {
+        self.log_scroll == 0
+    }
+
+    fn scroll_log_vertical(&mut self, delta: isize) {
+        let max_scroll = self.viewed_log().lines().len().saturating_sub(1);
+        self.log_scroll = self.log_scroll.saturating_add_signed(delta).min(max_scroll);
+    }
+
+    /// Appends output to its file under `log_dir`; devplexer's own log
+    /// lines go to `devplexer.log`.
+    fn write_log_file(&mut self, source: Option<&str>, data: &[u8]) {
+        let Some(log_dir) = self.log_dir.as_ref() else {
+            return;
+        };
+        let name = source.unwrap_or(DEVPLEXER_LOG_NAME);
+        let entry = self.log_files.entry(name.to_owned()).or_insert_with(|| {
+            let path = log_dir.join(format!("{}.log", name));
+            match RotatingLogFile::open(&path, LOG_FILE_MAX_BYTES, LOG_FILE_BACKUPS) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    warn!("Could not open log file {}: {}", path.display(), e);
+                    None
+                }
+            }
+        });
+        if let Some(file) = entry.as_mut()
+            && let Err(e) = file.write_all(data)
+        {
+            *entry = None;
+            warn!("Could not write the log file for {}: {}", name, e);
+        }
+    }
+
+    /// The app's configured `color`, or its palette color by declaration order.
+    fn app_color(&self, app_name: &str) -> Color {
+        self.app_specs
+            .get(app_name)
+            .and_then(|spec| spec.color)
+            .unwrap_or_else(|| {
+                let index = self
+                    .app_order
+                    .iter()
+                    .position(|a| a == app_name)
+                    .unwrap_or(0);
+                APP_PALETTE[index % APP_PALETTE.len()]
+            })
+    }
+
+    /// The `[app]` prefix for an app's lines in merged output, in the app's
+    /// color when `colored`.
+    fn log_prefix(&self, app_name: &str, colored: bool) -> String {
+        if colored {
+            format!(
+                "{}[{}]{} ",
+                foreground_sgr(self.app_color(app_name)),
+                app_name,
+                foreground_sgr(Color::Reset)
+            )
+        } else {
+            format!("[{}] ", app_name)
+        }
+    }
+
+    /// Records output everywhere it is kept, returning the whole lines it
+    /// completed for an app's merged output.
+    fn add_log_entry(&mut self, source: Option<&str>, data: &[u8]) -> Vec<String> {
+        self.write_log_file(source, data);
+        let completed = match source {
+            Some(app_name) => self.merged_lines.push(app_name, data),
+            None => Vec::new(),
+        };
+        let merged_added = match source {
+            Some(app_name) => {
+                let prefix = self.log_prefix(app_name, true);
+                self.logbuffer.write_prefixed(&prefix, &completed)
+            }
+            None => self.logbuffer.write_data(data),
+        };
+        let app_added = source
+            .and_then(|a| self.app_logs.get_mut(a))
+            .map(|l| l.write_data(data));
+        let viewed_added = match self.selected_app.as_deref() {
+            None => merged_added,
+            Some(selected) if source == Some(selected) => app_added.unwrap_or(0),
+            Some(_) => 0,
+        };
+        // Keep a scrolled-back view on the same lines as new ones arrive.
+        if self.log_scroll > 0 {
+            self.scroll_log_vertical(viewed_added);
+        }
+        completed
+    }
+
+    /// Moves the row selection through the table in display order. The
+    /// position before the first row is the "All" selection, whose log pane
+    /// shows every app's output merged.
+    fn select_app(&mut self, delta: isize) {
+        let mut choices: Vec<Option<String>> = vec![None];
+        choices.extend(
+            self.grouped_app_names()
+                .into_iter()
+                .flat_map(|(_, members)| members.into_iter().map(|m| Some(m.to_owned()))),
+        );
+        let current = choices
+            .iter()
+            .position(|c| *c == self.selected_app)
+            .unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(choices.len() as isize) as usize;
+        self.selected_app = choices[next].clone();
+        self.log_scroll = 0;
+    }
+
+    /// Keeps the selection and log scroll in range after apps die, restart,
+    /// or are stopped. The selection follows its app by name, so it stays on
+    /// an app that died; only one gone from the table falls back to "All".
+    fn clamp_selection(&mut self) {
+        if self
+            .selected_app
+            .as_ref()
+            .is_some_and(|a| !self.app_statuses.contains_key(a))
+        {
+            self.selected_app = None;
+            self.log_scroll = 0;
+        }
+        self.scroll_log_vertical(0);
+    }
+
+    /// The log shown in the log pane: the selected app's own output, or the
+    /// merged stream when no app is selected.
+    fn viewed_log(&self) -> &LogBuffer {
+        self.selected_app
+            .as_ref()
+            .and_then(|a| self.app_logs.get(a))
+            .unwrap_or(&self.logbuffer)
+    }
+
+    /// Relaunches a dead or completed app in a fresh session, cleaning up the old one
+    /// first so the session name is free.
+    fn restart_app(
+        &mut self,
+        app_name: &str,
+        launch_ctx: &LaunchContext,
+    ) -> Result<Option<RunningProgram>, Box<dyn Error>> {
+        let pid = match self.app_statuses.get(app_name) {
+            Some(AppStatus::Dead(pid)) => *pid,
+            Some(AppStatus::Completed(pid)) => Some(*pid),
+            _ => return Ok(None),
+        };
+        let Some(spec) = self.app_specs.get(app_name).cloned() else {
+            return Ok(None);
+        };
+        ensure_port_free(&spec)?;
+        if let Some(session_name) = pid.and_then(|pid| self.pid_map.remove(&pid)) {
+            self.dead_sessions.retain(|s| s != &session_name);
+            self.shutdown_session(&session_name);
+        }
+        self.stopping_apps.remove(app_name);
+        *self.restart_counts.entry(app_name.to_owned()).or_insert(0) += 1;
+        info!("Restarting {}.", app_name);
+        self.mark_app_started(app_name);
+        let started = (&spec)
+            .try_into_with(launch_ctx)
+            .map_err(|e| self.mark_app_failed_to_start(app_name, e))?;
+        let (running_programs, died) = convert_pids(&self.tmux_server, &[started])?;
+        if let Some((_spec, e)) = died.into_iter().next() {
+            return Err(self.mark_app_failed_to_start(app_name, e));
+        }
+        self.start_running(&running_programs);
+        self.clamp_selection();
+        Ok(running_programs.into_iter().next())
+    }
+
+    /// Schedules a relaunch of a dead app if its `restart` policy calls for
+    /// one, backing off exponentially while it keeps failing. An app that
+    /// ran for longer than the backoff cap starts over at the shortest delay.
+    /// Returns whether a restart was scheduled.
+    fn schedule_auto_restart(&mut self, app_name: &str, exit_status: Option<ExitStatus>) -> bool {
+        let Some(spec) = self.app_specs.get(app_name) else {
+            return false;
+        };
+        if self.is_quiting || self.restarting_all || !should_restart(spec.restart, exit_status) {
+            return false;
+        }
+        let ran_long = self
+            .running_since
+            .get(app_name)
+            .is_some_and(|t| t.elapsed() >= RESTART_BACKOFF_CAP);
+        let failures = self
+            .consecutive_failures
+            .entry(app_name.to_owned())
+            .or_insert(0);
+        *failures = if ran_long { 1 } else { *failures + 1 };
+        let delay = restart_backoff(*failures);
+        info!("Restarting {} in {}s.", app_name, delay.as_secs());
+        self.pending_restarts.insert(app_name.to_owned());
+        schedule_restart(self.child_event_sender, app_name, delay);
+        true
+    }
+
+    /// Stops a single running app, leaving the others alone. Its session is
+    /// cleaned up once the process has exited.
+    fn kill_app(&mut self, app_name: &str) {
+        let pid = match self.app_statuses.get(app_name) {
+            Some(AppStatus::Starting(pid))
+            | Some(AppStatus::Running(pid))
+            | Some(AppStatus::NotReady(pid)) => *pid,
+            _ => return,
+        };
+        let session_name = self.pid_map.get(&pid).cloned();
+        let settings = self.stop_settings_for(&pid);
+        let identity = self.identity_for(&pid);
+        info!("Stopping {}.", app_name);
+        self.record_event(app_name, "stopping");
+        self.stopping_apps.insert(app_name.to_owned());
+        let server = self.tmux_server.clone();
+        self.enqueue_receiver(thread::spawn(move || {
+            kill_process(&server, &identity, &session_name, &settings);
+        }));
+    }
+
+    /// Stops every live app one at a time in reverse dependency order, so
+    /// each is stopped before the apps it depends on, ahead of launching
+    /// them all again once `all_stopped_for_restart` says they're down.
+    fn restart_all(&mut self) {
+        if self.is_quiting || self.restarting_all {
+            return;
+        }
+        info!("Restarting all apps.");
+        self.restarting_all = true;
+        self.pending_restarts.clear();
+        let mut stops = Vec::new();
+        for app_name in self.launch_order.clone().iter().rev() {
+            let pid = match self.app_statuses.get(app_name) {
+                Some(AppStatus::Starting(pid))
+                | Some(AppStatus::Running(pid))
+                | Some(AppStatus::NotReady(pid)) => *pid,
+                _ => continue,
+            };
+            if !self.stopping_apps.insert(app_name.clone()) {
+                continue;
+            }
+            self.record_event(app_name, "stopping");
+            stops.push((
+                self.identity_for(&pid),
+                self.pid_map.get(&pid).cloned(),
+                self.stop_settings_for(&pid),
+            ));
+        }
+        let server = self.tmux_server.clone();
+        self.enqueue_receiver(thread::spawn(move || {
+            for (identity, session_name, settings) in stops.iter() {
+                kill_process(&server, identity, session_name, settings);
+            }
+        }));
+    }
+
+    /// Whether a restart of every app is waiting only on relaunching them.
+    fn all_stopped_for_restart(&self) -> bool {
+        self.restarting_all && self.stopping_apps.is_empty()
+    }
+
+    /// Cleans up the sessions left by the stopped apps and resets them to
+    /// be launched again. Their logs are kept.
+    fn finish_restart_all(&mut self) {
+        for session_name in std::mem::take(&mut self.dead_sessions).iter() {
+            self.shutdown_session(session_name);
+        }
+        self.pid_map.clear();
+        for app_name in self.launch_order.clone().iter() {
+            if matches!(
+                self.app_statuses.get(app_name),
+                Some(AppStatus::Dead(_) | AppStatus::Completed(_))
+            ) {
+                *self.restart_counts.entry(app_name.clone()).or_insert(0) += 1;
+            }
+            self.mark_app_started(app_name);
+        }
+        self.consecutive_failures.clear();
+        self.restarting_all = false;
+        self.clamp_selection();
+    }
+
+    /// Opens a new tab attached to a live app's session, for when its
+    /// original tab was closed.
+    fn attach_app(&mut self, app_name: &str) {
+        let pid = match self.app_statuses.get(app_name) {
+            Some(AppStatus::Starting(pid))
+            | Some(AppStatus::Running(pid))
+            | Some(AppStatus::NotReady(pid)) => *pid,
+            _ => return,
+        };
+        let Some(session_name) = self.pid_map.get(&pid).cloned() else {
+            return;
+        };
+        match self.tab_adapter.as_mut() {
+            Some(ta) => {
+                info!("Reattaching {}.", app_name);
+                let tab = AppTab {
+                    session_name: &session_name,
+                    title: &tab_title(&self.tab_title_template, app_name),
+                    read_only: self
+                        .app_specs
+                        .get(app_name)
+                        .is_some_and(|spec| spec.attach_readonly),
+                };
+                match ta.reopen(&tab) {
+                    Ok(()) => {
+                        self.detached_tabs.remove(app_name);
+                    }
+                    Err(e) => error!("Could not reattach {}: {}", app_name, e),
+                }
+            }
+            None => warn!("No terminal adapter to attach {} with.", app_name),
+        }
+    }
+
+    /// Asks the tab adapter, at most every `TAB_CHECK_INTERVAL`, which tabs
+    /// were closed by hand, marking their apps as detached.
+    fn check_for_closed_tabs(&mut self) {
+        if self.is_quiting || self.last_tab_check.elapsed() < TAB_CHECK_INTERVAL {
+            return;
+        }
+        self.last_tab_check = Instant::now();
+        let Some(ta) = self.tab_adapter.as_mut() else {
+            return;
+        };
+        for session_name in ta.closed_tabs() {
+            let Some(app_name) = self.session_apps.get(&session_name).cloned() else {
+                continue;
+            };
+            info!(
+                "The tab for {} was closed, press {} to reattach it.",
+                app_name,
+                self.keymap.label(Action::Attach)
+            );
+            self.detached_tabs.insert(app_name);
+        }
+    }
+
+    /// Whether the app exited because it was stopped with `k`, clearing the flag.
+    fn finish_stopping(&mut self, app_name: &str) -> bool {
+        self.stopping_apps.remove(app_name)
+    }
+
+    /// Hands the current app statuses to the status endpoint, if it's running.
+    fn publish_status(&self) {
+        let Some(server) = self.status_server.as_ref() else {
+            return;
+        };
+        let mut names: Vec<&String> = self.app_statuses.keys().collect();
+        names.sort_by_key(|name| {
+            self.app_order
+                .iter()
+                .position(|n| n == *name)
+                .unwrap_or(usize::MAX)
+        });
+        let snapshots: Vec<AppSnapshot> = names
+            .into_iter()
+            .map(|name| {
+                let status = &self.app_statuses[name];
+                AppSnapshot {
+                    name: name.to_owned(),
+                    pid: status.pid(),
+                    status: if self.timed_out_apps.contains(name) {
+                        "timed_out"
+                    } else {
+                        status.label()
+                    },
+                    up: matches!(
+                        status,
+                        AppStatus::Starting(_) | AppStatus::Running(_) | AppStatus::NotReady(_)
+                    ),
+                    restarts: self.restart_counts.get(name).copied().unwrap_or(0),
+                    exit_code: match status {
+                        AppStatus::Dead(_) | AppStatus::Completed(_) => self
+                            .exit_statuses
+                            .get(name)
+                            .copied()
+                            .flatten()
+                            .and_then(|es| es.code()),
+                        _ => None,
+                    },
+                }
+            })
+            .collect();
+        server.publish(&snapshots);
     }
 
-    fn finish_shutdown(mut self) {
+    /// Stops everything still running, returning the sessions left open
+    /// by `--keep-dead-sessions`.
+    fn finish_shutdown(mut self) -> Vec<String> {
+        if let Some(server) = self.status_server.take() {
+            server.stop();
+        }
         for sn in self.dead_sessions.clone().iter() {
-            self.shutdown_session(&sn);
+            if !self.kept_sessions.contains(sn) {
+                self.shutdown_session(sn);
+            }
+        }
+        for (_app_name, capture) in self.captures.drain() {
+            capture.stop();
         }
+        remove_capture_dir();
         self.finish_running_with_adapter();
         self.wait_for_handles();
+        if let Some(hook) = self.after_stop.as_ref() {
+            info!("Running after_stop.");
+            // The terminal UI still owns the screen, so its output is dropped.
+            if let Err(e) = run_hook(hook, self.event_handle.is_some()) {
+                error!("after_stop {}", e);
+            }
+        }
+        for (_name, file) in self.log_files.drain() {
+            if let Some(mut file) = file {
+                let _ = file.flush();
+            }
+        }
+        let kept_sessions = std::mem::take(&mut self.kept_sessions);
         self.shut_down_events();
+        kept_sessions
+    }
+}
+
+impl<'a> DisplayStatus<'a> {
+    /// The log lines that fit the pane, ending `log_scroll` lines above the
+    /// tail. Wrapped lines take as many rows as their width needs.
+    fn visible_log_lines(&self, width: u16, height: u16) -> Vec<&str> {
+        let mut rows_left = height as usize;
+        let mut visible = Vec::new();
+        for line in self.viewed_log().lines().iter().rev().skip(self.log_scroll) {
+            let rows = if self.log_wrap && width > 0 {
+                strip_ansi(line)
+                    .chars()
+                    .count()
+                    .div_ceil(width as usize)
+                    .max(1)
+            } else {
+                1
+            };
+            if rows > rows_left && !visible.is_empty() {
+                break;
+            }
+            rows_left = rows_left.saturating_sub(rows);
+            visible.push(line.as_str());
+        }
+        visible.reverse();
+        visible
+    }
+
+    /// Splits the apps into titled sections according to `group_by`, with
+    /// untagged apps in a trailing "other" section. Ungrouped tables have a
+    /// single untitled section. Apps keep their declaration order throughout.
+    fn grouped_app_names(&self) -> Vec<(Option<String>, Vec<&String>)> {
+        let mut names: Vec<&String> = self.app_statuses.keys().collect();
+        names.sort_by_key(|name| {
+            self.app_order
+                .iter()
+                .position(|n| n == *name)
+                .unwrap_or(usize::MAX)
+        });
+        match self.group_by {
+            None => vec![(None, names)],
+            Some(GroupBy::Tag) => {
+                let mut groups: Vec<(Option<String>, Vec<&String>)> = Vec::new();
+                let mut other = Vec::new();
+                for name in names {
+                    let tag = self.app_specs.get(name).and_then(|s| s.tags.first());
+                    match tag {
+                        Some(t) => match groups.iter_mut().find(|(g, _)| g.as_ref() == Some(t)) {
+                            Some((_, members)) => members.push(name),
+                            None => groups.push((Some(t.to_owned()), vec![name])),
+                        },
+                        None => other.push(name),
+                    }
+                }
+                groups.sort_by(|a, b| a.0.cmp(&b.0));
+                if !other.is_empty() {
+                    groups.push((Some("other".to_owned()), other));
+                }
+                groups
+            }
+        }
+    }
+
+    fn app_row(&self, aname: &str, astatus: &AppStatus) -> Row<'_> {
+        let pid_str = astatus
+            .pid()
+            .map(|rp| rp.to_string())
+            .unwrap_or_else(|| "N/A".to_owned());
+        let timed_out = self.timed_out_apps.contains(aname);
+        let status_cell = match self.status_style {
+            StatusStyle::Emoji if timed_out => Text::raw("⌛"),
+            StatusStyle::Symbols if timed_out => Text::raw("◌").red(),
+            StatusStyle::Emoji => Text::raw(match astatus {
+                AppStatus::Dead(_) => "❌",
+                AppStatus::Completed(_) => "✅",
+                AppStatus::Starting(_) => "⏳",
+                AppStatus::Running(_) => "🚀",
+                AppStatus::NotReady(_) => "⚠️",
+                AppStatus::Skipped => "⏭️",
+                AppStatus::Started => "🛫",
+            }),
+            StatusStyle::Symbols => match astatus {
+                AppStatus::Dead(_) => Text::raw("●").red(),
+                AppStatus::Completed(_) => Text::raw("✓").green(),
+                AppStatus::Starting(_) | AppStatus::Started => Text::raw("◌").yellow(),
+                AppStatus::Running(_) => Text::raw("●").green(),
+                AppStatus::NotReady(_) => Text::raw("●").yellow(),
+                AppStatus::Skipped => Text::raw("○").dark_gray(),
+            },
+        };
+        let mut name_cell = Line::from(aname.to_owned().fg(self.app_color(aname)));
+        if self.detached_tabs.contains(aname) {
+            name_cell.push_span(" (no tab)".dark_gray());
+        }
+        let mut row_vals = vec![Text::from(name_cell)];
+        if self.view_mode != ViewMode::Compact {
+            row_vals.push(Text::raw(pid_str).right_aligned());
+        }
+        row_vals.push(status_cell.right_aligned());
+        if self.view_mode != ViewMode::Compact {
+            let usage = match astatus {
+                AppStatus::Starting(rp) | AppStatus::Running(rp) | AppStatus::NotReady(rp) => {
+                    self.usage.get(rp)
+                }
+                _ => None,
+            };
+            let (cpu_str, mem_str) = match usage {
+                Some(u) => (
+                    format!("{:.1}%", u.cpu_percent),
+                    format_memory(u.memory_bytes),
+                ),
+                None => ("-".to_owned(), "-".to_owned()),
+            };
+            row_vals.push(Text::raw(cpu_str).right_aligned());
+            row_vals.push(Text::raw(mem_str).right_aligned());
+            let restarts = self.restart_counts.get(aname).copied().unwrap_or(0);
+            row_vals.push(Text::raw(restarts.to_string()).right_aligned());
+        }
+        if self.view_mode == ViewMode::Detailed {
+            let uptime = match astatus {
+                AppStatus::Starting(_) | AppStatus::Running(_) | AppStatus::NotReady(_) => self
+                    .running_since
+                    .get(aname)
+                    .map(|t| format_uptime(t.elapsed())),
+                _ => None,
+            };
+            row_vals.push(Text::raw(uptime.unwrap_or_else(|| "-".to_owned())).right_aligned());
+            let spec = self.app_specs.get(aname);
+            row_vals.push(Text::raw(truncate_with_ellipsis(
+                &spec
+                    .map(|s| s.command.to_shell_string())
+                    .unwrap_or_default(),
+                COMMAND_COLUMN_MAX_CHARS,
+            )));
+            row_vals.push(Text::raw(
+                spec.map(|s| s.working_directory.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ));
+        }
+        let row = Row::from_iter(row_vals);
+        if self.selected_app.as_deref() == Some(aname) {
+            row.reversed()
+        } else {
+            row
+        }
     }
 }
 
@@ -202,82 +1453,206 @@ impl<'a> Widget for &DisplayStatus<'a> {
         Self: Sized,
     {
         let mut rows = Vec::new();
-        let n_cell = Text::raw("Name").left_aligned();
-        let p_cell = Text::raw("PID").centered();
-        let s_cell = Text::raw("Status");
-        let title_row = Row::from_iter(vec![n_cell, p_cell, s_cell])
-            .underlined()
-            .bold();
+        let mut title_cells = vec![Text::raw("Name").left_aligned()];
+        if self.view_mode != ViewMode::Compact {
+            title_cells.push(Text::raw("PID").centered());
+        }
+        title_cells.push(Text::raw("Status"));
+        if self.view_mode != ViewMode::Compact {
+            title_cells.push(Text::raw("CPU").right_aligned());
+            title_cells.push(Text::raw("Mem").right_aligned());
+            title_cells.push(Text::raw("Restarts").right_aligned());
+        }
+        if self.view_mode == ViewMode::Detailed {
+            title_cells.push(Text::raw("Uptime").right_aligned());
+            title_cells.push(Text::raw("Command"));
+            title_cells.push(Text::raw("Directory"));
+        }
+        let title_row = Row::from_iter(title_cells).underlined().bold();
         rows.push(title_row);
-        for (aname, astatus) in self.app_statuses.iter() {
-            let row_vals = match astatus {
-                AppStatus::Dead(rp) => vec![
-                    Text::raw(aname.to_owned()),
-                    Text::raw(rp.to_string()).right_aligned(),
-                    Text::raw("❌".to_owned()).right_aligned(),
-                ],
-                AppStatus::Running(rp) => vec![
-                    Text::raw(aname.to_owned()),
-                    Text::raw(rp.to_string()).right_aligned(),
-                    Text::raw("🚀".to_owned()).right_aligned(),
-                ],
-                _ => vec![
-                    Text::raw(aname.to_owned()),
-                    Text::raw("N/A".to_owned()).right_aligned(),
-                    Text::raw("🛫".to_owned()).right_aligned(),
-                ],
-            };
-            let row = Row::from_iter(row_vals);
-            rows.push(row);
+        for (group_name, app_names) in self.grouped_app_names() {
+            if let Some(gn) = group_name {
+                rows.push(Row::from_iter(vec![Text::raw(gn)]).bold().italic());
+            }
+            for aname in app_names {
+                if let Some(astatus) = self.app_statuses.get(aname) {
+                    rows.push(self.app_row(aname, astatus));
+                }
+            }
         }
-        let widths = vec![
-            Constraint::Fill(1),
-            Constraint::Length(6),
-            Constraint::Length(6),
-        ];
+        let widths = match self.view_mode {
+            ViewMode::Compact => vec![Constraint::Fill(1), Constraint::Length(6)],
+            ViewMode::Normal => vec![
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(8),
+            ],
+            ViewMode::Detailed => vec![
+                Constraint::Fill(1),
+                Constraint::Length(6),
+                Constraint::Length(6),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Length(11),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+            ],
+        };
+        let row_count = rows.len();
         let table = Table::new(rows, widths);
-        let tlayout = Layout::vertical(vec![Constraint::Length(
-            (self.app_statuses.len() + 1) as u16,
-        )])
-        .flex(Flex::Center);
+        let tlayout =
+            Layout::vertical(vec![Constraint::Length(row_count as u16)]).flex(Flex::Center);
+        let follow = if self.following_log() {
+            "FOLLOW".green().bold()
+        } else {
+            "PAUSED".yellow().bold()
+        };
+        let uptime = format_uptime(self.started_at.elapsed().unwrap_or_default());
+        let help_line = Line::from(vec![
+            follow,
+            format!(" | Up {}", uptime).into(),
+            format!(
+                " | {} - Quit | V - View | W - Wrap | E - Events | C - Command | Shift-C - Clear | / - Search | {}/{} - Page | {} - Follow | {} - Select | {} - Restart | {} - Restart All | {} - Kill | {} - Attach",
+                self.keymap.label(Action::Quit),
+                self.keymap.label(Action::PageUp),
+                self.keymap.label(Action::PageDown),
+                self.keymap.label(Action::Follow),
+                self.keymap.label(Action::SelectNext),
+                self.keymap.label(Action::Restart),
+                self.keymap.label(Action::RestartAll),
+                self.keymap.label(Action::Kill),
+                self.keymap.label(Action::Attach),
+            )
+            .into(),
+        ]);
+        // Wrap the help bar onto a second line on narrow terminals.
+        let help_rows = help_line.width().div_ceil(area.width.max(1) as usize) as u16;
+        let p = Paragraph::new(help_line)
+            .centered()
+            .wrap(Wrap { trim: true });
         let vlayouttop = Layout::vertical(vec![
             Constraint::Fill(1),
             Constraint::Fill(1),
-            Constraint::Length(1),
+            Constraint::Length(help_rows),
         ])
         .split(area);
         let hlayout = Layout::horizontal(vec![Constraint::Fill(1)]).flex(Flex::Center);
         let [help_area] = hlayout.areas(vlayouttop[2]);
         let [log_area] = hlayout.areas(vlayouttop[1]);
         let [t_area] = hlayout.areas(tlayout.split(vlayouttop[0])[0]);
-        let p = Paragraph::new("Q - Quit").centered();
-        let log_string = Vec::from_iter(self.logbuffer.data_queue.iter().map(|f| f.clone()));
-        let str = unsafe { String::from_utf8_unchecked(log_string) };
-        let log_p = Paragraph::new(str);
-        log_p.render(log_area, buf);
+        let [log_title_area, log_body_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(log_area);
+        let log_title = Paragraph::new(self.log_title()).bold();
+        let log_text = Text::from_iter(
+            self.visible_log_lines(log_body_area.width, log_body_area.height)
+                .into_iter()
+                .map(ansi_to_line)
+                .map(|line| match self.search.active_query() {
+                    Some(query) => highlight_matches(line, query, self.search.case_sensitive),
+                    None => line,
+                }),
+        );
+        let log_p = if self.log_wrap {
+            Paragraph::new(log_text).wrap(Wrap { trim: false })
+        } else {
+            let widest = u16::try_from(log_text.width()).unwrap_or(u16::MAX);
+            let max_scroll = widest.saturating_sub(log_body_area.width);
+            self.log_h_scroll_max.set(max_scroll);
+            Paragraph::new(log_text).scroll((0, self.log_h_scroll.min(max_scroll)))
+        };
+        if self.show_command_detail {
+            Paragraph::new(self.command_detail_lines().join("\n"))
+                .wrap(Wrap { trim: false })
+                .render(log_area, buf);
+        } else if self.show_event_history {
+            let visible = log_area.height as usize;
+            let skip = self.event_history.len().saturating_sub(visible);
+            let lines: Vec<String> = self
+                .event_history
+                .iter()
+                .skip(skip)
+                .map(|r| r.to_line(self.started_at))
+                .collect();
+            Paragraph::new(lines.join("\n")).render(log_area, buf);
+        } else {
+            log_title.render(log_title_area, buf);
+            log_p.render(log_body_area, buf);
+        }
         table.render(t_area, buf);
         p.render(help_area, buf);
     }
 }
 
+/// Reads keys on a thread of its own, translating them into `AppEvent`s,
+/// and sends a `Tick` every `tick_interval` (checked each `poll_interval`).
+/// Keys bound in `keymap` take precedence over the built-in ones.
 pub(crate) fn start_event_loop(
     out_chan: &Sender<AppEvent>,
+    poll_interval: Duration,
+    tick_interval: Duration,
+    keymap: Keymap,
     die_chan: Receiver<()>,
 ) -> JoinHandle<()> {
     let tx = out_chan.clone();
     thread::spawn(move || {
+        // While a search query is being typed, keys edit it instead.
+        let mut typing_search = false;
+        let mut last_tick = Instant::now();
         loop {
-            let ep = event::poll(Duration::from_millis(200));
+            if last_tick.elapsed() >= tick_interval {
+                last_tick = Instant::now();
+                let _ = tx.send(AppEvent::Tick);
+            }
+            let ep = event::poll(poll_interval);
             match ep {
                 Ok(true) => {
                     if let Ok(ev) = event::read() {
                         match ev {
                             Event::Key(ke) => {
-                                if ke.code == KeyCode::Char('q') {
-                                    let _ = tx.send(AppEvent::QuitKeyEvent);
-                                } else {
-                                    let _ = tx.send(AppEvent::IgnoredEvent);
-                                }
+                                let _ = tx.send(match ke.code {
+                                    // Raw mode swallows SIGINT, so Ctrl-C arrives as a key.
+                                    KeyCode::Char('c')
+                                        if ke.modifiers.contains(KeyModifiers::CONTROL) =>
+                                    {
+                                        AppEvent::QuitKeyEvent
+                                    }
+                                    KeyCode::Enter if typing_search => {
+                                        typing_search = false;
+                                        AppEvent::SubmitSearch
+                                    }
+                                    KeyCode::Esc if typing_search => {
+                                        typing_search = false;
+                                        AppEvent::CancelSearch
+                                    }
+                                    KeyCode::Backspace if typing_search => {
+                                        AppEvent::SearchInput(None)
+                                    }
+                                    KeyCode::Char(c) if typing_search => {
+                                        AppEvent::SearchInput(Some(c))
+                                    }
+                                    _ if typing_search => AppEvent::IgnoredEvent,
+                                    _ if let Some(event) = keymap.event_for(&ke) => event,
+                                    KeyCode::Char('/') => {
+                                        typing_search = true;
+                                        AppEvent::StartSearch
+                                    }
+                                    KeyCode::Char('n') => AppEvent::NextSearchMatch,
+                                    KeyCode::Char('N') => AppEvent::PreviousSearchMatch,
+                                    KeyCode::Char('i') => AppEvent::ToggleSearchCase,
+                                    KeyCode::Esc => AppEvent::CancelSearch,
+                                    KeyCode::Char('v') => AppEvent::CycleViewMode,
+                                    KeyCode::Char('w') => AppEvent::ToggleLogWrap,
+                                    KeyCode::Char('e') => AppEvent::ToggleEventHistory,
+                                    KeyCode::Char('c') => AppEvent::ToggleCommandDetail,
+                                    KeyCode::Char('C') => AppEvent::ClearLog,
+                                    KeyCode::Left => AppEvent::ScrollLogLeft,
+                                    KeyCode::Right => AppEvent::ScrollLogRight,
+                                    _ => AppEvent::IgnoredEvent,
+                                });
                             }
                             _ => {
                                 let _ = tx.send(AppEvent::IgnoredEvent);
@@ -301,7 +1676,7 @@ pub(crate) fn start_event_loop(
 }
 
 fn check_for_message(ds: &DisplayStatus) -> Option<AppEvent> {
-    if ds.outstanding_pids.is_empty() {
+    if ds.is_finished() {
         return None;
     }
     if let Ok(msg) = ds.child_event_listener.recv() {
@@ -311,55 +1686,798 @@ fn check_for_message(ds: &DisplayStatus) -> Option<AppEvent> {
     }
 }
 
+/// The sender is leaked on purpose: the logger and every worker thread hold
+/// it for the whole run, and this is only called once, from `main`.
 fn create_app_event_channel() -> (&'static Sender<AppEvent>, Receiver<AppEvent>) {
     let (s, r) = channel::<AppEvent>();
     (Box::leak(Box::new(s)), r)
 }
 
+/// How preparing and starting one app's session went, before any state is
+/// updated, so apps can be started on separate threads.
+enum LaunchOutcome {
+    Started(Box<StartedProgram>),
+    /// Its `launch_if` check failed.
+    Skipped,
+    PortInUse(PortWaitError),
+    Failed(String),
+    /// It was still being started when its `start_timeout` ran out.
+    TimedOut(Duration),
+}
+
+fn start_app(
+    spec: &ProgramSpec,
+    launch_ctx: &LaunchContext,
+    deadline: Option<Instant>,
+) -> LaunchOutcome {
+    match should_launch(spec, deadline) {
+        Some(true) => {}
+        Some(false) => return LaunchOutcome::Skipped,
+        None => return LaunchOutcome::TimedOut(spec.start_timeout.unwrap_or_default()),
+    }
+    if let Err(e) = ensure_port_free(spec) {
+        return LaunchOutcome::PortInUse(e);
+    }
+    match spec.try_into_with(launch_ctx) {
+        Ok(comm) => LaunchOutcome::Started(Box::new(comm)),
+        Err(e) => LaunchOutcome::Failed(e.to_string()),
+    }
+}
+
+/// Runs `start_app` on its own thread and gives up on it once the app's
+/// `start_timeout` passes, so a hung `launch_if` check can't wedge the launch.
+/// The check is killed at the deadline, and a session the abandoned thread
+/// still goes on to start is killed too. The app's own command isn't timed
+/// once its session is up; that is what readiness probes are for.
+fn start_app_within_timeout(spec: &ProgramSpec, launch_ctx: &LaunchContext) -> LaunchOutcome {
+    let Some(timeout) = spec.start_timeout else {
+        return start_app(spec, launch_ctx, None);
+    };
+    let deadline = Instant::now() + timeout;
+    let (tx, rx) = channel::<LaunchOutcome>();
+    let given_up = Arc::new(Mutex::new(false));
+    let thread_given_up = Arc::clone(&given_up);
+    let (spec, ctx) = (spec.clone(), launch_ctx.clone());
+    thread::spawn(move || {
+        let outcome = start_app(&spec, &ctx, Some(deadline));
+        // Holding the lock while sending means the launch either finished in
+        // time or is cleaned up here, never lost in between.
+        let given_up = thread_given_up
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !*given_up {
+            let _ = tx.send(outcome);
+        } else if let LaunchOutcome::Started(started) = outcome {
+            warn!(
+                "{} started after its start timeout, killing its session.",
+                spec.name
+            );
+            cleanup_session(&ctx.server, &started.session_name);
+        }
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(outcome) => outcome,
+        Err(RecvTimeoutError::Timeout) => {
+            *given_up.lock().unwrap_or_else(PoisonError::into_inner) = true;
+            rx.try_recv().unwrap_or(LaunchOutcome::TimedOut(timeout))
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            LaunchOutcome::Failed("its launch thread panicked".to_owned())
+        }
+    }
+}
+
+/// Launches every app the scheduler has released, repeating until no more
+/// become launchable, then marks apps blocked by a failed dependency as skipped.
+/// An app that fails to start is marked dead and the rest carry on; the names
+/// of those that failed are returned.
+fn launch_available_apps(
+    scheduler: &mut StartupScheduler,
+    display_status: &mut DisplayStatus,
+    launch_ctx: &LaunchContext,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut failed_apps = Vec::new();
+    loop {
+        let launchable = scheduler.take_launchable();
+        if launchable.is_empty() {
+            break;
+        }
+        let outcomes: Vec<LaunchOutcome> = if display_status.sequential_launch {
+            launchable
+                .iter()
+                .map(|spec| start_app_within_timeout(spec, launch_ctx))
+                .collect()
+        } else {
+            // Apps released together don't depend on each other, so their
+            // checks and sessions can all start at once.
+            thread::scope(|scope| {
+                let handles: Vec<_> = launchable
+                    .iter()
+                    .map(|spec| scope.spawn(|| start_app_within_timeout(spec, launch_ctx)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| {
+                        h.join().unwrap_or_else(|_e| {
+                            LaunchOutcome::Failed("its launch thread panicked".to_owned())
+                        })
+                    })
+                    .collect()
+            })
+        };
+        let mut started_commands: Vec<StartedProgram> = Vec::new();
+        for (spec, outcome) in launchable.iter().zip(outcomes) {
+            match outcome {
+                LaunchOutcome::Started(comm) => {
+                    started_commands.push(*comm);
+                    display_status.mark_app_started(&spec.name);
+                }
+                LaunchOutcome::Skipped => {
+                    info!("Skipping {}, its launch_if check failed.", spec.name);
+                    scheduler.mark_skipped(&spec.name);
+                    display_status.mark_app_skipped(&spec.name);
+                }
+                LaunchOutcome::PortInUse(e) => {
+                    error!(
+                        "Not starting {}, its port did not free up: {}",
+                        spec.name, e
+                    );
+                    scheduler.mark_failed(&spec.name);
+                    display_status.mark_app_failed_to_start(&spec.name, Box::new(e));
+                    failed_apps.push(spec.name.clone());
+                }
+                LaunchOutcome::Failed(e) => {
+                    error!("Could not start {}: {}", spec.name, e);
+                    scheduler.mark_failed(&spec.name);
+                    display_status.mark_app_failed_to_start(&spec.name, e.into());
+                    failed_apps.push(spec.name.clone());
+                }
+                LaunchOutcome::TimedOut(timeout) => {
+                    error!(
+                        "Gave up on {}, it did not start within {}ms.",
+                        spec.name,
+                        timeout.as_millis()
+                    );
+                    scheduler.mark_failed(&spec.name);
+                    display_status.mark_app_timed_out(&spec.name, timeout);
+                    failed_apps.push(spec.name.clone());
+                }
+            }
+        }
+        let (running_programs, died) = convert_pids(&launch_ctx.server, &started_commands)?;
+        for (spec, e) in died {
+            error!("Could not start {}: {}", spec.name, e);
+            scheduler.mark_failed(&spec.name);
+            display_status.mark_app_failed_to_start(&spec.name, e);
+            failed_apps.push(spec.name);
+        }
+        display_status.start_running(&running_programs);
+        for rp in running_programs.iter() {
+            if rp.spec.readiness.is_none() {
+                scheduler.mark_ready(&rp.spec.name);
+            }
+        }
+    }
+    for (app_name, dep) in scheduler.take_blocked() {
+        if display_status
+            .app_specs
+            .get(&dep)
+            .is_some_and(|spec| spec.oneshot)
+        {
+            error!(
+                "Not starting {}, its one-shot dependency {} did not complete.",
+                app_name, dep
+            );
+        } else {
+            warn!("Not starting {}, a dependency did not start.", app_name);
+        }
+        display_status.mark_app_skipped(&app_name);
+    }
+    Ok(failed_apps)
+}
+
+/// Launches apps released while the TUI is running, logging rather than
+/// aborting on failure.
+fn launch_pending_apps(
+    scheduler: &mut StartupScheduler,
+    display_status: &mut DisplayStatus,
+    launch_ctx: &LaunchContext,
+) {
+    if display_status.is_quiting || display_status.restarting_all {
+        return;
+    }
+    if let Err(e) = launch_available_apps(scheduler, display_status, launch_ctx) {
+        error!("Failed to launch dependent apps: {}", e);
+    }
+}
+
+/// Launches every app again once a restart of all of them has stopped
+/// the old ones, first killing any of their sessions still around so the
+/// new ones can take the same names.
+fn relaunch_all_apps(
+    scheduler: &mut StartupScheduler,
+    display_status: &mut DisplayStatus,
+    launch_ctx: &LaunchContext,
+) {
+    display_status.finish_restart_all();
+    match reclaim_sessions(&launch_ctx.server, &launch_ctx.namespace) {
+        Ok(leftovers) => {
+            for session_name in leftovers {
+                warn!(
+                    "Killed leftover session {} before relaunching.",
+                    session_name
+                );
+            }
+        }
+        Err(e) => warn!("Could not check for leftover sessions: {}", e),
+    }
+    scheduler.reset();
+    launch_pending_apps(scheduler, display_status, launch_ctx);
+}
+
+fn redraw(
+    terminal: &mut Option<DefaultTerminal>,
+    display_status: &DisplayStatus,
+) -> io::Result<()> {
+    if let Some(t) = terminal.as_mut() {
+        t.draw(|f| f.render_widget(display_status, f.area()))?;
+    }
+    Ok(())
+}
+
+/// How long the whole screen stays inverted when an app crashes.
+const CRASH_FLASH_DURATION: Duration = Duration::from_millis(100);
+
+/// Rings the terminal bell and, with the terminal UI up, shows one inverted
+/// frame as a visual flash. The next redraw puts the screen back.
+fn ring_bell_and_flash(
+    terminal: &mut Option<DefaultTerminal>,
+    display_status: &DisplayStatus,
+) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(b"\x07")?;
+    stdout.flush()?;
+    if let Some(t) = terminal.as_mut() {
+        t.draw(|f| {
+            let area = f.area();
+            f.render_widget(display_status, area);
+            f.buffer_mut().set_style(area, Style::new().reversed());
+        })?;
+        thread::sleep(CRASH_FLASH_DURATION);
+    }
+    Ok(())
+}
+
+/// Restarts a dead app, reporting the new process to the event stream.
+fn restart_and_report(
+    display_status: &mut DisplayStatus,
+    app_name: &str,
+    launch_ctx: &LaunchContext,
+    event_stream: &mut Option<EventStream>,
+) {
+    match display_status.restart_app(app_name, launch_ctx) {
+        Ok(Some(rp)) => {
+            if let Some(es) = event_stream.as_mut() {
+                es.emit(&LifecycleEvent::Restart {
+                    app_name: app_name.to_owned(),
+                    session_name: rp.program.session_name,
+                    pid: rp.program.program_pid,
+                });
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("Failed to restart {}: {}", app_name, e),
+    }
+}
+
+/// Writes log output straight to stdout when running without the TUI. App
+/// output is printed as the whole lines it completed, each after the app's
+/// prefix; devplexer's own output is printed as is.
+fn print_log_output(app_prefix: Option<&str>, data: &[u8], app_lines: &[String]) {
+    match app_prefix {
+        Some(prefix) => {
+            for line in app_lines.iter() {
+                println!("{}{}", prefix, line);
+            }
+        }
+        None => print!("{}", String::from_utf8_lossy(data)),
+    }
+}
+
+/// Loads and validates the configuration without launching anything,
+/// printing a summary. Returns whether the configuration is valid.
+fn run_config_check(current_dir: &Path, cli_options: &CliOptions) -> bool {
+    let config = match try_load_config(current_dir, cli_options) {
+        Ok(config) => config,
+        Err(e) => {
+            match e.downcast_ref::<ConfigurationSettingsError>() {
+                Some(ConfigurationSettingsError::InvalidSpecStructuresError(fails)) => {
+                    for fail in fails.iter() {
+                        println!("error: {}", fail);
+                    }
+                }
+                _ => println!("error: {}", e),
+            }
+            return false;
+        }
+    };
+    println!("namespace: {}", config.namespace);
+    for spec in config.apps.iter() {
+        println!("  {} ({})", spec.name, spec.working_directory.display());
+    }
+    let problems = validate_config(&config);
+    for problem in problems.iter() {
+        println!("error: {}", problem);
+    }
+    problems.is_empty()
+}
+
+/// Prints one `name version` line each for devplexer, tmux and the tab
+/// adapter that would be used, for bug reports.
+fn print_versions(cli_options: &CliOptions) {
+    println!("devplexer {}", env!("CARGO_PKG_VERSION"));
+    match ensure_available() {
+        Ok(Some(version)) => println!("tmux {}", version),
+        Ok(None) => println!("tmux unknown"),
+        Err(_) => println!("tmux missing"),
+    }
+    let adapter = if cli_options.tmux_windows {
+        "tmux-windows"
+    } else {
+        tab_adapter_name()
+    };
+    println!("tab-adapter {}", adapter);
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli_options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if cli_options.version {
+        print_versions(&cli_options);
+        return Ok(());
+    }
+    let log_level = match cli_options.log_level {
+        Some(level) => level,
+        None => match std::env::var("RUST_LOG")
+            .ok()
+            .map(|spec| parse_log_level(&spec))
+        {
+            Some(Err(e)) => {
+                eprintln!("error: RUST_LOG: {}", e);
+                std::process::exit(1);
+            }
+            Some(Ok(level)) => level.unwrap_or(DEFAULT_LOG_LEVEL),
+            None => DEFAULT_LOG_LEVEL,
+        },
+    };
     let (aes, aer) = create_app_event_channel();
-    initialize_logger(aes);
-    let mut args = std::env::args();
+    initialize_logger(aes, log_level);
 
     let exe_loc = std::env::current_dir().unwrap();
     let exe_path = exe_loc.canonicalize().unwrap();
+    if cli_options.check {
+        std::process::exit(if run_config_check(&exe_path, &cli_options) {
+            0
+        } else {
+            1
+        });
+    }
 
-    let config = try_load_config(&exe_path, &mut args)?;
+    let loaded = try_load_config(&exe_path, &cli_options).and_then(|config| {
+        if !cli_options.no_validate_paths {
+            ensure_paths_exist(&config)?;
+        }
+        Ok(config)
+    });
+    let config = match loaded {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if cli_options.dry_run {
+        // Without running tmux its version is unknown, so assume a recent one.
+        let launch_ctx = LaunchContext {
+            namespace: config.namespace.clone(),
+            remain_on_exit: true,
+            server: TmuxServer::new(config.tmux_socket.clone()),
+        };
+        let plan = dry_run_plan(
+            &config,
+            &launch_ctx,
+            !cli_options.no_reclaim,
+            cli_options.tmux_windows,
+        )?;
+        for line in plan.iter() {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
     info!("Loaded configuration.");
-    let mut started_commands: Vec<StartedProgram> = Vec::new();
-    let tab_adapter = choose_tab_adapter()?;
-    let mut display_status = DisplayStatus::new(tab_adapter, &aes, aer);
+    let mut event_stream = cli_options
+        .events_file
+        .as_deref()
+        .map(EventStream::open)
+        .transpose()?;
+    let tmux_version = match ensure_available() {
+        Ok(version) => version,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let tmux_server = TmuxServer::new(config.tmux_socket.clone());
+    if !cli_options.no_reclaim {
+        for session_name in reclaim_sessions(&tmux_server, &config.namespace)? {
+            info!("Reclaimed orphaned session: {}", session_name);
+        }
+    }
+    if let Some(hook) = config.before_start.as_ref() {
+        info!("Running before_start.");
+        if let Err(e) = run_hook(hook, false) {
+            eprintln!("error: before_start {}", e);
+            std::process::exit(1);
+        }
+    }
+    let tab_adapter: Option<Box<dyn TabAdapter>> = if cli_options.tmux_windows {
+        info!("Booted tmux window adapter.");
+        Some(Box::new(TmuxWindowAdapter::new(
+            &tmux_server,
+            &config.namespace,
+        )?))
+    } else {
+        choose_tab_adapter(&tmux_server)?
+    };
+    let mut display_status = DisplayStatus::new(tab_adapter, &config, aes, aer);
+    display_status.sequential_launch = cli_options.sequential;
+    display_status.keep_dead_sessions = cli_options.keep_dead_sessions;
+    if cli_options.no_emoji {
+        display_status.status_style = StatusStyle::Symbols;
+    }
+    if let Some(port) = cli_options.status_port {
+        display_status.status_server = Some(StatusServer::start(port, cli_options.metrics)?);
+        info!("Serving app status on http://127.0.0.1:{}/", port);
+        if cli_options.metrics {
+            info!("Serving metrics on http://127.0.0.1:{}/metrics", port);
+        }
+    }
 
-    for spec in config.apps.iter() {
-        let comm = spec.try_into_with(&config.namespace)?;
-        started_commands.push(comm);
-        display_status.mark_app_started(&spec.name);
+    let remain_on_exit = supports_remain_on_exit(tmux_version);
+    if !remain_on_exit {
+        warn!(
+            "tmux {} does not support remain-on-exit, exit codes will not be reported.",
+            tmux_version.map(|v| v.to_string()).unwrap_or_default()
+        );
     }
-    let running_programs = convert_pids(&started_commands)?;
-    display_status.start_running(&running_programs);
-    let mut terminal = ratatui::init();
+    let launch_ctx = LaunchContext {
+        namespace: config.namespace.clone(),
+        remain_on_exit,
+        server: tmux_server.clone(),
+    };
+
+    forward_quit_signals(display_status.child_event_sender)?;
+    let mut scheduler = StartupScheduler::new(&config.apps);
+    let failed_apps = launch_available_apps(&mut scheduler, &mut display_status, &launch_ctx)?;
+    // One app failing to start shouldn't stop the others, but with none
+    // running there is nothing left to show.
+    if !failed_apps.is_empty() && display_status.outstanding_pids.is_empty() {
+        eprintln!(
+            "error: {}",
+            ProgramStartErrors::NoProgramStartedError(failed_apps)
+        );
+        std::process::exit(1);
+    }
+    let mut terminal = if cli_options.headless {
+        None
+    } else {
+        display_status.start_input();
+        display_status.start_usage_sampling();
+        Some(ratatui::init())
+    };
     while let Some(evt) = check_for_message(&display_status) {
         match evt {
-            AppEvent::ProcessEnded(s, s_name, _t_pid, p_pid, _) => {
-                display_status.mark_app_dead(&s, &s_name, &p_pid);
-                error!("Application Died: {}", s);
-                terminal.draw(|f| f.render_widget(&display_status, f.area()))?;
+            AppEvent::ProcessEnded(s, s_name, _t_pid, p_pid, exit_status) => {
+                if let Some(es) = event_stream.as_mut() {
+                    es.emit(&LifecycleEvent::ProcessEnded {
+                        app_name: s.clone(),
+                        session_name: s_name.clone(),
+                        pid: p_pid,
+                        exit_status,
+                    });
+                }
+                display_status.mark_app_dead(&s, &s_name, &p_pid, exit_status);
+                if display_status.finish_stopping(&s) {
+                    info!("Application Stopped: {}", s);
+                    display_status.shutdown_session(&s_name);
+                    display_status.dead_sessions.retain(|d| d != &s_name);
+                    scheduler.mark_failed(&s);
+                } else if matches!(
+                    display_status.app_statuses.get(&s),
+                    Some(AppStatus::Completed(_))
+                ) {
+                    info!("Application Completed: {}", s);
+                    scheduler.mark_completed(&s);
+                } else {
+                    error!("Application Died: {}", s);
+                    display_status.snapshot_startup_output(&s, &s_name);
+                    if config.notify_on_crash && !display_status.is_quiting {
+                        send_notification(&CrashNotification::new(&s, exit_status));
+                    }
+                    if config.bell_on_crash && !display_status.is_quiting {
+                        ring_bell_and_flash(&mut terminal, &display_status)?;
+                    }
+                    if !display_status.schedule_auto_restart(&s, exit_status) {
+                        scheduler.mark_failed(&s);
+                    }
+                }
+                if display_status.all_stopped_for_restart() {
+                    relaunch_all_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                } else {
+                    launch_pending_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                }
+                redraw(&mut terminal, &display_status)?;
             }
             AppEvent::QuitKeyEvent => {
                 info!("Shutdown Request Received.");
+                if !display_status.is_quiting
+                    && let Some(es) = event_stream.as_mut()
+                {
+                    es.emit(&LifecycleEvent::Quit);
+                }
                 display_status.execute_quit();
-                terminal.draw(|f| f.render_widget(&display_status, f.area()))?;
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::CycleViewMode => {
+                display_status.cycle_view_mode();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ToggleEventHistory => {
+                display_status.toggle_event_history();
+                redraw(&mut terminal, &display_status)?;
             }
-            AppEvent::LogEvent(ld) => {
-                display_status.add_log_entry(&ld);
-                terminal.draw(|f| f.render_widget(&display_status, f.area()))?;
+            AppEvent::ToggleCommandDetail => {
+                display_status.toggle_command_detail();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ClearLog => {
+                display_status.clear_viewed_log();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::StartSearch => {
+                display_status.start_search();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::SearchInput(input) => {
+                display_status.edit_search(input);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::SubmitSearch => {
+                display_status.submit_search();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::CancelSearch => {
+                display_status.cancel_search();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::NextSearchMatch => {
+                display_status.next_search_match(true);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::PreviousSearchMatch => {
+                display_status.next_search_match(false);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ToggleSearchCase => {
+                display_status.toggle_search_case();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ToggleLogWrap => {
+                display_status.toggle_log_wrap();
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogLeft => {
+                display_status.scroll_log_horizontal(-LOG_H_SCROLL_STEP);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogRight => {
+                display_status.scroll_log_horizontal(LOG_H_SCROLL_STEP);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogUp => {
+                display_status.scroll_log_vertical(1);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogDown => {
+                display_status.scroll_log_vertical(-1);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogPageUp => {
+                display_status.scroll_log_vertical(LOG_PAGE_SCROLL_LINES);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::ScrollLogPageDown => {
+                display_status.scroll_log_vertical(-LOG_PAGE_SCROLL_LINES);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::FollowLog => {
+                display_status.log_scroll = 0;
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::SelectNextApp => {
+                display_status.select_app(1);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::SelectPreviousApp => {
+                display_status.select_app(-1);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::RestartSelectedApp => {
+                if !display_status.is_quiting
+                    && let Some(app_name) = display_status.selected_app.clone()
+                {
+                    restart_and_report(
+                        &mut display_status,
+                        &app_name,
+                        &launch_ctx,
+                        &mut event_stream,
+                    );
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::RestartAll => {
+                display_status.restart_all();
+                if display_status.all_stopped_for_restart() {
+                    relaunch_all_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::RestartApp(app_name) => {
+                if display_status.pending_restarts.remove(&app_name) {
+                    restart_and_report(
+                        &mut display_status,
+                        &app_name,
+                        &launch_ctx,
+                        &mut event_stream,
+                    );
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::KillSelectedApp => {
+                if !display_status.is_quiting
+                    && let Some(app_name) = display_status.selected_app.clone()
+                {
+                    display_status.kill_app(&app_name);
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::AttachSelectedApp => {
+                if !display_status.is_quiting
+                    && let Some(app_name) = display_status.selected_app.clone()
+                {
+                    display_status.attach_app(&app_name);
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::AppReady(s) => {
+                display_status.mark_app_ready(&s);
+                info!("Application Ready: {}", s);
+                scheduler.mark_ready(&s);
+                launch_pending_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::AppReadyTimeout(s) => {
+                display_status.mark_app_not_ready(&s);
+                warn!("Application Not Ready: {}", s);
+                scheduler.mark_ready_timed_out(&s);
+                launch_pending_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::AppReadyFailed(s, reason) => {
+                display_status.mark_app_not_ready(&s);
+                error!("Application Readiness Failed: {} - {}", s, reason);
+                scheduler.mark_failed(&s);
+                launch_pending_apps(&mut scheduler, &mut display_status, &launch_ctx);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::UsageSampled(samples) => {
+                display_status.record_usage(samples);
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::LogEvent(source, ld) => {
+                let app_lines = display_status.add_log_entry(source.as_deref(), &ld);
+                if terminal.is_none() {
+                    // Only color the prefix when stdout isn't piped to a file.
+                    let prefix = source
+                        .as_deref()
+                        .map(|a| display_status.log_prefix(a, io::stdout().is_terminal()));
+                    print_log_output(prefix.as_deref(), &ld, &app_lines);
+                }
+                redraw(&mut terminal, &display_status)?;
+            }
+            AppEvent::Tick => {
+                display_status.check_for_closed_tabs();
+                redraw(&mut terminal, &display_status)?;
             }
             _ => {
-                terminal.draw(|f| f.render_widget(&display_status, f.area()))?;
+                redraw(&mut terminal, &display_status)?;
             }
         }
+        display_status.publish_status();
+    }
+    let tmux_server = display_status.tmux_server.clone();
+    let kept_sessions = display_status.finish_shutdown();
+    if terminal.is_some() {
+        ratatui::restore();
+    }
+    for session_name in kept_sessions.iter() {
+        match attach_session_command_for_cli(&tmux_server, session_name, false) {
+            Ok(attach) => println!("Kept dead session {}: {}", session_name, attach),
+            Err(_) => println!("Kept dead session {}", session_name),
+        }
     }
-    display_status.finish_shutdown();
-    ratatui::restore();
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, sync::mpsc::channel, thread};
+
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+    use sysinfo::Pid;
+
+    use crate::{
+        DisplayStatus, apps::AppEvent, check_for_message, config::string_to_config,
+        processes::ProcessIdentity,
+    };
+
+    #[test]
+    fn test_ui_outlives_the_last_app() {
+        let config = string_to_config(Path::new("/"), "apps:\n  web:\n    command: ls\n").unwrap();
+        let (aes, aer) = channel::<AppEvent>();
+        let mut ds = DisplayStatus::new(None, &config, &aes, aer);
+        let identity = ProcessIdentity {
+            pid: Pid::from_u32(4242),
+            start_time: None,
+        };
+        ds.mark_app_running("web", "ns-web", &identity);
+        ds.mark_app_dead("web", "ns-web", &identity.pid, None);
+        // Headless, a dead app can't be restarted by hand, so there is nothing to wait for.
+        assert!(ds.is_finished());
+        assert!(check_for_message(&ds).is_none());
+
+        ds.event_handle = Some(thread::spawn(|| {}));
+        assert!(!ds.is_finished());
+        aes.send(AppEvent::RestartSelectedApp).unwrap();
+        assert!(matches!(
+            check_for_message(&ds),
+            Some(AppEvent::RestartSelectedApp)
+        ));
+
+        ds.execute_quit();
+        assert!(ds.is_finished());
+    }
+
+    #[test]
+    fn test_horizontal_scroll_stops_at_the_widest_line() {
+        let config = string_to_config(Path::new("/"), "apps:\n  web:\n    command: ls\n").unwrap();
+        let (aes, aer) = channel::<AppEvent>();
+        let mut ds = DisplayStatus::new(None, &config, &aes, aer);
+        ds.toggle_log_wrap();
+        ds.logbuffer
+            .write_data(format!("{}\n", "x".repeat(100)).as_bytes());
+        let area = Rect::new(0, 0, 60, 20);
+        (&ds).render(area, &mut Buffer::empty(area));
+        for _ in 0..50 {
+            ds.scroll_log_horizontal(8);
+        }
+        assert_eq!(ds.log_h_scroll, 40);
+        ds.scroll_log_horizontal(-8);
+        assert_eq!(ds.log_h_scroll, 32);
+    }
+}
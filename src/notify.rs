@@ -0,0 +1,106 @@
+use std::{
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+};
+
+/// A desktop notification about an app that exited on its own.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct CrashNotification {
+    pub(crate) title: String,
+    pub(crate) body: String,
+    pub(crate) urgent: bool,
+}
+
+impl CrashNotification {
+    /// Anything but a clean exit is urgent, including an unknown status.
+    pub(crate) fn new(app_name: &str, exit_status: Option<ExitStatus>) -> Self {
+        let (body, urgent) = match exit_status {
+            Some(es) => match (es.code(), es.signal()) {
+                (Some(0), _) => (format!("{} exited with code 0", app_name), false),
+                (Some(code), _) => (format!("{} exited with code {}", app_name, code), true),
+                (None, Some(sig)) => (format!("{} was killed by signal {}", app_name, sig), true),
+                _ => (format!("{} exited", app_name), true),
+            },
+            None => (format!("{} exited", app_name), true),
+        };
+        let title = if urgent {
+            "devplexer: app crashed"
+        } else {
+            "devplexer: app exited"
+        };
+        CrashNotification {
+            title: title.to_owned(),
+            body,
+            urgent,
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn notification_command(notification: &CrashNotification) -> Option<Command> {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut script = format!(
+        "display notification {} with title {}",
+        quote(&notification.body),
+        quote(&notification.title)
+    );
+    if notification.urgent {
+        script.push_str(" sound name \"Basso\"");
+    }
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(script);
+    Some(cmd)
+}
+
+#[cfg(target_os = "linux")]
+fn notification_command(notification: &CrashNotification) -> Option<Command> {
+    let mut cmd = Command::new("notify-send");
+    cmd.arg("--urgency")
+        .arg(if notification.urgent {
+            "critical"
+        } else {
+            "normal"
+        })
+        .arg(&notification.title)
+        .arg(&notification.body);
+    Some(cmd)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn notification_command(_notification: &CrashNotification) -> Option<Command> {
+    None
+}
+
+/// Shows the notification without blocking, ignoring any failure.
+pub(crate) fn send_notification(notification: &CrashNotification) {
+    if let Some(mut cmd) = notification_command(notification) {
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        thread::spawn(move || {
+            let _ = cmd.status();
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{os::unix::process::ExitStatusExt, process::ExitStatus};
+
+    use crate::notify::CrashNotification;
+
+    #[test]
+    fn test_crash_notification_urgency() {
+        let failed = CrashNotification::new("web", Some(ExitStatus::from_raw(1 << 8)));
+        assert_eq!(failed.body, "web exited with code 1");
+        assert!(failed.urgent);
+        let clean = CrashNotification::new("web", Some(ExitStatus::from_raw(0)));
+        assert_eq!(clean.body, "web exited with code 0");
+        assert!(!clean.urgent);
+        let killed = CrashNotification::new("web", Some(ExitStatus::from_raw(9)));
+        assert_eq!(killed.body, "web was killed by signal 9");
+        assert!(killed.urgent);
+        assert!(CrashNotification::new("web", None).urgent);
+    }
+}
@@ -1,75 +1,348 @@
-use std::time::{Duration, SystemTime};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
 
-use sysinfo::{Pid, ProcessesToUpdate, Signal, System};
+use sysinfo::{Pid, ProcessStatus, ProcessesToUpdate, Signal, System};
 
-use crate::tmux::send_interrupt;
+use crate::{
+    config::ProgramSpec,
+    tmux::{TmuxServer, send_interrupt},
+};
+
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_millis(2000);
+const DEFAULT_KILL_TIMEOUT: Duration = Duration::from_millis(3000);
+const DEFAULT_STOP_SIGNALS: [Signal; 2] = [Signal::Interrupt, Signal::Term];
+
+/// How an app is stopped: Ctrl-C is sent to its pane, then after
+/// `stop_timeout` each of `signals` is tried in turn, waiting `kill_timeout`
+/// after each before finally sending `SIGKILL`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct StopSettings {
+    pub(crate) signals: Vec<Signal>,
+    pub(crate) stop_timeout: Duration,
+    pub(crate) kill_timeout: Duration,
+}
+
+impl Default for StopSettings {
+    fn default() -> Self {
+        StopSettings {
+            signals: DEFAULT_STOP_SIGNALS.to_vec(),
+            stop_timeout: DEFAULT_STOP_TIMEOUT,
+            kill_timeout: DEFAULT_KILL_TIMEOUT,
+        }
+    }
+}
+
+impl StopSettings {
+    pub(crate) fn for_spec(spec: &ProgramSpec) -> Self {
+        let defaults = StopSettings::default();
+        StopSettings {
+            signals: spec
+                .stop_signal
+                .map(|s| vec![s])
+                .unwrap_or(defaults.signals),
+            stop_timeout: spec.stop_timeout.unwrap_or(defaults.stop_timeout),
+            kill_timeout: spec.kill_timeout.unwrap_or(defaults.kill_timeout),
+        }
+    }
+}
+
+/// Parses a signal name such as `SIGTERM` or `TERM`, ignoring case.
+pub(crate) fn parse_signal(name: &str) -> Option<Signal> {
+    let upper = name.trim().to_ascii_uppercase();
+    let short = upper.strip_prefix("SIG").unwrap_or(&upper);
+    match short {
+        "HUP" => Some(Signal::Hangup),
+        "INT" => Some(Signal::Interrupt),
+        "QUIT" => Some(Signal::Quit),
+        "ABRT" => Some(Signal::Abort),
+        "KILL" => Some(Signal::Kill),
+        "USR1" => Some(Signal::User1),
+        "USR2" => Some(Signal::User2),
+        "ALRM" => Some(Signal::Alarm),
+        "TERM" => Some(Signal::Term),
+        "WINCH" => Some(Signal::Winch),
+        _ => None,
+    }
+}
+
+/// A `System` that only knows about `pid`, avoiding a scan of every
+/// process on the machine.
+pub(crate) fn system_for_process(pid: &Pid) -> System {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[*pid]), true);
+    system
+}
+
+/// A pid together with when its process started, so that once the app has
+/// exited and the OS hands the pid to something else, that process isn't
+/// mistaken for the app.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct ProcessIdentity {
+    pub(crate) pid: Pid,
+    /// Seconds since the epoch, or `None` if it couldn't be read at launch,
+    /// in which case any process with the pid is trusted.
+    pub(crate) start_time: Option<u64>,
+}
+
+impl ProcessIdentity {
+    /// Records the start time of the process currently running as `pid`.
+    pub(crate) fn capture(pid: Pid) -> Self {
+        let system = system_for_process(&pid);
+        ProcessIdentity {
+            pid,
+            start_time: system.process(pid).map(|p| p.start_time()),
+        }
+    }
+
+    fn started_at(&self, start_time: u64) -> bool {
+        self.start_time.is_none_or(|t| t == start_time)
+    }
+}
+
+/// Whether the identified process is still running. A zombie counts as
+/// exited: tmux may not reap it right away, but it will never run again.
+fn is_alive(system: &System, identity: &ProcessIdentity) -> bool {
+    system
+        .process(identity.pid)
+        .is_some_and(|p| p.status() != ProcessStatus::Zombie && identity.started_at(p.start_time()))
+}
+
+/// Blocks until the identified process exits. The apps aren't our children,
+/// so this polls rather than waiting on them.
+pub(crate) fn wait_for_exit(system: &mut System, identity: &ProcessIdentity) {
+    while is_alive(system, identity) {
+        std::thread::sleep(Duration::from_millis(100));
+        let _ = system.refresh_processes(ProcessesToUpdate::Some(&[identity.pid]), true);
+    }
+}
 
 pub(crate) fn kill_with_timeout(
     system: &mut System,
-    pid: &Pid,
+    identity: &ProcessIdentity,
     sigs: &[Signal],
     time_to_wait: Duration,
 ) {
+    let pid = &identity.pid;
     let mut timeup = false;
     let mut start_at;
-    let process = system.process(pid.clone());
-    if let None = process {
+    if !is_alive(system, identity) {
         return;
     }
     for s in sigs.iter() {
         start_at = SystemTime::now();
         timeup = false;
-        let fp = system.process(pid.clone());
-        if let Some(process) = fp {
-            let _ = process.kill_with(s.clone());
-        } else {
+        if !is_alive(system, identity) {
             return;
         }
-        let _ = system.refresh_processes(ProcessesToUpdate::Some(&[pid.clone()]), true);
-        while let Some(_proc) = system.process(pid.clone())
-            && !timeup
-        {
+        if let Some(process) = system.process(*pid) {
+            let _ = process.kill_with(*s);
+        }
+        let _ = system.refresh_processes(ProcessesToUpdate::Some(&[*pid]), true);
+        while is_alive(system, identity) && !timeup {
             std::thread::sleep(Duration::from_millis(100));
             timeup = start_at.elapsed().unwrap_or(Duration::from_millis(0)) >= time_to_wait;
-            let _ = system.refresh_processes(ProcessesToUpdate::Some(&[pid.clone()]), true);
+            let _ = system.refresh_processes(ProcessesToUpdate::Some(&[*pid]), true);
         }
         if !timeup {
             return;
         }
     }
-    if timeup {
-        if let Some(process) = system.process(pid.clone()) {
-            let _ = process.kill_with_and_wait(Signal::Kill);
-        }
+    if timeup
+        && is_alive(system, identity)
+        && let Some(process) = system.process(*pid)
+    {
+        let _ = process.kill_with(Signal::Kill);
+        wait_for_exit(system, identity);
     }
 }
 
-pub(crate) fn kill_process(pid: &Pid, session_name: &Option<String>) {
-    let mut s: sysinfo::System = sysinfo::System::new_all();
-    let p_proc = s.process(pid.clone());
+pub(crate) fn kill_process(
+    server: &TmuxServer,
+    identity: &ProcessIdentity,
+    session_name: &Option<String>,
+    settings: &StopSettings,
+) {
+    let pid = &identity.pid;
+    let mut s = system_for_process(pid);
 
-    if let Some(_process) = p_proc {
+    if is_alive(&s, identity) {
         if let Some(sn) = session_name {
-            send_interrupt(&sn);
+            send_interrupt(server, sn);
             let mut timedout = false;
             let start_at = SystemTime::now();
-            while let Some(_p) = s.process(pid.clone())
-                && !timedout
-            {
+            while is_alive(&s, identity) && !timedout {
                 std::thread::sleep(Duration::from_millis(100));
-                let _ = s.refresh_processes(ProcessesToUpdate::Some(&[pid.clone()]), true);
-                timedout = start_at.elapsed().unwrap_or(Duration::from_millis(0))
-                    >= Duration::from_millis(2000);
+                let _ = s.refresh_processes(ProcessesToUpdate::Some(&[*pid]), true);
+                timedout =
+                    start_at.elapsed().unwrap_or(Duration::from_millis(0)) >= settings.stop_timeout;
             }
         }
 
-        if let Some(_proc) = s.process(pid.clone()) {
-            kill_with_timeout(
-                &mut s,
-                pid,
-                &[Signal::Interrupt, Signal::Term],
-                Duration::from_millis(3000),
-            );
+        if is_alive(&s, identity) {
+            kill_with_timeout(&mut s, identity, &settings.signals, settings.kill_timeout);
+        }
+    }
+}
+
+/// Kills `root` and everything it started, since `sh -c` may be waiting on a
+/// child of its own rather than having exec'd the command.
+fn kill_process_tree(root: Pid) {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let mut tree = vec![root];
+    let mut next = 0;
+    while next < tree.len() {
+        let parent = tree[next];
+        tree.extend(
+            system
+                .processes()
+                .values()
+                .filter(|p| p.parent() == Some(parent))
+                .map(|p| p.pid()),
+        );
+        next += 1;
+    }
+    for pid in tree.iter() {
+        if let Some(process) = system.process(*pid) {
+            let _ = process.kill_with(Signal::Kill);
+        }
+    }
+}
+
+/// How often a shell command run against a deadline is checked for having exited.
+const SHELL_COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Runs `command` through `sh -c` with its output discarded, reporting whether it exited 0.
+pub(crate) fn shell_command_succeeds(command: &str, working_directory: &Path) -> bool {
+    shell_command_succeeds_before(command, working_directory, None).unwrap_or(false)
+}
+
+/// Like `shell_command_succeeds`, but kills the command and returns `None`
+/// if it is still running at `deadline`.
+pub(crate) fn shell_command_succeeds_before(
+    command: &str,
+    working_directory: &Path,
+    deadline: Option<Instant>,
+) -> Option<bool> {
+    let Ok(mut child) = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(working_directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return Some(false);
+    };
+    let Some(deadline) = deadline else {
+        return Some(child.wait().is_ok_and(|s| s.success()));
+    };
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status.success()),
+            Ok(None) if Instant::now() < deadline => thread::sleep(SHELL_COMMAND_POLL_INTERVAL),
+            Ok(None) => {
+                kill_process_tree(Pid::from_u32(child.id()));
+                let _ = child.wait();
+                return None;
+            }
+            Err(_) => return Some(false),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        path::Path,
+        time::{Duration, Instant},
+    };
+
+    use sysinfo::{Pid, Signal};
+
+    use crate::{
+        config::ProgramSpec,
+        processes::{
+            ProcessIdentity, StopSettings, is_alive, parse_signal, shell_command_succeeds_before,
+            system_for_process,
+        },
+    };
+
+    #[test]
+    fn test_shell_command_killed_at_deadline() {
+        let root = Path::new("/");
+        let started = Instant::now();
+        let deadline = Some(started + Duration::from_millis(100));
+        assert_eq!(
+            shell_command_succeeds_before("sleep 5; true", root, deadline),
+            None
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+        let later = Some(Instant::now() + Duration::from_secs(5));
+        assert_eq!(
+            shell_command_succeeds_before("true", root, later),
+            Some(true)
+        );
+        assert_eq!(
+            shell_command_succeeds_before("false", root, None),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_process_identity_start_time() {
+        let identity = ProcessIdentity {
+            pid: Pid::from_u32(42),
+            start_time: Some(1_700_000_000),
+        };
+        assert!(identity.started_at(1_700_000_000));
+        assert!(!identity.started_at(1_700_000_100));
+        let unknown = ProcessIdentity {
+            pid: Pid::from_u32(42),
+            start_time: None,
+        };
+        assert!(unknown.started_at(1_700_000_100));
+    }
+
+    #[test]
+    fn test_reused_pid_is_not_alive() {
+        let pid = sysinfo::get_current_pid().unwrap();
+        let system = system_for_process(&pid);
+        let identity = ProcessIdentity::capture(pid);
+        assert!(identity.start_time.is_some());
+        assert!(is_alive(&system, &identity));
+        let reused = ProcessIdentity {
+            pid,
+            start_time: identity.start_time.map(|t| t.saturating_sub(60)),
+        };
+        assert!(!is_alive(&system, &reused));
+    }
+
+    #[test]
+    fn test_parse_signal() {
+        assert_eq!(parse_signal("SIGTERM"), Some(Signal::Term));
+        assert_eq!(parse_signal("SIGQUIT"), Some(Signal::Quit));
+        assert_eq!(parse_signal("int"), Some(Signal::Interrupt));
+        assert_eq!(parse_signal("sigusr1"), Some(Signal::User1));
+        assert_eq!(parse_signal("SIGBOGUS"), None);
+        assert_eq!(parse_signal(""), None);
+    }
+
+    #[test]
+    fn test_stop_settings_fall_back_to_defaults() {
+        let spec = ProgramSpec {
+            stop_signal: Some(Signal::Quit),
+            kill_timeout: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let settings = StopSettings::for_spec(&spec);
+        assert_eq!(settings.signals, vec![Signal::Quit]);
+        assert_eq!(settings.stop_timeout, StopSettings::default().stop_timeout);
+        assert_eq!(settings.kill_timeout, Duration::from_secs(30));
+    }
+}
@@ -0,0 +1,167 @@
+use std::{collections::HashMap, error::Error, path::Path};
+
+use crate::{
+    capture::DEFAULT_CAPTURE_INTERVAL,
+    config::{
+        Configuration, DEFAULT_NAMESPACE, DEFAULT_POLL_INTERVAL, DEFAULT_TAB_TITLE_TEMPLATE,
+        DEFAULT_TICK_INTERVAL, ProgramCommand, ProgramSpec, StatusStyle,
+    },
+    keymap::Keymap,
+    logging::{DEFAULT_LOG_LINES, LogTimestamps},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProcfileError {
+    InvalidLine(usize, String),
+    DuplicateProcess(String),
+}
+
+impl std::fmt::Display for ProcfileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{:?}", self))
+    }
+}
+
+impl std::error::Error for ProcfileError {}
+
+fn is_valid_process_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Parses Heroku-style `name: command` lines, skipping blank lines and `#`
+/// comments.
+pub(crate) fn parse_procfile(contents: &str) -> Result<Vec<(String, String)>, ProcfileError> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let (name, command) = trimmed
+            .split_once(':')
+            .ok_or_else(|| ProcfileError::InvalidLine(i + 1, line.to_owned()))?;
+        let (name, command) = (name.trim(), command.trim());
+        if !is_valid_process_name(name) || command.is_empty() {
+            return Err(ProcfileError::InvalidLine(i + 1, line.to_owned()));
+        }
+        if entries.iter().any(|(n, _)| n == name) {
+            return Err(ProcfileError::DuplicateProcess(name.to_owned()));
+        }
+        entries.push((name.to_owned(), command.to_owned()));
+    }
+    Ok(entries)
+}
+
+/// Builds a configuration running every Procfile process in `base_dir`,
+/// with the defaults a YAML file without settings would get.
+pub(crate) fn procfile_to_config(
+    base_dir: &Path,
+    contents: &str,
+) -> Result<Configuration, ProcfileError> {
+    let apps: Vec<ProgramSpec> = parse_procfile(contents)?
+        .into_iter()
+        .map(|(name, command)| ProgramSpec {
+            name,
+            command: ProgramCommand::Shell(command),
+            working_directory: base_dir.to_path_buf(),
+            ..Default::default()
+        })
+        .collect();
+    Ok(Configuration {
+        namespace: DEFAULT_NAMESPACE.to_owned(),
+        app_order: apps.iter().map(|spec| spec.name.clone()).collect(),
+        apps,
+        dedup_logs: false,
+        notify_on_crash: false,
+        bell_on_crash: false,
+        log_lines: DEFAULT_LOG_LINES,
+        capture_interval: DEFAULT_CAPTURE_INTERVAL,
+        poll_interval: DEFAULT_POLL_INTERVAL,
+        tick_interval: DEFAULT_TICK_INTERVAL,
+        log_dir: None,
+        log_timestamps: LogTimestamps::None,
+        group_by: None,
+        tab_title_template: DEFAULT_TAB_TITLE_TEMPLATE.to_owned(),
+        tmux_socket: None,
+        profiles: HashMap::new(),
+        before_start: None,
+        after_stop: None,
+        keymap: Keymap::default(),
+        status_style: StatusStyle::default(),
+    })
+}
+
+pub(crate) fn load_procfile(
+    file_path: &Path,
+    base_dir_override: Option<&Path>,
+) -> Result<Configuration, Box<dyn Error>> {
+    let base_dir = base_dir_override.unwrap_or_else(|| file_path.parent().unwrap());
+    let contents = std::fs::read_to_string(file_path)?;
+    Ok(procfile_to_config(base_dir, &contents)?)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        config::ProgramCommand,
+        procfile::{ProcfileError, parse_procfile, procfile_to_config},
+    };
+
+    #[test]
+    fn test_parse_procfile() {
+        let contents = r#"
+# Processes for local development
+web: bundle exec rails server -p $PORT
+
+worker:   bundle exec sidekiq -C config/sidekiq.yml
+  # indented comment
+release: bin/rails db:migrate
+"#;
+        assert_eq!(
+            parse_procfile(contents).unwrap(),
+            vec![
+                (
+                    "web".to_owned(),
+                    "bundle exec rails server -p $PORT".to_owned()
+                ),
+                (
+                    "worker".to_owned(),
+                    "bundle exec sidekiq -C config/sidekiq.yml".to_owned()
+                ),
+                ("release".to_owned(), "bin/rails db:migrate".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_procfile_rejects_bad_lines() {
+        assert_eq!(
+            parse_procfile("web: rails s\nnot a process\n"),
+            Err(ProcfileError::InvalidLine(2, "not a process".to_owned()))
+        );
+        assert_eq!(
+            parse_procfile("web:\n"),
+            Err(ProcfileError::InvalidLine(1, "web:".to_owned()))
+        );
+        assert_eq!(
+            parse_procfile("web: a\nweb: b\n"),
+            Err(ProcfileError::DuplicateProcess("web".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_procfile_to_config() {
+        let config = procfile_to_config(Path::new("/srv/app"), "web: rails s\n").unwrap();
+        assert_eq!(config.apps.len(), 1);
+        let web = &config.apps[0];
+        assert_eq!(web.name, "web");
+        assert_eq!(web.command, ProgramCommand::Shell("rails s".to_owned()));
+        assert_eq!(web.working_directory, Path::new("/srv/app"));
+        assert!(web.deps.is_empty());
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    io,
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::Sender,
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    apps::AppEvent,
+    config::{ProgramSpec, ReadinessProbe},
+    processes::shell_command_succeeds,
+    tmux::{TmuxServer, capture_pane_history},
+};
+
+pub(crate) const DEFAULT_READY_TIMEOUT: Duration = Duration::from_millis(30000);
+
+const DEFAULT_PORT_FREE_TIMEOUT: Duration = Duration::from_millis(10000);
+
+const PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Why an app's `wait_port_free` address never became free.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PortWaitError {
+    /// Something was still listening when the timeout ran out.
+    StillInUse(String, Duration),
+    /// The address couldn't be probed at all, e.g. an unknown host.
+    ProbeFailed(String, String),
+}
+
+impl std::fmt::Display for PortWaitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortWaitError::StillInUse(address, timeout) => {
+                write!(f, "{} was still in use after {:?}", address, timeout)
+            }
+            PortWaitError::ProbeFailed(address, reason) => {
+                write!(f, "could not check whether {} is free: {}", address, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PortWaitError {}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ProbeOutcome {
+    Ready,
+    NotReady,
+    Failed(String),
+}
+
+/// Errors that mean the app just isn't listening yet, as opposed to a
+/// misconfigured address that waiting won't fix.
+fn is_retryable_connect_error(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+    )
+}
+
+fn probe_tcp(address: &str) -> ProbeOutcome {
+    let addrs = match address.to_socket_addrs() {
+        Ok(addrs) => addrs,
+        Err(e) => return ProbeOutcome::Failed(format!("cannot resolve {}: {}", address, e)),
+    };
+    let mut outcome = ProbeOutcome::Failed(format!("{} resolved to no addresses", address));
+    for a in addrs {
+        match TcpStream::connect_timeout(&a, PROBE_INTERVAL) {
+            Ok(_) => return ProbeOutcome::Ready,
+            Err(e) if is_retryable_connect_error(e.kind()) => outcome = ProbeOutcome::NotReady,
+            Err(e) => {
+                if outcome != ProbeOutcome::NotReady {
+                    outcome = ProbeOutcome::Failed(format!("cannot connect to {}: {}", a, e));
+                }
+            }
+        }
+    }
+    outcome
+}
+
+/// Polls `address` until nothing accepts connections on it, for a port a
+/// previous run's process may still be releasing.
+pub(crate) fn wait_for_port_free(address: &str, timeout: Duration) -> Result<(), PortWaitError> {
+    let start_at = SystemTime::now();
+    loop {
+        match probe_tcp(address) {
+            ProbeOutcome::NotReady => return Ok(()),
+            ProbeOutcome::Failed(reason) => {
+                return Err(PortWaitError::ProbeFailed(address.to_owned(), reason));
+            }
+            ProbeOutcome::Ready => {}
+        }
+        if start_at.elapsed().unwrap_or(Duration::from_millis(0)) >= timeout {
+            return Err(PortWaitError::StillInUse(address.to_owned(), timeout));
+        }
+        thread::sleep(PROBE_INTERVAL);
+    }
+}
+
+/// Waits for the app's `wait_port_free` address, if it has one, to be free.
+pub(crate) fn ensure_port_free(spec: &ProgramSpec) -> Result<(), PortWaitError> {
+    match spec.wait_port_free.as_deref() {
+        Some(address) => wait_for_port_free(
+            address,
+            spec.port_free_timeout.unwrap_or(DEFAULT_PORT_FREE_TIMEOUT),
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Looks for `pattern` in the session's pane. A pane that can't be read yet
+/// just isn't ready.
+fn probe_log_pattern(server: &TmuxServer, session_name: &str, pattern: &str) -> ProbeOutcome {
+    match capture_pane_history(server, session_name) {
+        Some(output) if output.contains(pattern) => ProbeOutcome::Ready,
+        _ => ProbeOutcome::NotReady,
+    }
+}
+
+fn probe_once(
+    spec: &ProgramSpec,
+    probe: &ReadinessProbe,
+    server: &TmuxServer,
+    session_name: &str,
+) -> ProbeOutcome {
+    match probe {
+        ReadinessProbe::Tcp(address) => probe_tcp(address),
+        ReadinessProbe::Command(command) => {
+            if shell_command_succeeds(command, &spec.working_directory) {
+                ProbeOutcome::Ready
+            } else {
+                ProbeOutcome::NotReady
+            }
+        }
+        ReadinessProbe::LogPattern(pattern) => probe_log_pattern(server, session_name, pattern),
+    }
+}
+
+/// Polls the app's readiness probe until it succeeds or `ready_timeout` elapses.
+/// Returns `None` when the app has no probe configured.
+pub(crate) fn wait_for_ready(
+    out_chan: &Sender<AppEvent>,
+    spec: &ProgramSpec,
+    server: &TmuxServer,
+    session_name: &str,
+) -> Option<JoinHandle<()>> {
+    let probe = spec.readiness.clone()?;
+    let spec = spec.clone();
+    let server = server.clone();
+    let session_name = session_name.to_owned();
+    let tx = out_chan.clone();
+    Some(thread::spawn(move || {
+        let timeout = spec.ready_timeout.unwrap_or(DEFAULT_READY_TIMEOUT);
+        let start_at = SystemTime::now();
+        loop {
+            match probe_once(&spec, &probe, &server, &session_name) {
+                ProbeOutcome::Ready => {
+                    let _ = tx.send(AppEvent::AppReady(spec.name));
+                    return;
+                }
+                ProbeOutcome::Failed(reason) => {
+                    let _ = tx.send(AppEvent::AppReadyFailed(spec.name, reason));
+                    return;
+                }
+                ProbeOutcome::NotReady => {}
+            }
+            if start_at.elapsed().unwrap_or(Duration::from_millis(0)) >= timeout {
+                let _ = tx.send(AppEvent::AppReadyTimeout(spec.name));
+                return;
+            }
+            thread::sleep(PROBE_INTERVAL);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::TcpListener, time::Duration};
+
+    use crate::{
+        readiness::{
+            PortWaitError, ProbeOutcome, probe_log_pattern, probe_tcp, wait_for_port_free,
+        },
+        tmux::TmuxServer,
+    };
+
+    #[test]
+    fn test_closed_port_keeps_polling() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        assert_eq!(
+            probe_tcp(&format!("127.0.0.1:{}", port)),
+            ProbeOutcome::NotReady
+        );
+    }
+
+    #[test]
+    fn test_open_port_is_ready() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_eq!(
+            probe_tcp(&format!("127.0.0.1:{}", port)),
+            ProbeOutcome::Ready
+        );
+    }
+
+    #[test]
+    fn test_invalid_host_fails_fast() {
+        assert!(matches!(
+            probe_tcp("devplexer-no-such-host.invalid:5432"),
+            ProbeOutcome::Failed(_)
+        ));
+        assert!(matches!(probe_tcp("localhost"), ProbeOutcome::Failed(_)));
+    }
+
+    #[test]
+    fn test_log_pattern_waits_for_missing_pane() {
+        // Nothing runs on this socket, so there is no output to match yet.
+        let server = TmuxServer::new(Some("devplexer-test-log-pattern".to_owned()));
+        assert_eq!(
+            probe_log_pattern(&server, "ns-web", "Listening on"),
+            ProbeOutcome::NotReady
+        );
+    }
+
+    #[test]
+    fn test_wait_for_port_free() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let still_in_use = wait_for_port_free(&address, Duration::from_millis(300));
+        assert_eq!(
+            still_in_use,
+            Err(PortWaitError::StillInUse(
+                address.clone(),
+                Duration::from_millis(300)
+            ))
+        );
+        assert_eq!(
+            still_in_use.unwrap_err().to_string(),
+            format!("{} was still in use after 300ms", address)
+        );
+        drop(listener);
+        assert_eq!(
+            wait_for_port_free(&address, Duration::from_millis(300)),
+            Ok(())
+        );
+    }
+}
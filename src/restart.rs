@@ -0,0 +1,65 @@
+use std::{process::ExitStatus, sync::mpsc::Sender, thread, time::Duration};
+
+use crate::{apps::AppEvent, config::RestartPolicy};
+
+/// Delay before the first automatic restart; it doubles with each
+/// consecutive failure up to `RESTART_BACKOFF_CAP`.
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(1);
+pub(crate) const RESTART_BACKOFF_CAP: Duration = Duration::from_secs(60);
+
+/// Whether an app that exited with `exit_status` should be relaunched.
+/// An unknown exit status counts as a failure.
+pub(crate) fn should_restart(policy: RestartPolicy, exit_status: Option<ExitStatus>) -> bool {
+    match policy {
+        RestartPolicy::Never => false,
+        RestartPolicy::Always => true,
+        RestartPolicy::OnFailure => !exit_status.is_some_and(|es| es.success()),
+    }
+}
+
+/// The delay before restarting after `consecutive_failures` failures in a row.
+pub(crate) fn restart_backoff(consecutive_failures: u32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    RESTART_BACKOFF_BASE
+        .saturating_mul(1 << exponent)
+        .min(RESTART_BACKOFF_CAP)
+}
+
+/// Sends `AppEvent::RestartApp` for the app once `delay` has passed.
+pub(crate) fn schedule_restart(out_chan: &Sender<AppEvent>, app_name: &str, delay: Duration) {
+    let tx = out_chan.clone();
+    let app_name = app_name.to_owned();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let _ = tx.send(AppEvent::RestartApp(app_name));
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::{os::unix::process::ExitStatusExt, process::ExitStatus, time::Duration};
+
+    use crate::{
+        config::RestartPolicy,
+        restart::{RESTART_BACKOFF_CAP, restart_backoff, should_restart},
+    };
+
+    #[test]
+    fn test_should_restart() {
+        let success = Some(ExitStatus::from_raw(0));
+        let failure = Some(ExitStatus::from_raw(1 << 8));
+        assert!(!should_restart(RestartPolicy::Never, failure));
+        assert!(should_restart(RestartPolicy::Always, success));
+        assert!(!should_restart(RestartPolicy::OnFailure, success));
+        assert!(should_restart(RestartPolicy::OnFailure, failure));
+        assert!(should_restart(RestartPolicy::OnFailure, None));
+    }
+
+    #[test]
+    fn test_restart_backoff_doubles_up_to_cap() {
+        assert_eq!(restart_backoff(1), Duration::from_secs(1));
+        assert_eq!(restart_backoff(2), Duration::from_secs(2));
+        assert_eq!(restart_backoff(4), Duration::from_secs(8));
+        assert_eq!(restart_backoff(30), RESTART_BACKOFF_CAP);
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use crate::config::{ProgramSpec, ReadyTimeoutPolicy};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum LaunchState {
+    Pending,
+    Launched,
+    Ready,
+    /// A `oneshot` app that exited 0.
+    Completed,
+    Skipped,
+    Failed,
+}
+
+/// Tracks which apps may be launched, releasing each app only once every
+/// one of its `deps` is ready, or for `oneshot` deps, has completed.
+pub(crate) struct StartupScheduler {
+    apps: Vec<ProgramSpec>,
+    states: HashMap<String, LaunchState>,
+}
+
+impl StartupScheduler {
+    /// `apps` must already be in dependency order, as loaded by `config`.
+    pub(crate) fn new(apps: &[ProgramSpec]) -> Self {
+        StartupScheduler {
+            apps: apps.to_vec(),
+            states: HashMap::from_iter(apps.iter().map(|a| (a.name.clone(), LaunchState::Pending))),
+        }
+    }
+
+    /// Puts every app back to pending, for launching them all again.
+    pub(crate) fn reset(&mut self) {
+        for state in self.states.values_mut() {
+            *state = LaunchState::Pending;
+        }
+    }
+
+    pub(crate) fn state(&self, app_name: &str) -> Option<LaunchState> {
+        self.states.get(app_name).copied()
+    }
+
+    fn deps_in_state(&self, spec: &ProgramSpec, f: impl Fn(LaunchState) -> bool) -> Vec<bool> {
+        spec.deps
+            .iter()
+            .map(|d| self.state(d).map(&f).unwrap_or(false))
+            .collect()
+    }
+
+    fn is_oneshot(&self, app_name: &str) -> bool {
+        self.apps.iter().any(|a| a.name == app_name && a.oneshot)
+    }
+
+    /// Returns the pending apps whose dependencies are all ready, marking them launched.
+    pub(crate) fn take_launchable(&mut self) -> Vec<ProgramSpec> {
+        let launchable: Vec<ProgramSpec> = self
+            .apps
+            .iter()
+            .filter(|a| self.state(&a.name) == Some(LaunchState::Pending))
+            .filter(|a| {
+                self.deps_in_state(a, |s| {
+                    s == LaunchState::Ready || s == LaunchState::Completed
+                })
+                .iter()
+                .all(|r| *r)
+            })
+            .cloned()
+            .collect();
+        for a in launchable.iter() {
+            self.states.insert(a.name.clone(), LaunchState::Launched);
+        }
+        launchable
+    }
+
+    /// Returns the pending apps that can never launch because a dependency was
+    /// skipped or failed, each with that dependency, marking them skipped in turn.
+    pub(crate) fn take_blocked(&mut self) -> Vec<(String, String)> {
+        let mut blocked = Vec::new();
+        for i in 0..self.apps.len() {
+            let spec = &self.apps[i];
+            if self.state(&spec.name) != Some(LaunchState::Pending) {
+                continue;
+            }
+            let blocking_dep = spec.deps.iter().find(|d| {
+                matches!(
+                    self.state(d),
+                    Some(LaunchState::Skipped) | Some(LaunchState::Failed)
+                )
+            });
+            if let Some(dep) = blocking_dep {
+                let name = spec.name.clone();
+                blocked.push((name.clone(), dep.clone()));
+                self.states.insert(name, LaunchState::Skipped);
+            }
+        }
+        blocked
+    }
+
+    /// One-shot apps release their dependents on completion rather than
+    /// readiness, so this leaves them launched.
+    pub(crate) fn mark_ready(&mut self, app_name: &str) {
+        if self.state(app_name) == Some(LaunchState::Launched) && !self.is_oneshot(app_name) {
+            self.states.insert(app_name.to_owned(), LaunchState::Ready);
+        }
+    }
+
+    pub(crate) fn mark_completed(&mut self, app_name: &str) {
+        if self.state(app_name) == Some(LaunchState::Launched) {
+            self.states
+                .insert(app_name.to_owned(), LaunchState::Completed);
+        }
+    }
+
+    pub(crate) fn mark_skipped(&mut self, app_name: &str) {
+        self.states
+            .insert(app_name.to_owned(), LaunchState::Skipped);
+    }
+
+    /// An app that fails after becoming ready has already released its dependents.
+    pub(crate) fn mark_failed(&mut self, app_name: &str) {
+        if self.state(app_name) == Some(LaunchState::Launched) {
+            self.states.insert(app_name.to_owned(), LaunchState::Failed);
+        }
+    }
+
+    /// Applies the app's `on_ready_timeout` policy.
+    pub(crate) fn mark_ready_timed_out(&mut self, app_name: &str) {
+        let policy = self
+            .apps
+            .iter()
+            .find(|a| a.name == app_name)
+            .map(|a| a.on_ready_timeout)
+            .unwrap_or_default();
+        match policy {
+            ReadyTimeoutPolicy::Continue => self.mark_ready(app_name),
+            ReadyTimeoutPolicy::Fail => self.mark_failed(app_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        config::{ProgramSpec, ReadyTimeoutPolicy},
+        scheduler::{LaunchState, StartupScheduler},
+    };
+
+    fn spec(name: &str, deps: &[&str]) -> ProgramSpec {
+        ProgramSpec {
+            name: name.to_owned(),
+            deps: deps.iter().map(|d| d.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn names(specs: Vec<ProgramSpec>) -> Vec<String> {
+        specs.into_iter().map(|s| s.name).collect()
+    }
+
+    #[test]
+    fn test_dependents_wait_for_ready() {
+        let mut scheduler = StartupScheduler::new(&[
+            spec("db", &[]),
+            spec("cache", &[]),
+            spec("api", &["db", "cache"]),
+            spec("worker", &["api"]),
+        ]);
+        assert_eq!(names(scheduler.take_launchable()), vec!["db", "cache"]);
+        assert!(scheduler.take_launchable().is_empty());
+        scheduler.mark_ready("db");
+        assert!(scheduler.take_launchable().is_empty());
+        scheduler.mark_ready("cache");
+        assert_eq!(names(scheduler.take_launchable()), vec!["api"]);
+        scheduler.mark_ready("api");
+        assert_eq!(names(scheduler.take_launchable()), vec!["worker"]);
+    }
+
+    #[test]
+    fn test_failed_and_skipped_deps_block_dependents() {
+        let mut scheduler = StartupScheduler::new(&[
+            spec("db", &[]),
+            spec("api", &["db"]),
+            spec("worker", &["api"]),
+            spec("s3-mock", &[]),
+            spec("uploader", &["s3-mock"]),
+        ]);
+        scheduler.take_launchable();
+        scheduler.mark_failed("db");
+        scheduler.mark_skipped("s3-mock");
+        assert_eq!(
+            scheduler.take_blocked(),
+            vec![
+                ("api".to_owned(), "db".to_owned()),
+                ("worker".to_owned(), "api".to_owned()),
+                ("uploader".to_owned(), "s3-mock".to_owned()),
+            ]
+        );
+        assert_eq!(scheduler.state("worker"), Some(LaunchState::Skipped));
+        assert!(scheduler.take_launchable().is_empty());
+    }
+
+    #[test]
+    fn test_reset_relaunches_in_dependency_order() {
+        let mut scheduler =
+            StartupScheduler::new(&[spec("db", &[]), spec("api", &["db"]), spec("lint", &[])]);
+        scheduler.take_launchable();
+        scheduler.mark_ready("db");
+        scheduler.take_launchable();
+        scheduler.mark_failed("lint");
+        scheduler.reset();
+        assert_eq!(scheduler.state("api"), Some(LaunchState::Pending));
+        assert_eq!(names(scheduler.take_launchable()), vec!["db", "lint"]);
+        scheduler.mark_ready("db");
+        assert_eq!(names(scheduler.take_launchable()), vec!["api"]);
+    }
+
+    #[test]
+    fn test_ready_timeout_policy() {
+        let mut lenient = spec("db", &[]);
+        lenient.on_ready_timeout = ReadyTimeoutPolicy::Continue;
+        let mut scheduler = StartupScheduler::new(&[
+            lenient,
+            spec("cache", &[]),
+            spec("api", &["db"]),
+            spec("worker", &["cache"]),
+        ]);
+        scheduler.take_launchable();
+        scheduler.mark_ready_timed_out("db");
+        scheduler.mark_ready_timed_out("cache");
+        assert_eq!(scheduler.state("db"), Some(LaunchState::Ready));
+        assert_eq!(scheduler.state("cache"), Some(LaunchState::Failed));
+        assert_eq!(names(scheduler.take_launchable()), vec!["api"]);
+        assert_eq!(
+            scheduler.take_blocked(),
+            vec![("worker".to_owned(), "cache".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_oneshot_deps_wait_for_completion() {
+        let mut migrate = spec("migrate", &[]);
+        migrate.oneshot = true;
+        let mut seed = spec("seed", &[]);
+        seed.oneshot = true;
+        let mut scheduler = StartupScheduler::new(&[
+            migrate,
+            seed,
+            spec("web", &["migrate"]),
+            spec("worker", &["seed"]),
+        ]);
+        assert_eq!(names(scheduler.take_launchable()), vec!["migrate", "seed"]);
+        scheduler.mark_ready("migrate");
+        assert_eq!(scheduler.state("migrate"), Some(LaunchState::Launched));
+        assert!(scheduler.take_launchable().is_empty());
+        scheduler.mark_completed("migrate");
+        assert_eq!(names(scheduler.take_launchable()), vec!["web"]);
+        scheduler.mark_failed("seed");
+        assert_eq!(
+            scheduler.take_blocked(),
+            vec![("worker".to_owned(), "seed".to_owned())]
+        );
+        assert!(scheduler.take_launchable().is_empty());
+    }
+}
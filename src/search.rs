@@ -0,0 +1,192 @@
+use std::{collections::VecDeque, ops::Range};
+
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use crate::ansi::strip_ansi;
+
+/// A search of the log pane, started with `/`.
+#[derive(Debug, Default)]
+pub(crate) struct LogSearch {
+    pub(crate) query: String,
+    /// Whether the query is still being typed.
+    pub(crate) editing: bool,
+    pub(crate) case_sensitive: bool,
+}
+
+impl LogSearch {
+    /// The query to highlight, once one has been entered.
+    pub(crate) fn active_query(&self) -> Option<&str> {
+        Some(self.query.as_str()).filter(|q| !q.is_empty())
+    }
+}
+
+fn chars_match(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
+}
+
+/// The byte ranges of the non-overlapping matches of `query` in `text`.
+pub(crate) fn find_matches(text: &str, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+    let mut pos = 0;
+    while let Some(first) = text[pos..].chars().next() {
+        let mut candidate = text[pos..].chars();
+        let mut len = 0;
+        let found = query.chars().all(|q| match candidate.next() {
+            Some(c) if chars_match(c, q, case_sensitive) => {
+                len += c.len_utf8();
+                true
+            }
+            _ => false,
+        });
+        if found {
+            matches.push(pos..pos + len);
+            pos += len;
+        } else {
+            pos += first.len_utf8();
+        }
+    }
+    matches
+}
+
+fn line_matches(line: &str, query: &str, case_sensitive: bool) -> bool {
+    !find_matches(&strip_ansi(line), query, case_sensitive).is_empty()
+}
+
+/// How many of `lines` contain a match.
+pub(crate) fn count_matching_lines(
+    lines: &VecDeque<String>,
+    query: &str,
+    case_sensitive: bool,
+) -> usize {
+    lines
+        .iter()
+        .filter(|l| line_matches(l, query, case_sensitive))
+        .count()
+}
+
+/// The index of the nearest matching line after `from` when `forward`, or
+/// before it otherwise, wrapping around the ends of the buffer.
+pub(crate) fn find_matching_line(
+    lines: &VecDeque<String>,
+    query: &str,
+    case_sensitive: bool,
+    from: usize,
+    forward: bool,
+) -> Option<usize> {
+    let len = lines.len();
+    (1..=len)
+        .map(|step| {
+            if forward {
+                (from + step) % len
+            } else {
+                (from + len - step % len) % len
+            }
+        })
+        .find(|i| line_matches(&lines[*i], query, case_sensitive))
+}
+
+/// Highlights every match of `query` in an already styled line, splitting
+/// spans where a match starts or ends part way through them.
+pub(crate) fn highlight_matches(
+    line: Line<'static>,
+    query: &str,
+    case_sensitive: bool,
+) -> Line<'static> {
+    let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    let matches = find_matches(&text, query, case_sensitive);
+    if matches.is_empty() {
+        return line;
+    }
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for span in line.spans {
+        let content = span.content.as_ref();
+        let span_range = offset..offset + content.len();
+        let mut cuts = vec![span_range.start, span_range.end];
+        for m in matches.iter() {
+            for edge in [m.start, m.end] {
+                if span_range.contains(&edge) {
+                    cuts.push(edge);
+                }
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+        for piece in cuts.windows(2) {
+            let part = Span::styled(
+                content[piece[0] - offset..piece[1] - offset].to_owned(),
+                span.style,
+            );
+            let in_match = matches
+                .iter()
+                .any(|m| m.start <= piece[0] && piece[1] <= m.end);
+            spans.push(if in_match {
+                part.black().on_yellow()
+            } else {
+                part
+            });
+        }
+        offset = span_range.end;
+    }
+    Line::from(spans)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use ratatui::{
+        style::{Color, Style, Stylize},
+        text::{Line, Span},
+    };
+
+    use crate::search::{find_matches, find_matching_line, highlight_matches};
+
+    #[test]
+    fn test_find_matches_case() {
+        assert_eq!(
+            find_matches("Error: error ERRÖR", "error", false),
+            vec![0..5, 7..12]
+        );
+        assert_eq!(find_matches("Error: error", "error", true), vec![7..12]);
+        assert_eq!(find_matches("ÉTÉ été", "été", false), vec![0..5, 6..11]);
+        assert_eq!(find_matches("aaaa", "aa", false), vec![0..2, 2..4]);
+        assert!(find_matches("anything", "", false).is_empty());
+    }
+
+    #[test]
+    fn test_find_matching_line_wraps() {
+        let lines: VecDeque<String> = ["boot", "\x1b[31mfail\x1b[0m 1", "ok", "fail 2", "ok"]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(find_matching_line(&lines, "FAIL", false, 4, false), Some(3));
+        assert_eq!(find_matching_line(&lines, "fail", false, 3, false), Some(1));
+        assert_eq!(find_matching_line(&lines, "fail", false, 1, false), Some(3));
+        assert_eq!(find_matching_line(&lines, "fail", false, 3, true), Some(1));
+        assert_eq!(find_matching_line(&lines, "fail", false, 1, true), Some(3));
+        assert_eq!(find_matching_line(&lines, "FAIL", true, 4, false), None);
+    }
+
+    #[test]
+    fn test_highlight_splits_styled_spans() {
+        let red = Style::default().fg(Color::Red);
+        let line = Line::from(vec![Span::styled("net err", red), Span::raw("or done")]);
+        let highlighted = highlight_matches(line, "error", false);
+        assert_eq!(
+            highlighted.spans,
+            vec![
+                Span::styled("net ", red),
+                Span::styled("err", red).black().on_yellow(),
+                Span::raw("or").black().on_yellow(),
+                Span::raw(" done"),
+            ]
+        );
+    }
+}
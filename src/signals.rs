@@ -0,0 +1,22 @@
+use std::{error::Error, sync::mpsc::Sender, thread};
+
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+};
+
+use crate::apps::AppEvent;
+
+/// Turns SIGINT and SIGTERM into `AppEvent::QuitKeyEvent`s so a signal shuts
+/// down the apps the same way pressing `q` does. The signals are handled on
+/// a dedicated thread rather than inside a signal handler.
+pub(crate) fn forward_quit_signals(out_chan: &Sender<AppEvent>) -> Result<(), Box<dyn Error>> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    let tx = out_chan.clone();
+    thread::spawn(move || {
+        for _signal in signals.forever() {
+            let _ = tx.send(AppEvent::QuitKeyEvent);
+        }
+    });
+    Ok(())
+}
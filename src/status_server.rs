@@ -0,0 +1,279 @@
+use std::{
+    io::{self, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex,
+        mpsc::{RecvTimeoutError, Sender, channel},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use log::warn;
+use sysinfo::Pid;
+
+use crate::json::{json_number, json_string};
+
+/// How long the server sleeps between checks for a connection or a stop
+/// request.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// One row of the status snapshot served over HTTP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct AppSnapshot {
+    pub(crate) name: String,
+    pub(crate) pid: Option<Pid>,
+    pub(crate) status: &'static str,
+    /// Whether the app's process is running.
+    pub(crate) up: bool,
+    /// Restarts since devplexer launched, kept across the app's own restarts.
+    pub(crate) restarts: u32,
+    pub(crate) exit_code: Option<i32>,
+}
+
+pub(crate) fn snapshot_json(apps: &[AppSnapshot]) -> String {
+    let entries: Vec<String> = apps
+        .iter()
+        .map(|app| {
+            format!(
+                "{{\"name\":{},\"pid\":{},\"status\":{},\"exit_code\":{}}}",
+                json_string(&app.name),
+                json_number(app.pid),
+                json_string(app.status),
+                json_number(app.exit_code),
+            )
+        })
+        .collect();
+    format!("{{\"apps\":[{}]}}", entries.join(","))
+}
+
+/// Escapes a Prometheus label value.
+fn metric_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The snapshot in the Prometheus text exposition format.
+pub(crate) fn snapshot_metrics(apps: &[AppSnapshot]) -> String {
+    let mut out = String::new();
+    let mut family =
+        |name: &str, kind: &str, help: &str, value: &dyn Fn(&AppSnapshot) -> Option<i64>| {
+            out.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} {}\n",
+                name, help, name, kind
+            ));
+            for app in apps {
+                if let Some(v) = value(app) {
+                    out.push_str(&format!(
+                        "{}{{app=\"{}\"}} {}\n",
+                        name,
+                        metric_label(&app.name),
+                        v
+                    ));
+                }
+            }
+        };
+    family(
+        "devplexer_app_up",
+        "gauge",
+        "Whether the app's process is running.",
+        &|app| Some(app.up as i64),
+    );
+    family(
+        "devplexer_app_restarts_total",
+        "counter",
+        "How many times the app has been restarted.",
+        &|app| Some(app.restarts as i64),
+    );
+    family(
+        "devplexer_app_exit_code",
+        "gauge",
+        "The exit code of the app's process, once it has exited.",
+        &|app| app.exit_code.map(|c| c as i64),
+    );
+    out
+}
+
+/// Serves the latest published snapshot as JSON on localhost, and as
+/// Prometheus metrics on `/metrics` when enabled.
+pub(crate) struct StatusServer {
+    snapshot: Arc<Mutex<Vec<AppSnapshot>>>,
+    stop_channel: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// The path of a request such as `GET /metrics HTTP/1.1`.
+fn request_path(request: &[u8]) -> Option<&str> {
+    let line = std::str::from_utf8(request).ok()?.lines().next()?;
+    line.split_whitespace().nth(1)
+}
+
+fn respond(
+    mut stream: TcpStream,
+    snapshot: &Mutex<Vec<AppSnapshot>>,
+    serve_metrics: bool,
+) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request).unwrap_or(0);
+    // Every other path gets the JSON snapshot.
+    let (content_type, body) = {
+        let apps = snapshot.lock().unwrap();
+        match request_path(&request[..read]) {
+            Some("/metrics") if serve_metrics => {
+                ("text/plain; version=0.0.4", snapshot_metrics(&apps))
+            }
+            _ => ("application/json", snapshot_json(&apps)),
+        }
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+impl StatusServer {
+    pub(crate) fn start(port: u16, serve_metrics: bool) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        let snapshot = Arc::new(Mutex::new(Vec::new()));
+        let served = snapshot.clone();
+        let (stop_channel, die_chan) = channel::<()>();
+        let handle = thread::spawn(move || {
+            loop {
+                match listener.accept() {
+                    Ok((stream, _addr)) => {
+                        if let Err(e) = respond(stream, &served, serve_metrics) {
+                            warn!("Could not answer status request: {}", e);
+                        }
+                        continue;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => warn!("Status server failed to accept a connection: {}", e),
+                }
+                match die_chan.recv_timeout(ACCEPT_INTERVAL) {
+                    Err(RecvTimeoutError::Timeout) => {}
+                    _ => break,
+                }
+            }
+        });
+        Ok(StatusServer {
+            snapshot,
+            stop_channel,
+            handle,
+        })
+    }
+
+    pub(crate) fn publish(&self, apps: &[AppSnapshot]) {
+        *self.snapshot.lock().unwrap() = apps.to_vec();
+    }
+
+    pub(crate) fn stop(self) {
+        let _ = self.stop_channel.send(());
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    use sysinfo::Pid;
+
+    use crate::status_server::{AppSnapshot, StatusServer, snapshot_json, snapshot_metrics};
+
+    fn sample_apps() -> Vec<AppSnapshot> {
+        vec![
+            AppSnapshot {
+                name: "api".to_owned(),
+                pid: None,
+                status: "dead",
+                up: false,
+                restarts: 2,
+                exit_code: Some(1),
+            },
+            AppSnapshot {
+                name: "web".to_owned(),
+                pid: Some(Pid::from_u32(42)),
+                status: "running",
+                up: true,
+                restarts: 0,
+                exit_code: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_snapshot_json() {
+        assert_eq!(
+            snapshot_json(&sample_apps()),
+            r#"{"apps":[{"name":"api","pid":null,"status":"dead","exit_code":1},{"name":"web","pid":42,"status":"running","exit_code":null}]}"#
+        );
+    }
+
+    #[test]
+    fn test_snapshot_metrics() {
+        assert_eq!(
+            snapshot_metrics(&sample_apps()),
+            r#"# HELP devplexer_app_up Whether the app's process is running.
+# TYPE devplexer_app_up gauge
+devplexer_app_up{app="api"} 0
+devplexer_app_up{app="web"} 1
+# HELP devplexer_app_restarts_total How many times the app has been restarted.
+# TYPE devplexer_app_restarts_total counter
+devplexer_app_restarts_total{app="api"} 2
+devplexer_app_restarts_total{app="web"} 0
+# HELP devplexer_app_exit_code The exit code of the app's process, once it has exited.
+# TYPE devplexer_app_exit_code gauge
+devplexer_app_exit_code{app="api"} 1
+"#
+        );
+    }
+
+    fn get(port: u16, path: &str) -> String {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn free_port() -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn test_answers_http_request() {
+        let port = free_port();
+        let server = StatusServer::start(port, false).unwrap();
+        server.publish(&sample_apps());
+        let response = get(port, "/");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(&snapshot_json(&sample_apps())));
+        assert!(get(port, "/metrics").ends_with(&snapshot_json(&sample_apps())));
+        server.stop();
+    }
+
+    #[test]
+    fn test_serves_metrics_when_enabled() {
+        let port = free_port();
+        let server = StatusServer::start(port, true).unwrap();
+        server.publish(&sample_apps());
+        let response = get(port, "/metrics");
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+        assert!(response.ends_with(&snapshot_metrics(&sample_apps())));
+        assert!(get(port, "/").ends_with(&snapshot_json(&sample_apps())));
+        server.stop();
+    }
+}
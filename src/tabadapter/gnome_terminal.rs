@@ -0,0 +1,67 @@
+use std::{collections::HashMap, error::Error, process::Command};
+
+use tmux_interface::DetachClient;
+
+use crate::{
+    tabadapter::{AppTab, TabAdapter, titled_attach_command},
+    tmux::TmuxServer,
+};
+
+/// Opens one gnome-terminal tab per app, each running a tmux attach.
+pub(crate) struct GnomeTerminalAdapter {
+    server: TmuxServer,
+    tab_commands: HashMap<String, String>,
+}
+
+impl GnomeTerminalAdapter {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
+        Ok(GnomeTerminalAdapter {
+            server: server.clone(),
+            tab_commands: HashMap::new(),
+        })
+    }
+}
+
+impl TabAdapter for GnomeTerminalAdapter {
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let cmd_string = spawn_gnome_terminal_tab(&self.server, tab)?;
+        self.tab_commands
+            .insert(tab.session_name.to_owned(), cmd_string);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        // gnome-terminal has no handle for closing a tab, but the tab closes
+        // itself once its attach client goes away.
+        if self.tab_commands.remove(session_name).is_some() {
+            self.server
+                .tmux(DetachClient::new().target_session(session_name).build())
+                .status()?;
+        }
+        Ok(())
+    }
+
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+pub(crate) fn gnome_terminal_installed() -> bool {
+    Command::new("gnome-terminal")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn spawn_gnome_terminal_tab(server: &TmuxServer, tab: &AppTab) -> Result<String, Box<dyn Error>> {
+    let cmd_string = titled_attach_command(server, tab)?;
+    Command::new("gnome-terminal")
+        .args(["--tab", "--", "bash", "-c", &cmd_string])
+        .status()?;
+    Ok(cmd_string)
+}
@@ -2,46 +2,82 @@ use std::{
     collections::HashMap, error::Error, ffi::OsStr, os::unix::ffi::OsStrExt, process::Command,
 };
 
+use log::warn;
 use osakit::{Script, Value};
 
-use crate::{tabadapter::TabAdapter, tmux::attach_session_command_for_cli};
+use crate::{
+    tabadapter::{
+        AppTab, TabAdapter, osx_terminal::OsxTerminalAdapter, spawn_with_retries,
+        titled_attach_command,
+    },
+    tmux::TmuxServer,
+};
 
 pub(crate) struct ITermTabAdapter {
+    server: TmuxServer,
     current_session: Value,
     iterm_mappings: HashMap<String, Value>,
+    /// Opens a Terminal tab instead for sessions iTerm refused, e.g. when
+    /// automation permission was denied, so no session is left invisible.
+    fallback: OsxTerminalAdapter,
 }
 
 impl ITermTabAdapter {
-    pub(crate) fn new() -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
         let cs = get_original_session()?;
         Ok(ITermTabAdapter {
+            server: server.clone(),
             current_session: cs,
             iterm_mappings: HashMap::new(),
+            fallback: OsxTerminalAdapter::new(server)?,
         })
     }
 }
 
 impl TabAdapter for ITermTabAdapter {
-    fn open(&mut self, session_name: &str) {
-        let spawn_res = spawn_iterm_tab(session_name);
-        if let Ok(sr) = spawn_res {
-            self.iterm_mappings.insert(session_name.to_owned(), sr);
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        match spawn_with_retries(tab.session_name, || spawn_iterm_tab(&self.server, tab)) {
+            Ok(sr) => {
+                self.iterm_mappings.insert(tab.session_name.to_owned(), sr);
+                Ok(())
+            }
+            Err(e) => {
+                warn!(
+                    "Could not open an iTerm tab for {}, opening it in Terminal instead: {}",
+                    tab.session_name, e
+                );
+                self.fallback.open(tab)
+            }
         }
     }
 
-    fn close(&mut self, session_name: &str) {
-        if let Some(v) = self.iterm_mappings.get(session_name) {
-            let _ = cleanup_iterm_tab(v);
-            self.iterm_mappings.remove(session_name);
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        match self.iterm_mappings.remove(session_name) {
+            Some(v) => cleanup_iterm_tab(&v),
+            None => self.fallback.close(session_name),
         }
     }
 
-    fn after_all_open(&mut self) {
-        let _ = refocus_original_session(&self.current_session);
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        refocus_original_session(&self.current_session)
+    }
+
+    fn closed_tabs(&mut self) -> Vec<String> {
+        let mut closed: Vec<String> = self
+            .iterm_mappings
+            .iter()
+            .filter(|(_, sid)| !iterm_session_exists(sid))
+            .map(|(session_name, _)| session_name.clone())
+            .collect();
+        for session_name in closed.iter() {
+            self.iterm_mappings.remove(session_name);
+        }
+        closed.extend(self.fallback.closed_tabs());
+        closed
     }
 
-    fn after_all_closed(&mut self) {
-        let _ = refocus_original_session(&self.current_session);
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        refocus_original_session(&self.current_session)
     }
 }
 
@@ -91,6 +127,35 @@ fn get_original_session() -> Result<Value, Box<dyn Error>> {
     Ok(r.unwrap())
 }
 
+/// Whether iTerm still has the session. A failed check counts as open, so
+/// a flaky AppleScript call doesn't mark every tab closed.
+fn iterm_session_exists(t: &Value) -> bool {
+    let mut script = Script::new_from_source(
+        osakit::Language::AppleScript,
+        "on session_exists(x)
+            tell application \"iTerm\"
+               	repeat with aWindow in windows
+                  repeat with aTab in tabs of aWindow
+                    repeat with aSession in sessions of aTab
+                      if id of aSession is x then
+                        return true
+                      end if
+                    end repeat
+                  end repeat
+               	end repeat
+            end tell
+            return false
+        end session_exists",
+    );
+    if script.compile().is_err() {
+        return true;
+    }
+    !matches!(
+        script.execute_function("session_exists", vec![t.clone()]),
+        Ok(Value::Bool(false))
+    )
+}
+
 fn refocus_original_session(t: &Value) -> Result<(), Box<dyn Error>> {
     if t.is_null() {
         return Ok(());
@@ -120,7 +185,7 @@ fn refocus_original_session(t: &Value) -> Result<(), Box<dyn Error>> {
         end focus_original_tab",
     );
     script.compile()?;
-    let _r = script.execute_function("focus_original_tab", vec![t.clone()]);
+    script.execute_function("focus_original_tab", vec![t.clone()])?;
     Ok(())
 }
 
@@ -150,12 +215,12 @@ fn cleanup_iterm_tab(t: &Value) -> Result<(), Box<dyn Error>> {
         end close_tmux_tab",
     );
     script.compile()?;
-    let _r = script.execute_function("close_tmux_tab", vec![t.clone()])?;
+    script.execute_function("close_tmux_tab", vec![t.clone()])?;
     Ok(())
 }
 
-fn spawn_iterm_tab(session_name: &str) -> Result<Value, Box<dyn Error>> {
-    let cmd_string = attach_session_command_for_cli(session_name)?;
+fn spawn_iterm_tab(server: &TmuxServer, tab: &AppTab) -> Result<Value, Box<dyn Error>> {
+    let cmd_string = titled_attach_command(server, tab)?;
     let cmd_str = osakit::Value::String(cmd_string);
     let mut script = Script::new_from_source(
         osakit::Language::AppleScript,
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, error::Error, process::Command};
+
+use crate::{
+    tabadapter::{AppTab, TabAdapter},
+    tmux::{TmuxServer, attach_session_command_for_cli},
+};
+
+/// Opens one kitty tab per app through kitty's remote control.
+pub(crate) struct KittyTabAdapter {
+    server: TmuxServer,
+    kitty_window_ids: HashMap<String, String>,
+}
+
+impl KittyTabAdapter {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
+        Ok(KittyTabAdapter {
+            server: server.clone(),
+            kitty_window_ids: HashMap::new(),
+        })
+    }
+}
+
+impl TabAdapter for KittyTabAdapter {
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let window_id = spawn_kitty_tab(&self.server, tab)?;
+        self.kitty_window_ids
+            .insert(tab.session_name.to_owned(), window_id);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        // A window already closed by hand makes kitty exit nonzero, which
+        // isn't worth reporting.
+        if let Some(window_id) = self.kitty_window_ids.remove(session_name) {
+            Command::new("kitty")
+                .args(["@", "close-window", "--match"])
+                .arg(format!("id:{}", window_id))
+                .output()?;
+        }
+        Ok(())
+    }
+
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Whether kitty's remote control is reachable from this process.
+pub(crate) fn kitty_remote_control_available() -> bool {
+    Command::new("kitty")
+        .args(["@", "ls"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn spawn_kitty_tab(server: &TmuxServer, tab: &AppTab) -> Result<String, Box<dyn Error>> {
+    let cmd_string = attach_session_command_for_cli(server, tab.session_name, tab.read_only)?;
+    let output = Command::new("kitty")
+        .args(["@", "launch", "--type=tab", "--keep-focus", "--tab-title"])
+        .arg(tab.title)
+        .args(["sh", "-c"])
+        .arg(&cmd_string)
+        .output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into());
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
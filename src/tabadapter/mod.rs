@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{error::Error, thread, time::Duration};
 
 #[cfg(target_os = "macos")]
 mod iterm;
@@ -6,6 +6,22 @@ mod iterm;
 #[cfg(target_os = "macos")]
 mod osx_terminal;
 
+#[cfg(target_os = "linux")]
+mod gnome_terminal;
+
+#[cfg(target_os = "linux")]
+mod windows_terminal;
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod kitty;
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod wezterm;
+
+mod tmux_window;
+
+pub(crate) use crate::tabadapter::tmux_window::TmuxWindowAdapter;
+
 #[cfg(target_os = "macos")]
 use crate::tabadapter::iterm::ITermTabAdapter;
 
@@ -15,30 +31,207 @@ use crate::tabadapter::iterm::iterm_installed;
 #[cfg(target_os = "macos")]
 use crate::tabadapter::osx_terminal::OsxTerminalAdapter;
 
-use log::info;
+#[cfg(target_os = "linux")]
+use crate::tabadapter::gnome_terminal::{GnomeTerminalAdapter, gnome_terminal_installed};
+
+#[cfg(target_os = "linux")]
+use crate::tabadapter::windows_terminal::{WindowsTerminalAdapter, windows_terminal_available};
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use crate::tabadapter::kitty::{KittyTabAdapter, kitty_remote_control_available};
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use crate::tabadapter::wezterm::{WezTermTabAdapter, wezterm_cli_available};
+
+use log::{info, warn};
+
+use crate::{
+    shell::shell_quote,
+    tmux::{TmuxServer, attach_session_command_for_cli},
+};
+
+/// How many times a tab is tried before giving up, since a terminal that
+/// is still launching can refuse the first AppleScript call.
+const TAB_SPAWN_ATTEMPTS: u32 = 3;
+
+/// The delay before the first retry, doubled before each further one.
+const TAB_SPAWN_RETRY_DELAY: Duration = Duration::from_millis(250);
 
+/// Runs `spawn` until it succeeds or `TAB_SPAWN_ATTEMPTS` have failed,
+/// returning the last error.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub(crate) fn spawn_with_retries<T>(
+    session_name: &str,
+    mut spawn: impl FnMut() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let mut delay = TAB_SPAWN_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match spawn() {
+            Ok(t) => return Ok(t),
+            Err(e) if attempt < TAB_SPAWN_ATTEMPTS => {
+                warn!(
+                    "Opening a tab for {} failed (attempt {} of {}), retrying in {}ms: {}",
+                    session_name,
+                    attempt,
+                    TAB_SPAWN_ATTEMPTS,
+                    delay.as_millis(),
+                    e
+                );
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The tab to open for one app.
+pub(crate) struct AppTab<'a> {
+    pub(crate) session_name: &'a str,
+    /// The tab's title, from the app's name and `tab_title_template`.
+    pub(crate) title: &'a str,
+    pub(crate) read_only: bool,
+}
+
+/// The shell command a tab runs to attach to its session, first setting the
+/// terminal's title with an OSC escape for terminals that can't be told the
+/// title directly.
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+pub(crate) fn titled_attach_command(
+    server: &TmuxServer,
+    tab: &AppTab,
+) -> Result<String, Box<dyn Error>> {
+    let attach = attach_session_command_for_cli(server, tab.session_name, tab.read_only)?;
+    let title: String = tab.title.chars().filter(|c| !c.is_control()).collect();
+    Ok(format!(
+        "printf '\\033]0;%s\\007' {}; {}",
+        shell_quote(&title),
+        attach
+    ))
+}
+
+/// Shows each app's tmux session in a terminal tab, attached read-only when
+/// the tab asks for it. Failures are returned for the caller to log rather
+/// than stopping the apps.
 pub(crate) trait TabAdapter {
-    fn open(&mut self, session_name: &str);
-    fn after_all_open(&mut self);
-    fn close(&mut self, session_name: &str);
-    fn after_all_closed(&mut self);
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>>;
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>>;
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>>;
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// Opens a fresh tab for a session whose tab may have been closed by
+    /// hand, closing any tab still mapped to it so it isn't shown twice.
+    /// The tab is opened even if closing the old one fails.
+    fn reopen(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let closed = self.close(tab.session_name);
+        self.open(tab)?;
+        closed
+    }
+
+    /// Forgets the tabs closed by hand since the last check, returning their
+    /// session names. Adapters that can't tell report none.
+    fn closed_tabs(&mut self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
+/// The name of the adapter `choose_tab_adapter` would boot, without
+/// booting it, for `--version`.
 #[cfg(target_os = "macos")]
-pub(crate) fn choose_tab_adapter() -> Result<Option<Box<dyn TabAdapter>>, Box<dyn Error>> {
+pub(crate) fn tab_adapter_name() -> &'static str {
     if iterm_installed() {
-        let ta = ITermTabAdapter::new()?;
+        "iterm"
+    } else if kitty_remote_control_available() {
+        "kitty"
+    } else if wezterm_cli_available() {
+        "wezterm"
+    } else {
+        "terminal"
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn choose_tab_adapter(
+    server: &TmuxServer,
+) -> Result<Option<Box<dyn TabAdapter>>, Box<dyn Error>> {
+    if iterm_installed() {
+        let ta = ITermTabAdapter::new(server)?;
         info!("Booted ITerm adapter.");
         return Ok(Some(Box::new(ta)));
     }
 
-    let ta = OsxTerminalAdapter::new()?;
+    if kitty_remote_control_available() {
+        let ta = KittyTabAdapter::new(server)?;
+        info!("Booted kitty adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+    if wezterm_cli_available() {
+        let ta = WezTermTabAdapter::new(server)?;
+        info!("Booted WezTerm adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+
+    let ta = OsxTerminalAdapter::new(server)?;
     info!("Booted Terminal Adapter");
     Ok(Some(Box::new(ta)))
 }
 
-#[cfg(not(target_os = "macos"))]
-pub(crate) fn choose_tab_adapter() -> Result<Option<Box<dyn TabAdapter>>, Box<dyn Error>> {
+/// The name of the adapter `choose_tab_adapter` would boot, without
+/// booting it, for `--version`.
+#[cfg(target_os = "linux")]
+pub(crate) fn tab_adapter_name() -> &'static str {
+    if kitty_remote_control_available() {
+        "kitty"
+    } else if wezterm_cli_available() {
+        "wezterm"
+    } else if windows_terminal_available() {
+        "windows-terminal"
+    } else if gnome_terminal_installed() {
+        "gnome-terminal"
+    } else {
+        "none"
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn choose_tab_adapter(
+    server: &TmuxServer,
+) -> Result<Option<Box<dyn TabAdapter>>, Box<dyn Error>> {
+    if kitty_remote_control_available() {
+        let ta = KittyTabAdapter::new(server)?;
+        info!("Booted kitty adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+    if wezterm_cli_available() {
+        let ta = WezTermTabAdapter::new(server)?;
+        info!("Booted WezTerm adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+    if windows_terminal_available() {
+        let ta = WindowsTerminalAdapter::new(server)?;
+        info!("Booted Windows Terminal adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+    if gnome_terminal_installed() {
+        let ta = GnomeTerminalAdapter::new(server)?;
+        info!("Booted gnome-terminal adapter.");
+        return Ok(Some(Box::new(ta)));
+    }
+    info!("No adapter available.");
+    Ok(None)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn tab_adapter_name() -> &'static str {
+    "none"
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn choose_tab_adapter(
+    _server: &TmuxServer,
+) -> Result<Option<Box<dyn TabAdapter>>, Box<dyn Error>> {
     info!("No adapter available.");
     Ok(None)
 }
@@ -2,46 +2,139 @@ use std::{collections::HashMap, error::Error};
 
 use osakit::{Script, Value};
 
-use crate::{tabadapter::TabAdapter, tmux::attach_session_command_for_cli};
+use crate::{
+    tabadapter::{AppTab, TabAdapter, spawn_with_retries, titled_attach_command},
+    tmux::TmuxServer,
+};
 
 pub(crate) struct OsxTerminalAdapter {
+    server: TmuxServer,
+    /// The tty of the tab that was focused before any tabs were opened.
+    current_tab: Value,
     terminal_mappings: HashMap<String, Value>,
 }
 
 impl OsxTerminalAdapter {
-    pub(crate) fn new() -> Result<Self, Box<dyn Error>> {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
+        let ct = get_original_tab()?;
         Ok(OsxTerminalAdapter {
+            server: server.clone(),
+            current_tab: ct,
             terminal_mappings: HashMap::new(),
         })
     }
 }
 
 impl TabAdapter for OsxTerminalAdapter {
-    fn open(&mut self, session_name: &str) {
-        let spawn_res = spawn_terminal_tab(session_name);
-        if let Ok(sr) = spawn_res {
-            self.terminal_mappings.insert(session_name.to_owned(), sr);
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let sr = spawn_with_retries(tab.session_name, || spawn_terminal_tab(&self.server, tab))?;
+        self.terminal_mappings
+            .insert(tab.session_name.to_owned(), sr);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        match self.terminal_mappings.remove(session_name) {
+            Some(v) => cleanup_terminal_tab(&v),
+            None => Ok(()),
         }
     }
 
-    fn close(&mut self, session_name: &str) {
-        if let Some(v) = self.terminal_mappings.get(session_name) {
-            let _ = cleanup_terminal_tab(v);
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        refocus_original_tab(&self.current_tab)
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        refocus_original_tab(&self.current_tab)
+    }
+
+    fn closed_tabs(&mut self) -> Vec<String> {
+        let closed: Vec<String> = self
+            .terminal_mappings
+            .iter()
+            .filter(|(_, wid)| !terminal_window_exists(wid))
+            .map(|(session_name, _)| session_name.clone())
+            .collect();
+        for session_name in closed.iter() {
             self.terminal_mappings.remove(session_name);
         }
+        closed
+    }
+}
+
+/// Whether Terminal still has the window. A failed check counts as open.
+fn terminal_window_exists(t: &Value) -> bool {
+    let mut script = Script::new_from_source(
+        osakit::Language::AppleScript,
+        "on window_exists(x)
+            tell application \"Terminal\"
+               return exists window id x
+            end tell
+        end window_exists",
+    );
+    if script.compile().is_err() {
+        return true;
     }
+    !matches!(
+        script.execute_function("window_exists", vec![t.clone()]),
+        Ok(Value::Bool(false))
+    )
+}
 
-    fn after_all_open(&mut self) {
-        //let _ = refocus_original_session(&self.current_session);
+/// Terminal tabs have no id, so the focused tab is remembered by its tty.
+/// Terminal isn't launched if it isn't already running, since the iTerm
+/// adapter keeps this adapter as a fallback.
+fn get_original_tab() -> Result<Value, Box<dyn Error>> {
+    let mut script = Script::new_from_source(
+        osakit::Language::AppleScript,
+        "on get_original_tab()
+            if application \"Terminal\" is not running then
+              return null
+            end if
+            tell application \"Terminal\"
+               if not(exists window 1)
+                 return null
+               end if
+               return tty of selected tab of front window
+            end tell
+         end get_original_tab",
+    );
+    script.compile()?;
+    let r = script.execute_function("get_original_tab", vec![]);
+    if r.is_err() {
+        return Ok(Value::Null);
     }
+    Ok(r.unwrap())
+}
 
-    fn after_all_closed(&mut self) {
-        //let _ = refocus_original_session(&self.current_session);
+fn refocus_original_tab(t: &Value) -> Result<(), Box<dyn Error>> {
+    if t.is_null() {
+        return Ok(());
     }
+    let mut script = Script::new_from_source(
+        osakit::Language::AppleScript,
+        "on focus_original_tab(x)
+            tell application \"Terminal\"
+               	activate
+               	repeat with aWindow in windows
+                  repeat with aTab in tabs of aWindow
+                    if tty of aTab is x then
+                      set selected of aTab to true
+                      set index of aWindow to 1
+                      return
+                    end if
+                  end repeat
+               	end repeat
+            end tell
+        end focus_original_tab",
+    );
+    script.compile()?;
+    script.execute_function("focus_original_tab", vec![t.clone()])?;
+    Ok(())
 }
 
-fn spawn_terminal_tab(session_name: &str) -> Result<Value, Box<dyn Error>> {
-    let cmd_string = attach_session_command_for_cli(session_name)?;
+fn spawn_terminal_tab(server: &TmuxServer, tab: &AppTab) -> Result<Value, Box<dyn Error>> {
+    let cmd_string = titled_attach_command(server, tab)?;
     let cmd_str = osakit::Value::String(cmd_string);
     let mut script = Script::new_from_source(
         osakit::Language::AppleScript,
@@ -82,6 +175,6 @@ fn cleanup_terminal_tab(t: &Value) -> Result<(), Box<dyn Error>> {
         end close_tmux_tab",
     );
     script.compile()?;
-    let _r = script.execute_function("close_tmux_tab", vec![t.clone()]);
+    script.execute_function("close_tmux_tab", vec![t.clone()])?;
     Ok(())
 }
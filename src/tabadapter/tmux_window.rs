@@ -0,0 +1,146 @@
+use std::{collections::HashMap, error::Error};
+
+use tmux_interface::{HasSession, KillWindow, ListWindows, NewSession, NewWindow};
+
+use crate::{
+    tabadapter::{AppTab, TabAdapter},
+    tmux::{TmuxServer, attach_session_command_for_cli, cleanup_session},
+};
+
+/// Surfaces each app as a window in one shared tmux session instead of a
+/// terminal tab, for machines without a GUI terminal.
+pub(crate) struct TmuxWindowAdapter {
+    server: TmuxServer,
+    shared_session: String,
+    created_session: bool,
+    window_ids: HashMap<String, String>,
+}
+
+impl TmuxWindowAdapter {
+    pub(crate) fn new(server: &TmuxServer, shared_session: &str) -> Result<Self, Box<dyn Error>> {
+        let exists = server
+            .tmux(HasSession::new().target_session(shared_session).build())
+            .into_command()
+            .output()?
+            .status
+            .success();
+        if !exists {
+            server
+                .tmux(
+                    NewSession::new()
+                        .detached()
+                        .session_name(shared_session)
+                        .build(),
+                )
+                .status()?;
+        }
+        Ok(TmuxWindowAdapter {
+            server: server.clone(),
+            shared_session: shared_session.to_owned(),
+            created_session: !exists,
+            window_ids: HashMap::new(),
+        })
+    }
+}
+
+impl TabAdapter for TmuxWindowAdapter {
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let window_id = spawn_tmux_window(&self.server, &self.shared_session, tab)?;
+        self.window_ids
+            .insert(tab.session_name.to_owned(), window_id);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        // The window is gone already if its session was killed first.
+        if let Some(window_id) = self.window_ids.remove(session_name) {
+            self.server
+                .tmux(KillWindow::new().target_window(window_id).build())
+                .into_command()
+                .output()?;
+        }
+        Ok(())
+    }
+
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn closed_tabs(&mut self) -> Vec<String> {
+        let Some(open_windows) = list_window_ids(&self.server, &self.shared_session) else {
+            return Vec::new();
+        };
+        let closed: Vec<String> = self
+            .window_ids
+            .iter()
+            .filter(|(_, window_id)| !open_windows.contains(window_id))
+            .map(|(session_name, _)| session_name.clone())
+            .collect();
+        for session_name in closed.iter() {
+            self.window_ids.remove(session_name);
+        }
+        closed
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.created_session {
+            cleanup_session(&self.server, &self.shared_session);
+        }
+        Ok(())
+    }
+}
+
+/// The ids of the shared session's windows, or `None` if tmux couldn't list
+/// them, in which case nothing should be assumed closed.
+fn list_window_ids(server: &TmuxServer, shared_session: &str) -> Option<Vec<String>> {
+    let output = server
+        .tmux(
+            ListWindows::new()
+                .format("#{window_id}")
+                .target_session(shared_session)
+                .build(),
+        )
+        .into_command()
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_owned)
+            .collect(),
+    )
+}
+
+fn spawn_tmux_window(
+    server: &TmuxServer,
+    shared_session: &str,
+    tab: &AppTab,
+) -> Result<String, Box<dyn Error>> {
+    // The attach runs inside tmux, so it must not see the outer client's TMUX,
+    // but it still has to reach the same server socket.
+    let attach = attach_session_command_for_cli(server, tab.session_name, tab.read_only)?;
+    let cmd_string = format!(
+        "socket=\"${{TMUX%%,*}}\"; unset TMUX; {}",
+        attach.replacen("tmux", "tmux -S \"$socket\"", 1)
+    );
+    let output = server
+        .tmux(
+            NewWindow::new()
+                .detached()
+                .print()
+                .format("#{window_id}")
+                .window_name(tab.title)
+                .target_window(format!("{}:", shared_session))
+                .shell_command(cmd_string)
+                .build(),
+        )
+        .into_command()
+        .output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into());
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
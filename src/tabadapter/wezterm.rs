@@ -0,0 +1,70 @@
+use std::{collections::HashMap, error::Error, process::Command};
+
+use crate::{
+    tabadapter::{AppTab, TabAdapter, titled_attach_command},
+    tmux::TmuxServer,
+};
+
+/// Opens one WezTerm tab per app through the `wezterm cli` commands.
+pub(crate) struct WezTermTabAdapter {
+    server: TmuxServer,
+    wezterm_pane_ids: HashMap<String, String>,
+}
+
+impl WezTermTabAdapter {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
+        Ok(WezTermTabAdapter {
+            server: server.clone(),
+            wezterm_pane_ids: HashMap::new(),
+        })
+    }
+}
+
+impl TabAdapter for WezTermTabAdapter {
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let pane_id = spawn_wezterm_tab(&self.server, tab)?;
+        self.wezterm_pane_ids
+            .insert(tab.session_name.to_owned(), pane_id);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        // A pane already closed by hand makes wezterm exit nonzero, which
+        // isn't worth reporting.
+        if let Some(pane_id) = self.wezterm_pane_ids.remove(session_name) {
+            Command::new("wezterm")
+                .args(["cli", "kill-pane", "--pane-id", &pane_id])
+                .output()?;
+        }
+        Ok(())
+    }
+
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Whether a running WezTerm instance answers `wezterm cli`.
+pub(crate) fn wezterm_cli_available() -> bool {
+    Command::new("wezterm")
+        .args(["cli", "list"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+fn spawn_wezterm_tab(server: &TmuxServer, tab: &AppTab) -> Result<String, Box<dyn Error>> {
+    let cmd_string = titled_attach_command(server, tab)?;
+    let output = Command::new("wezterm")
+        .args(["cli", "spawn", "--", "sh", "-c"])
+        .arg(&cmd_string)
+        .output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into());
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
@@ -0,0 +1,79 @@
+use std::{collections::HashMap, env, error::Error, process::Command};
+
+use tmux_interface::DetachClient;
+
+use crate::{
+    tabadapter::{AppTab, TabAdapter},
+    tmux::{TmuxServer, attach_session_command_for_cli},
+};
+
+const WT_EXE: &str = "wt.exe";
+
+/// Opens one Windows Terminal tab per app when running under WSL, each
+/// running a tmux attach back inside this distribution.
+pub(crate) struct WindowsTerminalAdapter {
+    server: TmuxServer,
+    distro: String,
+    tab_titles: HashMap<String, String>,
+}
+
+impl WindowsTerminalAdapter {
+    pub(crate) fn new(server: &TmuxServer) -> Result<Self, Box<dyn Error>> {
+        let distro = env::var("WSL_DISTRO_NAME")?;
+        Ok(WindowsTerminalAdapter {
+            server: server.clone(),
+            distro,
+            tab_titles: HashMap::new(),
+        })
+    }
+}
+
+impl TabAdapter for WindowsTerminalAdapter {
+    fn open(&mut self, tab: &AppTab) -> Result<(), Box<dyn Error>> {
+        let title = spawn_windows_terminal_tab(&self.server, &self.distro, tab)?;
+        self.tab_titles.insert(tab.session_name.to_owned(), title);
+        Ok(())
+    }
+
+    fn close(&mut self, session_name: &str) -> Result<(), Box<dyn Error>> {
+        // wt.exe can't close a tab, but a tab whose command exits closes
+        // itself, so detaching its client is enough.
+        if self.tab_titles.remove(session_name).is_some() {
+            self.server
+                .tmux(DetachClient::new().target_session(session_name).build())
+                .status()?;
+        }
+        Ok(())
+    }
+
+    fn after_all_open(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn after_all_closed(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Whether this is a WSL distribution with Windows Terminal reachable
+/// through interop.
+pub(crate) fn windows_terminal_available() -> bool {
+    // `wt.exe --version` pops up a dialog, so look for it on PATH instead.
+    env::var_os("WSL_DISTRO_NAME").is_some()
+        && env::var_os("PATH")
+            .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(WT_EXE).is_file()))
+}
+
+fn spawn_windows_terminal_tab(
+    server: &TmuxServer,
+    distro: &str,
+    tab: &AppTab,
+) -> Result<String, Box<dyn Error>> {
+    let cmd_string = attach_session_command_for_cli(server, tab.session_name, tab.read_only)?;
+    Command::new(WT_EXE)
+        .args(["-w", "0", "new-tab", "--title", tab.title])
+        .args(["--suppressApplicationTitle", "wsl.exe", "-d", distro])
+        .args(["-e", "sh", "-c", &cmd_string])
+        .status()?;
+    Ok(tab.title.to_owned())
+}
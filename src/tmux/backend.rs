@@ -0,0 +1,80 @@
+use std::{error::Error, path::Path};
+
+use tmux_interface::{KillSession, ListSessions, NewSession, SendKeys};
+
+use crate::tmux::TmuxServer;
+
+/// The tmux calls behind launching, tracking and reclaiming sessions, so
+/// that flow can be exercised in tests without a live tmux server.
+pub(crate) trait TmuxBackend {
+    /// Starts a detached session running `shell_command`. Whether the
+    /// command itself started is only known once the sessions are listed.
+    fn new_session(
+        &self,
+        session_name: &str,
+        start_directory: &Path,
+        shell_command: &str,
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Lists every session as a line formatted with `format`. Nothing is
+    /// listed when no tmux server is running.
+    fn list_sessions(&self, format: &str) -> Result<String, Box<dyn Error>>;
+
+    /// Kills the session, quietly ignoring one that has already closed itself.
+    fn kill_session(&self, session_name: &str);
+
+    /// Sends a key such as `C-c` to the session's pane.
+    fn send_keys(&self, session_name: &str, key: &str);
+}
+
+impl TmuxBackend for TmuxServer {
+    fn new_session(
+        &self,
+        session_name: &str,
+        start_directory: &Path,
+        shell_command: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        self.tmux(new_session_command(
+            session_name,
+            start_directory,
+            shell_command,
+        ))
+        .status()?;
+        Ok(())
+    }
+
+    fn list_sessions(&self, format: &str) -> Result<String, Box<dyn Error>> {
+        let output = self
+            .tmux(ListSessions::new().format(format).build())
+            .into_command()
+            .output()?;
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn kill_session(&self, session_name: &str) {
+        let _ = self
+            .tmux(KillSession::new().target_session(session_name).build())
+            .into_command()
+            .output();
+    }
+
+    fn send_keys(&self, session_name: &str, key: &str) {
+        let _ = self
+            .tmux(SendKeys::new().target_pane(session_name).key(key).build())
+            .status();
+    }
+}
+
+/// The `new-session` command for an app, shared by launching and `--dry-run`.
+pub(crate) fn new_session_command<'a>(
+    session_name: &'a str,
+    start_directory: &Path,
+    shell_command: &'a str,
+) -> tmux_interface::TmuxCommand<'a> {
+    NewSession::new()
+        .detached()
+        .session_name(session_name)
+        .start_directory(start_directory.to_string_lossy().into_owned())
+        .shell_command(shell_command)
+        .build()
+}
@@ -1,25 +1,155 @@
-use std::{error::Error, ffi::OsStr, os::unix::ffi::OsStringExt};
+use std::{
+    error::Error,
+    os::unix::process::ExitStatusExt,
+    process::{Command, ExitStatus},
+    thread,
+    time::Duration,
+};
 
-use tmux_interface::{AttachSession, KillSession};
+use tmux_interface::{AttachSession, CapturePane, DisplayMessage, Tmux, TmuxCommand};
 
-pub(crate) fn cleanup_session(session_name: &str) {
-    let _ = KillSession::new()
-        .target_session(session_name)
-        .build()
-        .into_tmux()
-        .status();
+use crate::{shell::shell_quote, tmux::TmuxBackend};
+
+/// The tmux server devplexer's sessions live on: the default one, or an
+/// isolated one named by `tmux_socket` (`tmux -L`).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TmuxServer {
+    socket_name: Option<String>,
+}
+
+impl TmuxServer {
+    pub(crate) fn new(socket_name: Option<String>) -> Self {
+        TmuxServer { socket_name }
+    }
+
+    /// Wraps a built command to run against this server.
+    pub(crate) fn tmux<'a>(&self, command: TmuxCommand<'a>) -> Tmux<'a> {
+        let tmux = Tmux::new().command(command);
+        match self.socket_name.clone() {
+            Some(socket_name) => tmux.socket_name(socket_name),
+            None => tmux,
+        }
+    }
+}
+
+/// Kills the session, quietly ignoring one that has already closed itself.
+pub(crate) fn cleanup_session(server: &dyn TmuxBackend, session_name: &str) {
+    server.kill_session(session_name);
 }
 
-pub(crate) fn attach_session_command_for_cli(session_name: &str) -> Result<String, Box<dyn Error>> {
-    let cmd = AttachSession::new()
+/// `cmd` as a shell command line, with each argument quoted as needed.
+pub(crate) fn command_line(cmd: &Command) -> String {
+    std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|part| shell_quote(&part.to_string_lossy()))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// The shell command a terminal tab runs to attach to the session, with
+/// tmux's `-r` when the tab should only watch the pane.
+pub(crate) fn attach_session_command_for_cli(
+    server: &TmuxServer,
+    session_name: &str,
+    read_only: bool,
+) -> Result<String, Box<dyn Error>> {
+    let mut attach = AttachSession::new()
         .target_session(session_name)
-        .detach_other()
-        .build()
-        .into_tmux()
-        .into_command();
-    let cmd_args: Vec<&OsStr> = cmd.get_args().collect();
-    let mut encoded_string = cmd.get_program().to_os_string().into_encoded_bytes();
-    encoded_string.extend(" ".as_bytes());
-    encoded_string.extend(cmd_args.join(OsStr::new(" ")).into_vec());
-    Ok(String::from_utf8(encoded_string)?)
+        .detach_other();
+    if read_only {
+        attach = attach.read_only();
+    }
+    let cmd = server.tmux(attach.build()).into_command();
+    if let Some(part) = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .find(|part| part.to_str().is_none())
+    {
+        return Err(format!("tmux argument is not UTF-8: {}", part.to_string_lossy()).into());
+    }
+    // The session name can carry quotes or `;` from the app name, and the
+    // tab runs this through a shell.
+    Ok(command_line(&cmd))
+}
+
+/// The last `lines` non-blank lines left in the session's pane, which only
+/// outlive the process when remain-on-exit is on.
+pub(crate) fn capture_pane_tail(
+    server: &TmuxServer,
+    session_name: &str,
+    lines: usize,
+) -> Option<Vec<String>> {
+    let output = server
+        .tmux(
+            CapturePane::new()
+                .stdout()
+                .join()
+                .start_line(format!("-{}", lines))
+                .target_pane(session_name)
+                .build(),
+        )
+        .into_command()
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let captured = String::from_utf8_lossy(&output.stdout);
+    // tmux's own "Pane is dead (status ...)" footer isn't the app's output.
+    let kept: Vec<&str> = captured
+        .lines()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with("Pane is dead"))
+        .collect();
+    let skip = kept.len().saturating_sub(lines);
+    Some(kept[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// Everything the session's pane has printed that is still in its
+/// scrollback, with wrapped lines joined back together.
+pub(crate) fn capture_pane_history(server: &TmuxServer, session_name: &str) -> Option<String> {
+    let output = server
+        .tmux(
+            CapturePane::new()
+                .stdout()
+                .join()
+                .start_line("-")
+                .target_pane(session_name)
+                .build(),
+        )
+        .into_command()
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Reads the exit status tmux recorded for the session's dead pane, which is
+/// only kept when remain-on-exit is on. tmux may notice the exit slightly
+/// after the process is gone, so this retries briefly.
+pub(crate) fn pane_exit_status(server: &TmuxServer, session_name: &str) -> Option<ExitStatus> {
+    for _attempt in 0..10 {
+        let output = server
+            .tmux(
+                DisplayMessage::new()
+                    .print()
+                    .target_pane(session_name)
+                    .message("#{pane_dead_status}")
+                    .build(),
+            )
+            .into_command()
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        if let Ok(code) = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<i32>()
+        {
+            return Some(ExitStatus::from_raw(code << 8));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    None
 }
@@ -1,23 +1,49 @@
-use std::{collections::HashMap, error::Error, io::BufRead, str::FromStr};
+use std::{collections::HashMap, error::Error, process::Command, str::FromStr};
 
-use log::info;
-use tmux_interface::{ListSessions, NewSession, SendKeys};
+use log::{info, warn};
 
-use crate::{apps::TryIntoWith, config::ProgramSpec};
+use crate::{
+    apps::TryIntoWith,
+    config::ProgramSpec,
+    dotenv::{load_dotenv, merge_env},
+    processes::ProcessIdentity,
+    shell::shell_quote,
+};
 
+mod backend;
 mod commands;
+mod version;
 
+pub(crate) use backend::*;
 pub(crate) use commands::*;
+pub(crate) use version::*;
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub(crate) enum ProgramStartErrors {
     ProgramDiedEarlyError(String),
+    /// tmux reported no usable pane pid, so the command exited at once.
+    ExitedBeforePid(String),
+    NoProgramStartedError(Vec<String>),
 }
 
 impl std::fmt::Display for ProgramStartErrors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("{:?}", self))
+        match self {
+            ProgramStartErrors::ProgramDiedEarlyError(session_name) => write!(
+                f,
+                "tmux session {} ended before it could be tracked, check the app's command",
+                session_name
+            ),
+            ProgramStartErrors::ExitedBeforePid(session_name) => write!(
+                f,
+                "the command in tmux session {} exited before its pid could be captured, check the app's command for typos",
+                session_name
+            ),
+            ProgramStartErrors::NoProgramStartedError(apps) => {
+                write!(f, "no app could be started: {}", apps.join(", "))
+            }
+        }
     }
 }
 
@@ -30,6 +56,10 @@ pub(crate) struct RunningTmuxProgram {
     pub(crate) session_name: String,
     pub(crate) tmux_pid: sysinfo::Pid,
     pub(crate) program_pid: sysinfo::Pid,
+    /// The program as it was at launch, for telling it apart from a later
+    /// process that reuses its pid.
+    pub(crate) program_identity: ProcessIdentity,
+    pub(crate) server: TmuxServer,
 }
 
 #[derive(Clone)]
@@ -39,101 +69,583 @@ pub(crate) struct StartedProgram {
     pub(crate) session_name: String,
 }
 
+/// Settings shared by every app launched into tmux.
+#[derive(Clone)]
+pub(crate) struct LaunchContext {
+    pub(crate) namespace: String,
+    pub(crate) remain_on_exit: bool,
+    pub(crate) server: TmuxServer,
+}
+
 #[derive(Clone)]
 pub(crate) struct RunningProgram {
     pub(crate) spec: ProgramSpec,
     pub(crate) program: RunningTmuxProgram,
 }
 
-impl TryIntoWith<RunningProgram, &HashMap<String, (sysinfo::Pid, sysinfo::Pid)>>
-    for &StartedProgram
-{
+/// Each session's tmux server pid and, when tmux reported one, its pane pid.
+type PidMapping = HashMap<String, (sysinfo::Pid, Option<sysinfo::Pid>)>;
+
+impl TryIntoWith<RunningProgram, (&TmuxServer, &PidMapping)> for &StartedProgram {
     fn try_into_with(
         &self,
-        ctx: &HashMap<String, (sysinfo::Pid, sysinfo::Pid)>,
+        ctx: (&TmuxServer, &PidMapping),
     ) -> Result<RunningProgram, Box<dyn Error>> {
+        let (server, pid_mapping) = ctx;
         let sn = self.session_name.clone();
-        let pm = ctx
+        let (tmux_pid, pane_pid) = pid_mapping
             .get(&sn)
             .ok_or_else(|| ProgramStartErrors::ProgramDiedEarlyError(sn.clone()))?;
+        let Some(pane_pid) = pane_pid else {
+            warn!(
+                "{}: command exited before a pid could be captured, check it for typos",
+                self.spec.name
+            );
+            return Err(Box::new(ProgramStartErrors::ExitedBeforePid(sn)));
+        };
         let rp = RunningProgram {
             spec: self.spec.clone(),
             program: RunningTmuxProgram {
                 command: self.command.clone(),
                 session_name: sn,
-                tmux_pid: pm.0,
-                program_pid: pm.1,
+                tmux_pid: *tmux_pid,
+                program_pid: *pane_pid,
+                program_identity: ProcessIdentity::capture(*pane_pid),
+                server: server.clone(),
             },
         };
         Ok(rp)
     }
 }
 
+/// A started program whose session ended before it could be tracked, and why.
+pub(crate) type DiedEarly = (ProgramSpec, Box<dyn Error>);
+
+/// Looks up the pids of freshly started sessions. Programs whose session
+/// is already gone are returned separately with why, so the rest can run.
 pub(crate) fn convert_pids(
-    started_commands: &Vec<StartedProgram>,
-) -> Result<Vec<RunningProgram>, Box<dyn Error>> {
+    server: &TmuxServer,
+    started_commands: &[StartedProgram],
+) -> Result<(Vec<RunningProgram>, Vec<DiedEarly>), Box<dyn Error>> {
+    let pid_mapping = session_pids(server)?;
+    Ok(track_started(server, &pid_mapping, started_commands))
+}
+
+/// Matches started programs to the sessions tmux listed, splitting off the
+/// ones whose session is gone or never had a pane pid.
+fn track_started(
+    server: &TmuxServer,
+    pid_mapping: &PidMapping,
+    started_commands: &[StartedProgram],
+) -> (Vec<RunningProgram>, Vec<DiedEarly>) {
     let mut running_programs: Vec<RunningProgram> = Vec::new();
-    let mut cs = ListSessions::new()
-        .format("#{session_name}: #{pid}: #{pane_pid}")
-        .build()
-        .into_tmux()
-        .into_command();
-    let output = cs.output()?;
-    let entries = output.stdout.lines();
-    let mut pid_mapping: HashMap<String, (sysinfo::Pid, sysinfo::Pid)> = HashMap::new();
-    for entry in entries {
-        if let Some((name, pids)) = entry?.split_once(": ") {
-            if let Some((tmux_pid, pane_pid)) = pids.split_once(": ") {
-                let pid_t = u32::from_str(tmux_pid)?;
-                let pid_c = u32::from_str(pane_pid)?;
-                let upid = sysinfo::Pid::from_u32(pid_t);
-                let cpid = sysinfo::Pid::from_u32(pid_c);
-                pid_mapping.insert(name.to_owned(), (upid, cpid));
-            }
+    let mut died = Vec::new();
+    for sc in started_commands.iter() {
+        match sc.try_into_with((server, pid_mapping)) {
+            Ok(rp) => running_programs.push(rp),
+            Err(e) => died.push((sc.spec.clone(), e)),
         }
     }
-    for sc in started_commands.iter() {
-        let rp = sc.try_into_with(&pid_mapping)?;
-        running_programs.push(rp);
+    (running_programs, died)
+}
+
+/// Every session's tmux server pid and pane pid.
+fn session_pids(backend: &dyn TmuxBackend) -> Result<PidMapping, Box<dyn Error>> {
+    let listing = backend.list_sessions("#{session_name}: #{pid}: #{pane_pid}")?;
+    let mut pid_mapping: PidMapping = HashMap::new();
+    for entry in listing.lines() {
+        let Some((name, pids)) = entry.split_once(": ") else {
+            continue;
+        };
+        // A pane whose command exited at once can report an empty pane pid.
+        let (tmux_pid, pane_pid) = pids
+            .split_once(": ")
+            .unwrap_or((pids.trim_end_matches(':'), ""));
+        let upid = sysinfo::Pid::from_u32(u32::from_str(tmux_pid)?);
+        pid_mapping.insert(name.to_owned(), (upid, parse_pane_pid(pane_pid)));
     }
-    Ok(running_programs)
+    Ok(pid_mapping)
+}
+
+/// A pane pid as tmux prints it, or `None` when it is empty or `0`.
+fn parse_pane_pid(pane_pid: &str) -> Option<sysinfo::Pid> {
+    u32::from_str(pane_pid.trim())
+        .ok()
+        .filter(|pid| *pid != 0)
+        .map(sysinfo::Pid::from_u32)
+}
+
+/// The form an app name takes in its session name: tmux rejects `.` and `:`
+/// in session names and whitespace breaks targeting, so these become `-`.
+pub(crate) fn sanitize_session_name(app_name: &str) -> String {
+    app_name
+        .chars()
+        .map(|c| {
+            if c == '.' || c == ':' || c.is_whitespace() {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect()
 }
 
-pub(crate) fn send_interrupt(session_name: &str) {
-    let _ = SendKeys::new()
-        .target_pane(session_name)
-        .key("C-c")
-        .build()
-        .into_tmux()
-        .status();
+/// Whether a session name is one devplexer would create for an app in `namespace`.
+fn is_namespace_session(session_name: &str, namespace: &str) -> bool {
+    session_name
+        .strip_prefix(namespace)
+        .is_some_and(|rest| rest.starts_with('-'))
 }
 
-impl TryIntoWith<StartedProgram, &str> for &ProgramSpec {
-    fn try_into_with(&self, ctx: &str) -> Result<StartedProgram, Box<dyn Error>> {
-        start_command(ctx, self)
+/// Kills sessions left behind in `namespace` by a previous run that didn't
+/// shut down cleanly, returning their names.
+pub(crate) fn reclaim_sessions(
+    server: &dyn TmuxBackend,
+    namespace: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    // With no tmux server running this lists nothing, so nothing is reclaimed.
+    let listing = server.list_sessions("#{session_name}")?;
+    let mut reclaimed = Vec::new();
+    for name in listing.lines() {
+        if is_namespace_session(name, namespace) {
+            cleanup_session(server, name);
+            reclaimed.push(name.to_owned());
+        }
     }
+    Ok(reclaimed)
 }
 
-fn start_command(
-    session_name: &str,
+pub(crate) fn send_interrupt(server: &dyn TmuxBackend, session_name: &str) {
+    server.send_keys(session_name, "C-c");
+}
+
+impl TryIntoWith<StartedProgram, &LaunchContext> for &ProgramSpec {
+    fn try_into_with(&self, ctx: &LaunchContext) -> Result<StartedProgram, Box<dyn Error>> {
+        start_command(&ctx.server, ctx, self)
+    }
+}
+
+/// Builds the shell command run inside the app's session: the optional
+/// remain-on-exit prefix, then the app's environment exports, then its
+/// command, all handed to the app's `shell` with `-c` when it has one.
+fn session_shell_command(
+    ctx: &LaunchContext,
     p_spec: &ProgramSpec,
-) -> Result<StartedProgram, Box<dyn Error>> {
-    let s_name = session_name.to_owned() + "-" + &p_spec.name;
+    s_name: &str,
+    env: &[(String, String)],
+) -> String {
+    let mut shell_command = String::new();
+    if ctx.remain_on_exit && p_spec.keeps_pane() {
+        // Quotes and `;` in app names survive into the session name, so it
+        // is quoted like any other argument.
+        shell_command += &format!(
+            "tmux set-window-option -t {} remain-on-exit on; ",
+            shell_quote(s_name)
+        );
+    }
+    for (k, v) in env.iter() {
+        shell_command += &format!("export {}={}; ", k, shell_quote(v));
+    }
+    shell_command += &p_spec.command.to_shell_string();
+    match p_spec.shell.as_deref() {
+        Some(shell) => format!("{} -c {}", shell_quote(shell), shell_quote(&shell_command)),
+        None => shell_command,
+    }
+}
 
-    let command_with_remain =
-        format!("tmux set-option -t {} remain-on-exit on; ", s_name) + &p_spec.command;
+/// The app's session name and the shell command its session runs.
+fn prepare_session(
+    ctx: &LaunchContext,
+    p_spec: &ProgramSpec,
+) -> Result<StartedProgram, Box<dyn Error>> {
+    let sanitized = sanitize_session_name(&p_spec.name);
+    if sanitized != p_spec.name {
+        info!(
+            "App name {} contains characters tmux rejects, using {} in its session name",
+            p_spec.name, sanitized
+        );
+    }
+    let s_name = ctx.namespace.to_owned() + "-" + &sanitized;
 
-    info!("Starting Session for {}", p_spec.name);
-    let s_cmd = NewSession::new()
-        .detached()
-        .session_name(&s_name)
-        .start_directory(p_spec.working_directory.as_os_str().to_string_lossy())
-        .shell_command(command_with_remain.clone());
-    let tmux = s_cmd.build().into_tmux();
-    let _estatus = tmux.status()?;
+    let env = match &p_spec.env_file {
+        Some(env_file) => merge_env(load_dotenv(env_file)?, &p_spec.env),
+        None => p_spec.env.clone(),
+    };
+    let command_with_remain = session_shell_command(ctx, p_spec, &s_name, &env);
     Ok(StartedProgram {
         spec: p_spec.clone(),
         command: command_with_remain,
         session_name: s_name,
     })
 }
+
+/// The `new-session` command that starts the app's session, without running
+/// it, along with the program it starts.
+pub(crate) fn session_command(
+    ctx: &LaunchContext,
+    p_spec: &ProgramSpec,
+) -> Result<(StartedProgram, Command), Box<dyn Error>> {
+    let started = prepare_session(ctx, p_spec)?;
+    let cmd = ctx
+        .server
+        .tmux(new_session_command(
+            &started.session_name,
+            &p_spec.working_directory,
+            &started.command,
+        ))
+        .into_command();
+    Ok((started, cmd))
+}
+
+fn start_command(
+    backend: &dyn TmuxBackend,
+    ctx: &LaunchContext,
+    p_spec: &ProgramSpec,
+) -> Result<StartedProgram, Box<dyn Error>> {
+    let started = prepare_session(ctx, p_spec)?;
+    info!("Starting Session for {}", p_spec.name);
+    backend.new_session(
+        &started.session_name,
+        &p_spec.working_directory,
+        &started.command,
+    )?;
+    Ok(started)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, error::Error, path::Path};
+
+    use tmux_interface::KillSession;
+
+    use crate::{
+        config::{ProgramCommand, ProgramSpec},
+        tmux::{
+            LaunchContext, StartedProgram, TmuxBackend, TmuxServer, attach_session_command_for_cli,
+            convert_pids, is_namespace_session, parse_pane_pid, reclaim_sessions,
+            sanitize_session_name, send_interrupt, session_pids, session_shell_command,
+            start_command, track_started,
+        },
+    };
+
+    /// Records what would have been sent to tmux and answers session
+    /// listings with a canned one.
+    #[derive(Default)]
+    struct FakeTmux {
+        listing: String,
+        created: RefCell<Vec<(String, String, String)>>,
+        killed: RefCell<Vec<String>>,
+        keys: RefCell<Vec<(String, String)>>,
+    }
+
+    impl TmuxBackend for FakeTmux {
+        fn new_session(
+            &self,
+            session_name: &str,
+            start_directory: &Path,
+            shell_command: &str,
+        ) -> Result<(), Box<dyn Error>> {
+            self.created.borrow_mut().push((
+                session_name.to_owned(),
+                start_directory.to_string_lossy().into_owned(),
+                shell_command.to_owned(),
+            ));
+            Ok(())
+        }
+
+        fn list_sessions(&self, _format: &str) -> Result<String, Box<dyn Error>> {
+            Ok(self.listing.clone())
+        }
+
+        fn kill_session(&self, session_name: &str) {
+            self.killed.borrow_mut().push(session_name.to_owned());
+        }
+
+        fn send_keys(&self, session_name: &str, key: &str) {
+            self.keys
+                .borrow_mut()
+                .push((session_name.to_owned(), key.to_owned()));
+        }
+    }
+
+    fn started(name: &str) -> StartedProgram {
+        StartedProgram {
+            spec: ProgramSpec {
+                name: name.to_owned(),
+                ..Default::default()
+            },
+            command: "true".to_owned(),
+            session_name: format!("ns-{}", name),
+        }
+    }
+
+    #[test]
+    fn test_start_command_creates_session() {
+        let fake = FakeTmux::default();
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: false,
+            server: TmuxServer::default(),
+        };
+        let spec = ProgramSpec {
+            name: "api.v2".to_owned(),
+            command: ProgramCommand::Shell("rails s".to_owned()),
+            working_directory: "/srv/api".into(),
+            ..Default::default()
+        };
+        let started = start_command(&fake, &ctx, &spec).unwrap();
+        assert_eq!(started.session_name, "ns-api-v2");
+        assert_eq!(started.command, "rails s");
+        assert_eq!(
+            *fake.created.borrow(),
+            vec![(
+                "ns-api-v2".to_owned(),
+                "/srv/api".to_owned(),
+                "rails s".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_session_pids_tracks_running_and_died_early() {
+        let fake = FakeTmux {
+            listing: "ns-web: 100: 200\nns-typo: 100: 0\nns-gone: 100: \nnot a session\n"
+                .to_owned(),
+            ..Default::default()
+        };
+        let pids = session_pids(&fake).unwrap();
+        assert_eq!(pids.len(), 3);
+        assert_eq!(
+            pids["ns-web"],
+            (
+                sysinfo::Pid::from_u32(100),
+                Some(sysinfo::Pid::from_u32(200))
+            )
+        );
+        assert_eq!(pids["ns-typo"].1, None);
+        assert_eq!(pids["ns-gone"].1, None);
+
+        let server = TmuxServer::default();
+        let (running, died) = track_started(
+            &server,
+            &pids,
+            &[started("web"), started("typo"), started("missing")],
+        );
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].program.session_name, "ns-web");
+        assert_eq!(running[0].program.program_pid, sysinfo::Pid::from_u32(200));
+        let died: Vec<(String, String)> = died
+            .iter()
+            .map(|(spec, e)| (spec.name.clone(), e.to_string()))
+            .collect();
+        assert_eq!(died[0].0, "typo");
+        assert!(died[0].1.contains("ns-typo"));
+        assert_eq!(
+            died[1],
+            (
+                "missing".to_owned(),
+                "tmux session ns-missing ended before it could be tracked, check the app's command"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_reclaim_sessions_and_interrupt() {
+        let fake = FakeTmux {
+            listing: "ns-web\nns\nnsx-web\nother-web\nns-worker\n".to_owned(),
+            ..Default::default()
+        };
+        let reclaimed = reclaim_sessions(&fake, "ns").unwrap();
+        assert_eq!(reclaimed, vec!["ns-web", "ns-worker"]);
+        assert_eq!(*fake.killed.borrow(), vec!["ns-web", "ns-worker"]);
+        send_interrupt(&fake, "ns-web");
+        assert_eq!(
+            *fake.keys.borrow(),
+            vec![("ns-web".to_owned(), "C-c".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_convert_pids_reports_sessions_that_died() {
+        // Nothing runs on this socket, so the session is already gone.
+        let server = TmuxServer::new(Some("devplexer-test-died-early".to_owned()));
+        let started = StartedProgram {
+            spec: ProgramSpec {
+                name: "typo".to_owned(),
+                ..Default::default()
+            },
+            command: "nosuchcommand".to_owned(),
+            session_name: "ns-typo".to_owned(),
+        };
+        let (running, died) = convert_pids(&server, &[started]).unwrap();
+        assert!(running.is_empty());
+        assert_eq!(died.len(), 1);
+        assert_eq!(died[0].0.name, "typo");
+        assert_eq!(
+            died[0].1.to_string(),
+            "tmux session ns-typo ended before it could be tracked, check the app's command"
+        );
+    }
+
+    #[test]
+    fn test_parse_pane_pid() {
+        assert_eq!(parse_pane_pid("1234"), Some(sysinfo::Pid::from_u32(1234)));
+        assert_eq!(parse_pane_pid("0"), None);
+        assert_eq!(parse_pane_pid(""), None);
+    }
+
+    #[test]
+    fn test_attach_command_read_only() {
+        let server = TmuxServer::default();
+        let read_only = attach_session_command_for_cli(&server, "ns-web", true).unwrap();
+        let interactive = attach_session_command_for_cli(&server, "ns-web", false).unwrap();
+        let args = |cmd: &str| cmd.split(' ').map(str::to_owned).collect::<Vec<String>>();
+        assert!(args(&read_only).contains(&"-r".to_owned()));
+        assert!(!args(&interactive).contains(&"-r".to_owned()));
+        assert!(read_only.ends_with("-t ns-web"));
+        assert!(interactive.ends_with("-t ns-web"));
+    }
+
+    #[test]
+    fn test_attach_command_quotes_session_name() {
+        let attach =
+            attach_session_command_for_cli(&TmuxServer::default(), "ns-it's;web", false).unwrap();
+        assert_eq!(attach, r#"tmux attach -d -t 'ns-it'\''s;web'"#);
+    }
+
+    #[test]
+    fn test_socket_name_in_commands() {
+        let isolated = TmuxServer::new(Some("devplexer".to_owned()));
+        let args = |cmd: std::process::Command| -> Vec<String> {
+            cmd.get_args()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect()
+        };
+        let kill = isolated
+            .tmux(KillSession::new().target_session("ns-web").build())
+            .into_command();
+        assert_eq!(
+            args(kill),
+            vec!["-L", "devplexer", "kill-session", "-t", "ns-web"]
+        );
+        let default_kill = TmuxServer::default()
+            .tmux(KillSession::new().target_session("ns-web").build())
+            .into_command();
+        assert_eq!(args(default_kill), vec!["kill-session", "-t", "ns-web"]);
+        let attach = attach_session_command_for_cli(&isolated, "ns-web", false).unwrap();
+        assert_eq!(attach, "tmux -L devplexer attach -d -t ns-web");
+    }
+
+    #[test]
+    fn test_sanitize_session_name() {
+        assert_eq!(sanitize_session_name("web"), "web");
+        assert_eq!(sanitize_session_name("api.v2"), "api-v2");
+        assert_eq!(
+            sanitize_session_name("db:primary replica\tone"),
+            "db-primary-replica-one"
+        );
+    }
+
+    #[test]
+    fn test_is_namespace_session() {
+        assert!(is_namespace_session("ns-web", "ns"));
+        assert!(is_namespace_session("ns-web-worker", "ns"));
+        assert!(!is_namespace_session("ns", "ns"));
+        assert!(!is_namespace_session("nsx-web", "ns"));
+        assert!(!is_namespace_session("other-web", "ns"));
+    }
+
+    #[test]
+    fn test_session_shell_command_with_shell() {
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: false,
+            server: TmuxServer::default(),
+        };
+        let spec = ProgramSpec {
+            name: "web".to_owned(),
+            command: ProgramCommand::Shell("echo 'hi' && [[ -n $HOME ]]".to_owned()),
+            env: vec![("GREETING".to_owned(), "hello world".to_owned())],
+            shell: Some("bash".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            session_shell_command(&ctx, &spec, "ns-web", &spec.env),
+            r#"bash -c 'export GREETING='\''hello world'\''; echo '\''hi'\'' && [[ -n $HOME ]]'"#
+        );
+        let custom = ProgramSpec {
+            shell: Some("/opt/my shells/zsh".to_owned()),
+            env: Vec::new(),
+            ..spec
+        };
+        assert_eq!(
+            session_shell_command(&ctx, &custom, "ns-web", &custom.env),
+            r#"'/opt/my shells/zsh' -c 'echo '\''hi'\'' && [[ -n $HOME ]]'"#
+        );
+    }
+
+    #[test]
+    fn test_session_shell_command_with_environment() {
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: true,
+            server: TmuxServer::default(),
+        };
+        let spec = ProgramSpec {
+            name: "web".to_owned(),
+            command: ProgramCommand::Shell("rails s".to_owned()),
+            env: vec![
+                ("RAILS_ENV".to_owned(), "development".to_owned()),
+                ("GREETING".to_owned(), "hello world".to_owned()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            session_shell_command(&ctx, &spec, "ns-web", &spec.env),
+            "tmux set-window-option -t ns-web remain-on-exit on; export RAILS_ENV=development; export GREETING='hello world'; rails s"
+        );
+    }
+
+    #[test]
+    fn test_session_shell_command_quotes_session_name() {
+        let fake = FakeTmux::default();
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: true,
+            server: TmuxServer::default(),
+        };
+        let spec = ProgramSpec {
+            name: "it's;web".to_owned(),
+            command: ProgramCommand::Shell("rails s".to_owned()),
+            working_directory: "/srv/my app; rm -rf".into(),
+            ..Default::default()
+        };
+        let started = start_command(&fake, &ctx, &spec).unwrap();
+        assert_eq!(started.session_name, "ns-it's;web");
+        assert_eq!(
+            started.command,
+            r#"tmux set-window-option -t 'ns-it'\''s;web' remain-on-exit on; rails s"#
+        );
+        assert_eq!(fake.created.borrow()[0].1, "/srv/my app; rm -rf");
+    }
+
+    #[test]
+    fn test_session_shell_command_without_keep_alive() {
+        let ctx = LaunchContext {
+            namespace: "ns".to_owned(),
+            remain_on_exit: true,
+            server: TmuxServer::default(),
+        };
+        let spec = ProgramSpec {
+            name: "migrate".to_owned(),
+            command: ProgramCommand::Shell("rake db:migrate".to_owned()),
+            keep_alive: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(
+            session_shell_command(&ctx, &spec, "ns-migrate", &spec.env),
+            "rake db:migrate"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use tmux_interface::Tmux;
+
+/// Oldest tmux release devplexer supports for its remain-on-exit prefix and
+/// pane pids. Releases before 3.0 expect window options such as
+/// `remain-on-exit` to be set with `set-window-option`, so the prefix uses
+/// that form, which newer releases still accept.
+pub(crate) const REMAIN_ON_EXIT_MIN_VERSION: TmuxVersion = TmuxVersion { major: 2, minor: 0 };
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct TmuxVersion {
+    pub(crate) major: u32,
+    pub(crate) minor: u32,
+}
+
+impl std::fmt::Display for TmuxVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Parses the output of `tmux -V`, e.g. `tmux 3.3a` or `tmux next-3.4`.
+pub(crate) fn parse_tmux_version(version_output: &str) -> Option<TmuxVersion> {
+    let version = version_output.trim().strip_prefix("tmux ")?;
+    let version = version.strip_prefix("next-").unwrap_or(version);
+    let (major, rest) = version.split_once('.')?;
+    let minor: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    Some(TmuxVersion {
+        major: major.parse().ok()?,
+        minor: minor.parse().ok()?,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TmuxUnavailableError {
+    NotRunnable(String),
+}
+
+impl std::fmt::Display for TmuxUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TmuxUnavailableError::NotRunnable(reason) => write!(
+                f,
+                "could not run `tmux -V` ({}). devplexer runs every app in tmux, which must be installed and on your PATH; tmux {} or newer is needed for remain-on-exit and pane_pid.",
+                reason, REMAIN_ON_EXIT_MIN_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TmuxUnavailableError {}
+
+/// Checks that tmux can be run, returning its version when it can be parsed.
+pub(crate) fn ensure_available() -> Result<Option<TmuxVersion>, TmuxUnavailableError> {
+    let output = Tmux::new()
+        .version()
+        .into_command()
+        .output()
+        .map_err(|e| TmuxUnavailableError::NotRunnable(e.to_string()))?;
+    if !output.status.success() {
+        return Err(TmuxUnavailableError::NotRunnable(output.status.to_string()));
+    }
+    Ok(parse_tmux_version(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// An undetectable version (e.g. a `tmux master` build) is assumed to be recent.
+pub(crate) fn supports_remain_on_exit(version: Option<TmuxVersion>) -> bool {
+    version
+        .map(|v| v >= REMAIN_ON_EXIT_MIN_VERSION)
+        .unwrap_or(true)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tmux::version::{
+        TmuxUnavailableError, TmuxVersion, parse_tmux_version, supports_remain_on_exit,
+    };
+
+    #[test]
+    fn test_parse_tmux_version() {
+        assert_eq!(
+            parse_tmux_version("tmux 3.3a\n"),
+            Some(TmuxVersion { major: 3, minor: 3 })
+        );
+        assert_eq!(
+            parse_tmux_version("tmux next-3.4"),
+            Some(TmuxVersion { major: 3, minor: 4 })
+        );
+        assert_eq!(
+            parse_tmux_version("tmux 2.0\n"),
+            Some(TmuxVersion { major: 2, minor: 0 })
+        );
+        assert_eq!(
+            parse_tmux_version("tmux 10.12-rc"),
+            Some(TmuxVersion {
+                major: 10,
+                minor: 12
+            })
+        );
+        assert_eq!(parse_tmux_version(""), None);
+        assert_eq!(parse_tmux_version("tmux master"), None);
+        assert_eq!(parse_tmux_version("bash: tmux: not found"), None);
+    }
+
+    #[test]
+    fn test_supports_remain_on_exit() {
+        assert!(supports_remain_on_exit(Some(TmuxVersion {
+            major: 3,
+            minor: 2
+        })));
+        assert!(!supports_remain_on_exit(Some(TmuxVersion {
+            major: 1,
+            minor: 8
+        })));
+    }
+
+    #[test]
+    fn test_unknown_version_assumed_recent() {
+        // `tmux -V` prints e.g. `tmux master` for builds from source, which
+        // are newer than any release; refusing them would lock those users out.
+        assert!(supports_remain_on_exit(None));
+        assert!(supports_remain_on_exit(parse_tmux_version("tmux master")));
+    }
+
+    #[test]
+    fn test_unavailable_error_names_minimum_version() {
+        let message =
+            TmuxUnavailableError::NotRunnable("No such file or directory".to_owned()).to_string();
+        assert!(message.contains("No such file or directory"));
+        assert!(message.contains("tmux 2.0 or newer"));
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::apps::AppEvent;
+
+/// How often the sampler refreshes CPU and memory figures. CPU usage is
+/// measured between two refreshes, so the first sample of an app reads 0%.
+const USAGE_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ResourceUsage {
+    pub(crate) cpu_percent: f32,
+    pub(crate) memory_bytes: u64,
+}
+
+/// Sums usage over each tracked pid and all of its descendants, since the
+/// tracked pid is usually the shell wrapping the app's real process.
+fn sample_usage(system: &System, tracked: &[Pid]) -> Vec<(Pid, ResourceUsage)> {
+    let parents: HashMap<Pid, Pid> = system
+        .processes()
+        .iter()
+        .filter_map(|(pid, p)| p.parent().map(|parent| (*pid, parent)))
+        .collect();
+    let root_of = |mut pid: Pid| -> Option<Pid> {
+        loop {
+            if tracked.contains(&pid) {
+                return Some(pid);
+            }
+            pid = *parents.get(&pid)?;
+        }
+    };
+    let mut usage: HashMap<Pid, ResourceUsage> = HashMap::new();
+    for (pid, process) in system.processes().iter() {
+        if let Some(root) = root_of(*pid) {
+            let entry = usage.entry(root).or_default();
+            entry.cpu_percent += process.cpu_usage();
+            entry.memory_bytes += process.memory();
+        }
+    }
+    usage.into_iter().collect()
+}
+
+/// Periodically samples the tracked pids with one long-lived `System`,
+/// sending the results as `AppEvent::UsageSampled` until `die_chan` fires.
+pub(crate) fn start_usage_sampler(
+    out_chan: &Sender<AppEvent>,
+    tracked: Arc<Mutex<Vec<Pid>>>,
+    die_chan: Receiver<()>,
+) -> JoinHandle<()> {
+    let tx = out_chan.clone();
+    thread::spawn(move || {
+        let mut system = System::new();
+        while let Err(RecvTimeoutError::Timeout) = die_chan.recv_timeout(USAGE_REFRESH_INTERVAL) {
+            system.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing().with_cpu().with_memory(),
+            );
+            let tracked_pids = tracked.lock().unwrap().clone();
+            let _ = tx.send(AppEvent::UsageSampled(sample_usage(&system, &tracked_pids)));
+        }
+    })
+}
+
+pub(crate) fn format_memory(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "K", "M", "G"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}{}", bytes, UNITS[0])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sysinfo::{ProcessesToUpdate, System};
+
+    use crate::usage::{format_memory, sample_usage};
+
+    #[test]
+    fn test_format_memory() {
+        assert_eq!(format_memory(512), "512B");
+        assert_eq!(format_memory(1536), "1.5K");
+        assert_eq!(format_memory(300 * 1024 * 1024), "300.0M");
+    }
+
+    #[test]
+    fn test_samples_tracked_process() {
+        let mut system = System::new();
+        system.refresh_processes(ProcessesToUpdate::All, true);
+        let me = sysinfo::get_current_pid().unwrap();
+        let usage = sample_usage(&system, &[me]);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].0, me);
+        assert!(usage[0].1.memory_bytes > 0);
+    }
+}